@@ -1,9 +1,12 @@
 use syntax::{SyntaxElement, SyntaxKind, SyntaxNode, SyntaxToken};
+use text_size::TextRange;
 
 #[derive(Debug)]
 pub enum Expr {
     Binary(BinaryExpr),
+    Call(FunctionCall),
     Literal(LiteralExpr),
+    Object(ObjectExpr),
     Paren(ParenExpr),
     PathIdent(PathIdentExpr),
     Unary(UnaryExpr),
@@ -15,7 +18,9 @@ impl Expr {
     pub fn cast(node: SyntaxNode) -> Option<Self> {
         let result = match node.kind() {
             SyntaxKind::InfixExpr => Self::Binary(BinaryExpr(node)),
+            SyntaxKind::FunctionCall => Self::Call(FunctionCall(node)),
             SyntaxKind::Literal => Self::Literal(LiteralExpr(node)),
+            SyntaxKind::ObjectExpr => Self::Object(ObjectExpr(node)),
             SyntaxKind::ParenExpr => Self::Paren(ParenExpr(node)),
             SyntaxKind::PrefixExpr => Self::Unary(UnaryExpr(node)),
             SyntaxKind::VariableRef => Self::VariableRef(VariableRef(node)),
@@ -26,6 +31,35 @@ impl Expr {
 
         Some(result)
     }
+
+    pub fn syntax(&self) -> &SyntaxNode {
+        match self {
+            Expr::Binary(expr) => &expr.0,
+            Expr::Call(expr) => &expr.0,
+            Expr::Literal(expr) => &expr.0,
+            Expr::Object(expr) => &expr.0,
+            Expr::Paren(expr) => &expr.0,
+            Expr::PathIdent(expr) => &expr.0,
+            Expr::Unary(expr) => &expr.0,
+            Expr::VariableRef(expr) => &expr.0,
+            Expr::VariableDef(expr) => &expr.0,
+        }
+    }
+
+    /// Range of the expression in the source, excluding any surrounding trivia
+    pub fn range(&self) -> TextRange {
+        let node = self.syntax();
+        let mut tokens = node
+            .descendants_with_tokens()
+            .filter_map(SyntaxElement::into_token)
+            .filter(|token| !token.kind().is_trivia());
+
+        match (tokens.next(), tokens.last()) {
+            (Some(first), Some(last)) => first.text_range().cover(last.text_range()),
+            (Some(only), None) => only.text_range(),
+            _ => node.text_range(),
+        }
+    }
 }
 
 #[derive(Debug)]
@@ -74,6 +108,20 @@ impl BinaryExpr {
     }
 }
 
+#[derive(Debug)]
+pub struct FunctionCall(SyntaxNode);
+
+impl FunctionCall {
+    /// Name of the function, including the preceding `$`
+    pub fn name(&self) -> String {
+        self.0.first_token().unwrap().text().into()
+    }
+
+    pub fn args(&self) -> impl Iterator<Item = Expr> {
+        self.0.children().filter_map(Expr::cast)
+    }
+}
+
 #[derive(Debug)]
 pub struct LiteralExpr(SyntaxNode);
 
@@ -83,6 +131,31 @@ impl LiteralExpr {
     }
 }
 
+#[derive(Debug)]
+pub struct ObjectExpr(SyntaxNode);
+
+impl ObjectExpr {
+    pub fn pairs(&self) -> impl Iterator<Item = ObjectPair> {
+        self.0
+            .children()
+            .filter(|node| node.kind() == SyntaxKind::ObjectPair)
+            .map(ObjectPair)
+    }
+}
+
+#[derive(Debug)]
+pub struct ObjectPair(SyntaxNode);
+
+impl ObjectPair {
+    pub fn key(&self) -> Option<Expr> {
+        self.0.children().find_map(Expr::cast)
+    }
+
+    pub fn value(&self) -> Option<Expr> {
+        self.0.children().filter_map(Expr::cast).nth(1)
+    }
+}
+
 #[derive(Debug)]
 pub struct ParenExpr(SyntaxNode);
 
@@ -90,6 +163,11 @@ impl ParenExpr {
     pub fn expr(&self) -> Option<Expr> {
         self.0.children().find_map(Expr::cast)
     }
+
+    /// Each expression of a block, which are separated by `;`
+    pub fn exprs(&self) -> impl Iterator<Item = Expr> {
+        self.0.children().filter_map(Expr::cast)
+    }
 }
 
 #[derive(Debug)]
@@ -99,6 +177,13 @@ impl PathIdentExpr {
     pub fn name(&self) -> String {
         self.0.first_token().unwrap().text().into()
     }
+
+    /// Whether the step is followed by `[]`, keeping its result as an array
+    pub fn keep_array(&self) -> bool {
+        self.0
+            .children()
+            .any(|node| node.kind() == SyntaxKind::KeepArray)
+    }
 }
 
 #[derive(Debug)]
@@ -1,10 +1,12 @@
-use core::parse;
+use core::{parse, Environment, Expression};
+use serde_json::Value;
 use std::io::{self, Write};
 
 fn main() -> io::Result<()> {
     let stdin = io::stdin();
     let mut stdout = io::stdout();
 
+    let env = Environment::new();
     let mut input = String::new();
 
     loop {
@@ -17,20 +19,13 @@ fn main() -> io::Result<()> {
         let parse = parse(trimmed_input);
         println!("{}", parse.debug_tree());
 
-        let root = ast::Root::cast(parse.syntax()).unwrap();
-
-        let var_defs = root.expr().map(|expr| {
-            if let ast::Expr::VariableDef(var_def) = expr {
-                Some(var_def.value())
-            } else {
-                None
+        if let Ok(expr) = Expression::compile(trimmed_input) {
+            match expr.evaluate(&Value::Null, &env) {
+                Ok(Some(result)) => println!("{}", result),
+                Ok(None) => println!("undefined"),
+                Err(error) => println!("{}", error),
             }
-        });
-
-        dbg!(var_defs);
-
-        let (_, hir) = hir::lower(root);
-        dbg!(hir);
+        }
 
         input.clear();
     }
@@ -2,7 +2,7 @@ use serde_json::Value;
 
 use crate::{evaluate::EvaluationResult, value::JSONataValue};
 
-use super::BuiltIns;
+use super::{arg, BuiltIns};
 
 #[cfg(test)]
 mod tests;
@@ -25,45 +25,38 @@ impl BuiltIns {
     /// function                    --> false
     ///
     /// **Signature**: `$boolean(arg)`
-    pub fn boolean(arg: &JSONataValue) -> EvaluationResult {
-        Ok(Some(match arg {
+    pub(crate) fn boolean(args: &[Option<JSONataValue>]) -> EvaluationResult {
+        Ok(arg(args, 0).map(|arg| match arg {
             JSONataValue::Value(val) => BuiltIns::boolean_coerce(val).into(),
             JSONataValue::Function(_) => false.into(),
         }))
     }
 
+    /// Casts a possibly undefined value to a Boolean, e.g. the result of a
+    /// predicate function, where undefined casts to false
+    pub(super) fn to_boolean(value: Option<&JSONataValue>) -> bool {
+        match value {
+            Some(JSONataValue::Value(val)) => BuiltIns::boolean_coerce(val),
+            Some(JSONataValue::Function(_)) | None => false,
+        }
+    }
+
     fn boolean_coerce(val: &Value) -> bool {
         match val {
             Value::Null => false,
             Value::Bool(b) => *b,
-            Value::Number(n) => {
-                if n.is_u64() {
-                    n.as_u64() == Some(0_u64)
-                } else if n.is_i64() {
-                    n.as_i64() == Some(0_i64)
-                } else if n.is_f64() {
-                    n.as_f64() == Some(0.0)
-                } else {
-                    false
-                }
-            }
-            Value::String(s) => s.is_empty(),
-            Value::Array(v) => {
-                if v.is_empty() {
-                    false
-                } else {
-                    v.iter().all(|val| BuiltIns::boolean_coerce(val))
-                }
-            }
-            Value::Object(o) => o.is_empty(),
+            Value::Number(n) => n.as_f64() != Some(0.0),
+            Value::String(s) => !s.is_empty(),
+            Value::Array(v) => v.iter().any(BuiltIns::boolean_coerce),
+            Value::Object(o) => !o.is_empty(),
         }
     }
 
     /// Returns Boolean NOT on the argument. arg is first cast to a boolean
     ///
     /// **Signature**: `$not(arg)`
-    pub fn not(arg: &JSONataValue) -> EvaluationResult {
-        Ok(Some(match arg {
+    pub(crate) fn not(args: &[Option<JSONataValue>]) -> EvaluationResult {
+        Ok(arg(args, 0).map(|arg| match arg {
             JSONataValue::Value(val) => (!BuiltIns::boolean_coerce(val)).into(),
             JSONataValue::Function(_) => true.into(),
         }))
@@ -74,10 +67,7 @@ impl BuiltIns {
     /// (e.g. a path to a non-existent field reference).
     ///
     /// **Signature**: `$exists(arg)`
-    pub fn exists(arg: &Option<JSONataValue>) -> EvaluationResult {
-        Ok(Some(match arg {
-            Some(_) => true.into(),
-            None => false.into(),
-        }))
+    pub(crate) fn exists(args: &[Option<JSONataValue>]) -> EvaluationResult {
+        Ok(Some(arg(args, 0).is_some().into()))
     }
 }
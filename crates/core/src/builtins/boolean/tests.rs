@@ -0,0 +1,49 @@
+use serde_json::json;
+
+use crate::{builtins::BuiltIns, tests::make_val};
+
+#[test]
+fn boolean() {
+    let cases = vec![
+        (json!(true), true),
+        (json!(false), false),
+        (json!(""), false),
+        (json!("false"), true),
+        (json!(0), false),
+        (json!(-0.0), false),
+        (json!(0.1), true),
+        (json!(null), false),
+        (json!([]), false),
+        (json!([0, ""]), false),
+        (json!([0, "a"]), true),
+        (json!({}), false),
+        (json!({ "a": null }), true),
+    ];
+    for (input, expected) in cases {
+        let actual = BuiltIns::boolean(&[Some(make_val(input))]);
+        assert_eq!(actual, Ok(Some(expected.into())));
+    }
+    assert_eq!(BuiltIns::boolean(&[None]), Ok(None));
+}
+
+#[test]
+fn not() {
+    assert_eq!(
+        BuiltIns::not(&[Some(make_val(json!("")))]),
+        Ok(Some(true.into()))
+    );
+    assert_eq!(
+        BuiltIns::not(&[Some(make_val(json!([1])))]),
+        Ok(Some(false.into()))
+    );
+    assert_eq!(BuiltIns::not(&[None]), Ok(None));
+}
+
+#[test]
+fn exists() {
+    assert_eq!(
+        BuiltIns::exists(&[Some(make_val(json!(null)))]),
+        Ok(Some(true.into()))
+    );
+    assert_eq!(BuiltIns::exists(&[None]), Ok(Some(false.into())));
+}
@@ -0,0 +1,208 @@
+//! Conversions between milliseconds since the Unix Epoch and the proleptic
+//! Gregorian calendar in UTC, as used by JavaScript dates
+
+pub(super) const MILLIS_PER_DAY: i64 = 86_400_000;
+
+pub(super) const MONTHS: [&str; 12] = [
+    "January",
+    "February",
+    "March",
+    "April",
+    "May",
+    "June",
+    "July",
+    "August",
+    "September",
+    "October",
+    "November",
+    "December",
+];
+
+/// Indexed by the ISO day of the week, where Monday is 1
+pub(super) const DAYS: [&str; 8] = [
+    "",
+    "Monday",
+    "Tuesday",
+    "Wednesday",
+    "Thursday",
+    "Friday",
+    "Saturday",
+    "Sunday",
+];
+
+/// The number of days since 1970-01-01 of a date, where `month` and `day` start
+/// at 1 and may overflow into the following months and years
+pub(super) fn days_from_civil(year: i64, month: i64, day: i64) -> i64 {
+    // normalize the month first, e.g. month 13 is January of the next year
+    let year = year + (month - 1).div_euclid(12);
+    let month = (month - 1).rem_euclid(12) + 1;
+
+    // http://howardhinnant.github.io/date_algorithms.html#days_from_civil
+    let y = if month <= 2 { year - 1 } else { year };
+    let era = y.div_euclid(400);
+    let year_of_era = y - era * 400;
+    let month_from_march = (month + 9) % 12;
+    let day_of_year = (153 * month_from_march + 2) / 5;
+    let day_of_era = year_of_era * 365 + year_of_era / 4 - year_of_era / 100 + day_of_year;
+    era * 146_097 + day_of_era - 719_468 + day - 1
+}
+
+/// The year, month and day of the month of a number of days since 1970-01-01
+pub(super) fn civil_from_days(days: i64) -> (i64, i64, i64) {
+    // http://howardhinnant.github.io/date_algorithms.html#civil_from_days
+    let z = days + 719_468;
+    let era = z.div_euclid(146_097);
+    let day_of_era = z - era * 146_097;
+    let year_of_era =
+        (day_of_era - day_of_era / 1460 + day_of_era / 36524 - day_of_era / 146_096) / 365;
+    let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+    let month_from_march = (5 * day_of_year + 2) / 153;
+    let day = day_of_year - (153 * month_from_march + 2) / 5 + 1;
+    let month = if month_from_march < 10 {
+        month_from_march + 3
+    } else {
+        month_from_march - 9
+    };
+    let year = year_of_era + era * 400 + if month <= 2 { 1 } else { 0 };
+    (year, month, day)
+}
+
+/// The ISO day of the week of a number of days since 1970-01-01, from Monday
+/// as 1 to Sunday as 7
+pub(super) fn iso_weekday(days: i64) -> i64 {
+    // 1970-01-01 was a Thursday
+    (days + 3).rem_euclid(7) + 1
+}
+
+/// The first day of the first week of a year or month starting at `first`, which
+/// as in ISO 8601 is the week containing its first Thursday
+pub(super) fn start_of_first_week(first: i64) -> i64 {
+    let weekday = iso_weekday(first);
+    if weekday > 4 {
+        first + 8 - weekday
+    } else {
+        first - (weekday - 1)
+    }
+}
+
+/// A point in time broken down into its date and time components in UTC
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub(super) struct DateTime {
+    /// Days since 1970-01-01
+    pub days: i64,
+    pub year: i64,
+    pub month: i64,
+    pub day: i64,
+    pub hour: i64,
+    pub minute: i64,
+    pub second: i64,
+    pub millisecond: i64,
+}
+
+impl DateTime {
+    pub fn from_millis(millis: i64) -> Self {
+        let days = millis.div_euclid(MILLIS_PER_DAY);
+        let time = millis.rem_euclid(MILLIS_PER_DAY);
+        let (year, month, day) = civil_from_days(days);
+        Self {
+            days,
+            year,
+            month,
+            day,
+            hour: time / 3_600_000,
+            minute: time / 60_000 % 60,
+            second: time / 1000 % 60,
+            millisecond: time % 1000,
+        }
+    }
+
+    /// The value of an integer component of a date picture, e.g. `Y` for the
+    /// year or `F` for the day of the week
+    pub fn component(&self, component: char) -> Option<i64> {
+        let value = match component {
+            'Y' => self.year,
+            'M' => self.month,
+            'D' => self.day,
+            'd' => self.days - days_from_civil(self.year, 1, 1) + 1,
+            'F' => iso_weekday(self.days),
+            'W' => self.week_date(false).2,
+            'X' => self.week_date(false).0,
+            'w' => self.week_date(true).2,
+            'x' => self.week_date(true).1,
+            'H' => self.hour,
+            'h' => match self.hour % 12 {
+                0 => 12,
+                hour => hour,
+            },
+            'P' => (self.hour >= 12) as i64,
+            'm' => self.minute,
+            's' => self.second,
+            'f' => self.millisecond,
+            _ => return None,
+        };
+        Some(value)
+    }
+
+    /// The year and month of the year or month (if `monthly`) that the week
+    /// of this date belongs to, and the number of that week within it
+    fn week_date(&self, monthly: bool) -> (i64, i64, i64) {
+        let period = |offset: i64| {
+            if monthly {
+                let month = self.month - 1 + offset;
+                (self.year + month.div_euclid(12), month.rem_euclid(12) + 1)
+            } else {
+                (self.year + offset, 1)
+            }
+        };
+        let start =
+            |(year, month): (i64, i64)| start_of_first_week(days_from_civil(year, month, 1));
+
+        let (current, next) = (period(0), period(1));
+        if self.days >= start(next) {
+            return (next.0, next.1, 1);
+        }
+        let period = if self.days < start(current) {
+            period(-1)
+        } else {
+            current
+        };
+        (period.0, period.1, (self.days - start(period)) / 7 + 1)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn civil_round_trip() {
+        let cases = vec![
+            (0, (1970, 1, 1)),
+            (-1, (1969, 12, 31)),
+            (17477, (2017, 11, 7)),
+            (11016, (2000, 2, 29)),
+            (-719_162, (1, 1, 1)),
+        ];
+        for (days, (year, month, day)) in cases {
+            assert_eq!(civil_from_days(days), (year, month, day));
+            assert_eq!(days_from_civil(year, month, day), days);
+        }
+        assert_eq!(days_from_civil(2017, 13, 1), days_from_civil(2018, 1, 1));
+    }
+
+    #[test]
+    fn iso_weeks() {
+        // 2017-01-01 was a Sunday, in the last week of 2016
+        let date = DateTime::from_millis(days_from_civil(2017, 1, 1) * MILLIS_PER_DAY);
+        assert_eq!(date.component('F'), Some(7));
+        assert_eq!(date.component('W'), Some(52));
+        assert_eq!(date.component('X'), Some(2016));
+
+        // 2018-12-31 was a Monday, in the first week of 2019
+        let date = DateTime::from_millis(days_from_civil(2018, 12, 31) * MILLIS_PER_DAY);
+        assert_eq!(date.component('W'), Some(1));
+        assert_eq!(date.component('X'), Some(2019));
+        assert_eq!(date.component('w'), Some(1));
+        assert_eq!(date.component('x'), Some(1));
+    }
+}
@@ -1,4 +1,13 @@
-use super::BuiltIns;
+use crate::{
+    evaluate::{EvaluationError, EvaluationResult},
+    value::{number::JSONataNumber, JSONataValue},
+};
+
+use self::picture::{parse_iso8601, DatePicture, Offset};
+use super::{number_arg, string_arg, BuiltIns};
+
+mod calendar;
+mod picture;
 
 #[cfg(test)]
 mod tests;
@@ -16,8 +25,11 @@ impl BuiltIns {
     /// ```
     /// $now() => "2017-05-15T15:12:59.152Z"
     /// ```
-    pub(crate) fn now(picture: Option<String>, timezone: Option<String>) {
-        todo!()
+    /// **Signature**: `$now([picture [, timezone]])`
+    ///
+    /// `timestamp` is the time at which the evaluation started.
+    pub(crate) fn now(timestamp: i64, args: &[Option<JSONataValue>]) -> EvaluationResult {
+        BuiltIns::format_millis(timestamp as f64, string_arg(args, 0), string_arg(args, 1))
     }
 
     /// Returns the number of milliseconds since the Unix Epoch (1 January, 1970 UTC) as a number.
@@ -29,8 +41,11 @@ impl BuiltIns {
     /// ```
     /// $millis() => 1502700297574
     /// ```
-    pub(crate) fn millis() {
-        todo!()
+    /// **Signature**: `$millis()`
+    ///
+    /// `timestamp` is the time at which the evaluation started.
+    pub(crate) fn millis(timestamp: i64, _args: &[Option<JSONataValue>]) -> EvaluationResult {
+        Ok(Some(JSONataNumber::from(timestamp).into()))
     }
 
     /// Convert the number representing milliseconds since the Unix Epoch (1 January, 1970 UTC) to
@@ -48,7 +63,7 @@ impl BuiltIns {
     /// If the optional timezone string is supplied, then the formatted timestamp will be in that timezone. The
     /// timezone string should be in the format "±HHMM", where ± is either the plus or minus sign and HHMM is the
     /// offset in hours and minutes from UTC. Positive offset for timezones east of UTC, negative offset for
-    /// timezones west of UTC.
+    /// timezones west of UTC. A timezone string which is not in this format is ignored.
     ///
     /// ## Examples
     ///
@@ -57,8 +72,13 @@ impl BuiltIns {
     /// $fromMillis(1510067557121, '[M01]/[D01]/[Y0001] [h#1]:[m01][P]') => "11/07/2017 3:12pm"
     /// $fromMillis(1510067557121, '[H01]:[m01]:[s01] [z]', '-0500') => "10:12:37 GMT-05:00"
     /// ```
-    pub(crate) fn from_millis() {
-        todo!()
+    /// **Signature**: `$fromMillis(number [, picture [, timezone]])`
+    pub(crate) fn from_millis(args: &[Option<JSONataValue>]) -> EvaluationResult {
+        let millis = match number_arg(args, 0) {
+            Some(millis) => millis.as_f64(),
+            None => return Ok(None),
+        };
+        BuiltIns::format_millis(millis, string_arg(args, 1), string_arg(args, 2))
     }
 
     /// Convert a timestamp string to the number of milliseconds since the Unix Epoch (1 January, 1970 UTC) as a number.
@@ -76,7 +96,50 @@ impl BuiltIns {
     /// ```
     /// $toMillis("2017-11-07T15:07:54.972Z") => 1510067274972
     /// ```
-    pub(crate) fn to_millis() {
-        todo!()
+    /// **Signature**: `$toMillis(timestamp [, picture])`
+    ///
+    /// Date components missing from the picture default to those of `now`, the
+    /// time at which the evaluation started.
+    pub(crate) fn to_millis(now: i64, args: &[Option<JSONataValue>]) -> EvaluationResult {
+        let timestamp = match string_arg(args, 0) {
+            Some(timestamp) => timestamp,
+            None => return Ok(None),
+        };
+        let millis = match string_arg(args, 1) {
+            Some(picture) => DatePicture::from_picture(picture)?.parse(timestamp, now)?,
+            None => Some(
+                parse_iso8601(timestamp)
+                    .ok_or_else(|| EvaluationError::InvalidTimestamp(timestamp.into()))?,
+            ),
+        };
+        Ok(millis.map(|millis| JSONataNumber::from(millis).into()))
+    }
+
+    fn format_millis(
+        millis: f64,
+        picture: Option<&str>,
+        timezone: Option<&str>,
+    ) -> EvaluationResult {
+        let millis = timestamp(millis)?;
+        let picture = match picture {
+            Some(picture) => DatePicture::from_picture(picture)?,
+            None => DatePicture::iso8601(),
+        };
+        let offset = timezone.and_then(Offset::parse).unwrap_or_default();
+        Ok(Some(picture.format(millis, offset)?.into()))
+    }
+}
+
+/// Largest number of milliseconds either side of the Unix Epoch that a
+/// JavaScript `Date` can hold, about 273,790 years
+const MAX_MILLIS: f64 = 8.64e15;
+
+/// Truncates `millis` to a whole number of milliseconds, raising D1001 for
+/// a time outside of the range of a JavaScript `Date`
+fn timestamp(millis: f64) -> Result<i64, EvaluationError> {
+    if millis.abs() <= MAX_MILLIS {
+        Ok(millis.trunc() as i64)
+    } else {
+        Err(EvaluationError::NumberOutOfRange(millis))
     }
 }
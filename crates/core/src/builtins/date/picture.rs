@@ -0,0 +1,578 @@
+use std::{
+    collections::{hash_map::Entry, HashMap},
+    sync::OnceLock,
+};
+
+use regex::Regex;
+
+use crate::{builtins::numeric::integer_format::IntegerFormat, evaluate::EvaluationError};
+
+use super::calendar::{
+    days_from_civil, start_of_first_week, DateTime, DAYS, MILLIS_PER_DAY, MONTHS,
+};
+
+/// The picture of the ISO 8601 format used when no picture is given
+const ISO_8601: &str = "[Y0001]-[M01]-[D01]T[H01]:[m01]:[s01].[f001][Z01:01t]";
+
+/// The components which are formatted as integers
+const INTEGER_COMPONENTS: &str = "YMDdFWwXxHhmsf";
+
+/// A timezone offset from UTC
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub(super) struct Offset {
+    minutes: i64,
+}
+
+impl Offset {
+    /// Parses an offset in the format `±HHMM`, e.g. `-0500`
+    pub fn parse(s: &str) -> Option<Self> {
+        let (sign, digits) = match s.strip_prefix('-') {
+            Some(digits) => (-1, digits),
+            None => (1, s.strip_prefix('+').unwrap_or(s)),
+        };
+        if digits.is_empty() || digits.len() > 4 || !digits.chars().all(|c| c.is_ascii_digit()) {
+            return None;
+        }
+        let hhmm: i64 = digits.parse().ok()?;
+        Some(Self {
+            minutes: sign * (hhmm / 100 * 60 + hhmm % 100),
+        })
+    }
+
+    fn millis(&self) -> i64 {
+        self.minutes * 60_000
+    }
+
+    /// The offset as a signed number of the form HHMM, e.g. -530 for -05:30
+    fn hhmm(&self) -> i64 {
+        self.minutes.signum() * (self.minutes.abs() / 60 * 100 + self.minutes.abs() % 60)
+    }
+}
+
+/// The case of the names of months, days and `am`/`pm`
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Names {
+    Upper,
+    Lower,
+    Title,
+}
+
+/// A variable marker of a date picture, e.g. `[M01]` or `[FNn,*-3]`
+#[derive(Debug, Clone)]
+struct Marker {
+    component: char,
+    presentation2: Option<char>,
+    names: Option<Names>,
+    integer: Option<IntegerFormat>,
+    max_width: Option<usize>,
+    /// The number of digits of an integer component of a fixed width, to
+    /// which a year is truncated, e.g. 2 for `[Y01]`
+    digits: Option<usize>,
+}
+
+#[derive(Debug, Clone)]
+enum Part {
+    Literal(String),
+    Marker(Marker),
+}
+
+/// A picture string of `$fromMillis` and `$toMillis`, see the
+/// [date picture](https://www.w3.org/TR/xpath-functions-31/#date-picture-string)
+/// syntax of fn:format-dateTime
+#[derive(Debug, Clone)]
+pub(super) struct DatePicture {
+    parts: Vec<Part>,
+}
+
+impl DatePicture {
+    pub fn iso8601() -> Self {
+        DatePicture::from_picture(ISO_8601).unwrap() // a valid picture
+    }
+
+    pub fn from_picture(picture: &str) -> Result<Self, EvaluationError> {
+        let chars: Vec<char> = picture.chars().collect();
+        let mut parts = Vec::new();
+        let mut literal = String::new();
+
+        let mut i = 0;
+        while i < chars.len() {
+            match chars[i] {
+                // doubled brackets are literal brackets
+                '[' if chars.get(i + 1) == Some(&'[') => {
+                    literal.push('[');
+                    i += 2;
+                }
+                ']' if chars.get(i + 1) == Some(&']') => {
+                    literal.push(']');
+                    i += 2;
+                }
+                '[' => {
+                    let end = chars[i..]
+                        .iter()
+                        .position(|c| *c == ']')
+                        .ok_or(EvaluationError::DatePictureUnterminated)?;
+                    // whitespace within a variable marker is ignored
+                    let marker: String = chars[i + 1..i + end]
+                        .iter()
+                        .filter(|c| !c.is_whitespace())
+                        .collect();
+                    if !literal.is_empty() {
+                        parts.push(Part::Literal(std::mem::take(&mut literal)));
+                    }
+                    parts.push(Part::Marker(Marker::from_marker(&marker)?));
+                    i += end + 1;
+                }
+                c => {
+                    literal.push(c);
+                    i += 1;
+                }
+            }
+        }
+        if !literal.is_empty() {
+            parts.push(Part::Literal(literal));
+        }
+
+        Ok(Self { parts })
+    }
+
+    /// Formats a number of milliseconds since the Unix Epoch in the given timezone
+    pub fn format(&self, millis: i64, offset: Offset) -> Result<String, EvaluationError> {
+        let date = DateTime::from_millis(millis + offset.millis());
+        let mut formatted = String::new();
+        for part in &self.parts {
+            match part {
+                Part::Literal(literal) => formatted.push_str(literal),
+                Part::Marker(marker) => formatted.push_str(&marker.format(&date, offset)?),
+            }
+        }
+        Ok(formatted)
+    }
+
+    /// Parses a timestamp formatted with this picture to a number of milliseconds
+    /// since the Unix Epoch, or `None` if it does not match the picture
+    ///
+    /// Components more significant than those in the picture are taken from
+    /// `now`, and less significant ones default to their lowest value, e.g. a
+    /// picture of only a month and day is midnight of that day in the current year.
+    pub fn parse(&self, s: &str, now: i64) -> Result<Option<i64>, EvaluationError> {
+        let mut regex = String::from("(?i)^");
+        let mut markers = Vec::new();
+        for part in &self.parts {
+            match part {
+                Part::Literal(literal) => regex.push_str(&regex::escape(literal)),
+                Part::Marker(marker) if "CE".contains(marker.component) => regex.push_str("ISO"),
+                Part::Marker(marker) => {
+                    regex.push('(');
+                    regex.push_str(&marker.regex());
+                    regex.push(')');
+                    markers.push(marker);
+                }
+            }
+        }
+        regex.push('$');
+
+        // e.g. a very large width modifier exceeds the size limit of a regex
+        let regex = match Regex::new(&regex) {
+            Ok(regex) => regex,
+            Err(_) => return Ok(None),
+        };
+        let captures = match regex.captures(s) {
+            Some(captures) => captures,
+            None => return Ok(None),
+        };
+        let mut components = HashMap::new();
+        for (marker, capture) in markers.iter().zip(captures.iter().skip(1)) {
+            if let Some(value) = capture.and_then(|capture| marker.parse(capture.as_str())) {
+                components.insert(marker.component, value);
+            }
+        }
+        if components.is_empty() {
+            return Ok(None);
+        }
+
+        // the date is given by one of {Y, M, D}, {Y, d}, {X, x, w, F} or {X, W, F},
+        // and the time by one of {H, m, s, f} or {P, h, m, s, f}
+        let is_type = |candidates: &str, set: &str| {
+            let present: Vec<char> = candidates
+                .chars()
+                .filter(|c| components.contains_key(c))
+                .collect();
+            !present.is_empty() && present.iter().all(|c| set.contains(*c))
+        };
+        let date_a = is_type("YXMxWwdD", "YMD");
+        let date_b = !date_a && is_type("YXMxWwdD", "Yd");
+        let date_c = is_type("YXMxWwdD", "Xxw");
+        let date_d = !date_c && is_type("YXMxWwdD", "XW");
+        let time_b = !is_type("PHhmsf", "Hmsf") && is_type("PHhmsf", "Phmsf");
+
+        let date_components = if date_b {
+            "Yd"
+        } else if date_c {
+            "XxwF"
+        } else if date_d {
+            "XWF"
+        } else {
+            "YMD"
+        };
+        let time_components = if time_b { "Phmsf" } else { "Hmsf" };
+
+        // default the components before the given ones to now, and those after to
+        // their lowest value, but there must not be a gap between given components
+        let now = DateTime::from_millis(now);
+        let mut started = false;
+        let mut ended = false;
+        for component in date_components.chars().chain(time_components.chars()) {
+            match components.entry(component) {
+                Entry::Occupied(_) if ended => {
+                    return Err(EvaluationError::DatePictureMissingComponents);
+                }
+                Entry::Occupied(_) => started = true,
+                Entry::Vacant(entry) if started => {
+                    entry.insert(if "MDdxwWF".contains(component) { 1 } else { 0 });
+                    ended = true;
+                }
+                Entry::Vacant(entry) => {
+                    entry.insert(now.component(component).unwrap_or(0));
+                }
+            }
+        }
+
+        let get = |component: char| components.get(&component).copied().unwrap_or(0);
+        let days = if date_b {
+            days_from_civil(get('Y'), 1, 1) + get('d') - 1
+        } else if date_c {
+            start_of_first_week(days_from_civil(get('X'), get('x'), 1))
+                + (get('w') - 1) * 7
+                + get('F')
+                - 1
+        } else if date_d {
+            start_of_first_week(days_from_civil(get('X'), 1, 1)) + (get('W') - 1) * 7 + get('F') - 1
+        } else {
+            days_from_civil(get('Y'), get('M'), get('D'))
+        };
+        let hour = if time_b {
+            get('h') % 12 + get('P') * 12
+        } else {
+            get('H')
+        };
+
+        let mut millis = days * MILLIS_PER_DAY
+            + hour * 3_600_000
+            + get('m') * 60_000
+            + get('s') * 1000
+            + get('f');
+        if let Some(offset) = components.get(&'Z').or_else(|| components.get(&'z')) {
+            millis -= offset * 60_000;
+        }
+        Ok(Some(millis))
+    }
+}
+
+impl Marker {
+    fn from_marker(marker: &str) -> Result<Self, EvaluationError> {
+        let component = marker
+            .chars()
+            .next()
+            .ok_or_else(|| EvaluationError::UnknownDateComponent(String::new()))?;
+        let rest = &marker[component.len_utf8()..];
+
+        // the width modifier follows the last comma, e.g. `,2-3` or `,*-3`
+        let (presentation, width) = match rest.rfind(',') {
+            Some(comma) => (&rest[..comma], Some(&rest[comma + 1..])),
+            None => (rest, None),
+        };
+        let (min_width, max_width) = match width {
+            Some(width) => {
+                let parse_width = |width: &str| width.parse::<usize>().ok();
+                match width.split_once('-') {
+                    Some((min, max)) => (parse_width(min), parse_width(max)),
+                    None => (parse_width(width), None),
+                }
+            }
+            None => (None, None),
+        };
+
+        let default = match component {
+            'Y' | 'M' | 'D' | 'd' | 'W' | 'w' | 'X' | 'x' | 'H' | 'h' | 'f' => "1",
+            'm' | 's' => "01",
+            'Z' | 'z' => "01:01",
+            'F' | 'P' | 'C' | 'E' => "n",
+            _ => return Err(EvaluationError::UnknownDateComponent(component.into())),
+        };
+
+        // a second presentation modifier of `a`, `t`, `c` or `o` may follow the first
+        let (presentation1, presentation2) = match presentation.chars().last() {
+            Some(last) if presentation.chars().count() > 1 && "atco".contains(last) => {
+                (&presentation[..presentation.len() - 1], Some(last))
+            }
+            _ if presentation.is_empty() => (default, None),
+            _ => (presentation, None),
+        };
+
+        let names = if presentation1.starts_with('n') {
+            Some(Names::Lower)
+        } else if presentation1.starts_with("Nn") {
+            Some(Names::Title)
+        } else if presentation1.starts_with('N') {
+            Some(Names::Upper)
+        } else {
+            None
+        };
+        if names.is_some() && INTEGER_COMPONENTS.contains(component) && !"MxF".contains(component) {
+            return Err(EvaluationError::NameModifierNotApplicable(component.into()));
+        }
+
+        let mut integer = None;
+        let mut digits = None;
+        if names.is_none() && INTEGER_COMPONENTS.contains(component) {
+            let picture = match presentation2 {
+                Some(modifier) => format!("{};{}", presentation1, modifier),
+                None => presentation1.to_string(),
+            };
+            let mut format = IntegerFormat::from_picture(&picture)?;
+            if let Some(min_width) = min_width {
+                if format.mandatory_digits() < min_width {
+                    format.set_mandatory_digits(min_width);
+                }
+            }
+            if let Some(max_width) = max_width {
+                digits = Some(max_width);
+                if "YMD".contains(component) {
+                    format.set_mandatory_digits(max_width);
+                }
+            } else {
+                let width = format.mandatory_digits() + format.optional_digits();
+                if width >= 2 {
+                    digits = Some(width);
+                }
+            }
+            integer = Some(format);
+        } else if component == 'Z' || component == 'z' {
+            integer = Some(IntegerFormat::from_picture(presentation1)?);
+        }
+
+        Ok(Self {
+            component,
+            presentation2,
+            names,
+            integer,
+            max_width,
+            digits,
+        })
+    }
+
+    fn names(&self) -> &'static [&'static str] {
+        if self.component == 'F' {
+            &DAYS[1..]
+        } else {
+            &MONTHS
+        }
+    }
+
+    /// The name of a month or day, truncated to the maximum width
+    fn name(&self, name: &str) -> String {
+        let name = match self.names {
+            Some(Names::Upper) => name.to_uppercase(),
+            Some(Names::Lower) => name.to_lowercase(),
+            _ => name.to_string(),
+        };
+        match self.max_width {
+            Some(max_width) => name.chars().take(max_width).collect(),
+            None => name,
+        }
+    }
+
+    fn format(&self, date: &DateTime, offset: Offset) -> Result<String, EvaluationError> {
+        match self.component {
+            'Z' | 'z' => self.format_timezone(offset),
+            'P' => {
+                let am_pm = if date.hour >= 12 { "pm" } else { "am" };
+                if self.names == Some(Names::Upper) {
+                    Ok(am_pm.to_uppercase())
+                } else {
+                    Ok(am_pm.into())
+                }
+            }
+            'C' | 'E' => Ok("ISO".into()),
+            component => {
+                let mut value = date.component(component).unwrap_or(0);
+                match &self.integer {
+                    Some(format) => {
+                        if let (Some(digits), 'Y') = (self.digits, component) {
+                            value %= 10_i64.pow(digits.min(18) as u32);
+                        }
+                        format.format(value.into())
+                    }
+                    None => Ok(self.name(self.names()[value as usize - 1])),
+                }
+            }
+        }
+    }
+
+    fn format_timezone(&self, offset: Offset) -> Result<String, EvaluationError> {
+        let format = match &self.integer {
+            Some(format) => format,
+            None => return Ok(String::new()),
+        };
+        let hhmm = offset.hhmm();
+        let (hours, minutes) = (hhmm.abs() / 100, hhmm.abs() % 100);
+
+        let mut formatted = if format.regular_separator().is_some() {
+            format.format(hhmm.abs().into())?
+        } else {
+            match format.mandatory_digits() {
+                1 | 2 if minutes == 0 => format.format(hours.into())?,
+                1 | 2 => format!("{}:{:02}", format.format(hours.into())?, minutes),
+                3 | 4 => format.format(hhmm.abs().into())?,
+                _ => return Err(EvaluationError::TimezoneTooManyDigits),
+            }
+        };
+        formatted.insert(0, if hhmm < 0 { '-' } else { '+' });
+
+        if hhmm == 0 && self.presentation2 == Some('t') {
+            return Ok("Z".into());
+        }
+        if self.component == 'z' {
+            formatted.insert_str(0, "GMT");
+        }
+        Ok(formatted)
+    }
+
+    fn regex(&self) -> String {
+        match self.component {
+            'P' => "am|pm".into(),
+            'Z' | 'z' => {
+                let separator = self
+                    .integer
+                    .as_ref()
+                    .and_then(|format| format.regular_separator())
+                    .map(|separator| regex::escape(&separator.to_string()));
+                let mut regex = match separator {
+                    Some(separator) => format!("[-+][0-9]+{}[0-9]+", separator),
+                    None => "[-+][0-9]+".into(),
+                };
+                if self.component == 'z' {
+                    regex.insert_str(0, "GMT");
+                }
+                if self.presentation2 == Some('t') {
+                    regex.push_str("|Z");
+                }
+                regex
+            }
+            _ => match &self.integer {
+                Some(format) => {
+                    let digits = self
+                        .digits
+                        .map(|digits| (digits.saturating_sub(format.optional_digits()), digits));
+                    format.regex(digits)
+                }
+                None => {
+                    let names: Vec<String> = self
+                        .names()
+                        .iter()
+                        .map(|name| regex::escape(&self.name(name)))
+                        .collect();
+                    names.join("|")
+                }
+            },
+        }
+    }
+
+    fn parse(&self, s: &str) -> Option<i64> {
+        match self.component {
+            'P' => Some(s.eq_ignore_ascii_case("pm") as i64),
+            'Z' | 'z' => {
+                let s = match s.get(..3) {
+                    Some(gmt) if self.component == 'z' && gmt.eq_ignore_ascii_case("GMT") => {
+                        &s[3..]
+                    }
+                    _ => s,
+                };
+                if s.eq_ignore_ascii_case("Z") {
+                    return Some(0);
+                }
+                let (sign, s) = match s.strip_prefix('-') {
+                    Some(s) => (-1, s),
+                    None => (1, s.strip_prefix('+').unwrap_or(s)),
+                };
+                let (hours, minutes) = match s.find(|c: char| !c.is_ascii_digit()) {
+                    Some(separator) => (&s[..separator], &s[separator + 1..]),
+                    None if s.len() <= 2 => (s, "0"),
+                    None => s.split_at(s.len() - 2),
+                };
+                Some(sign * (hours.parse::<i64>().ok()? * 60 + minutes.parse::<i64>().ok()?))
+            }
+            _ => match &self.integer {
+                Some(format) => format.parse(s),
+                None => {
+                    let index = self
+                        .names()
+                        .iter()
+                        .position(|name| self.name(name).eq_ignore_ascii_case(s))?;
+                    Some(index as i64 + 1)
+                }
+            },
+        }
+    }
+}
+
+/// Parses an ISO 8601 timestamp as `Date.parse` does in JavaScript, e.g.
+/// `2017-11-07T15:07:54.972Z`, where a timestamp without an offset is in UTC
+///
+/// The timestamp must match the pattern which jsonata-js checks before calling
+/// `Date.parse`, so a time must have seconds and the offset may be written
+/// without a colon, e.g. `+0100`.
+pub(super) fn parse_iso8601(s: &str) -> Option<i64> {
+    static ISO8601: OnceLock<Regex> = OnceLock::new();
+    let regex = ISO8601.get_or_init(|| {
+        Regex::new(concat!(
+            r"^(\d{4})(?:-([01]\d)(?:-([0-3]\d))?)?",
+            r"(?:T([0-2]\d):([0-5]\d):([0-5]\d)(?:\.(\d+))?)?(Z|[+-][0-2]\d:?[0-5]\d)?$",
+        ))
+        .unwrap() // a valid regex
+    });
+    let captures = regex.captures(s)?;
+    let number = |index: usize, default: i64| -> Option<i64> {
+        match captures.get(index) {
+            Some(capture) => capture.as_str().parse().ok(),
+            None => Some(default),
+        }
+    };
+
+    let year = number(1, 0)?;
+    let month = number(2, 1)?;
+    let day = number(3, 1)?;
+    let hour = number(4, 0)?;
+    let minute = number(5, 0)?;
+    let second = number(6, 0)?;
+    let millisecond = match captures.get(7) {
+        Some(fraction) => format!(
+            "{:0<3}",
+            &fraction.as_str()[..fraction.as_str().len().min(3)]
+        )
+        .parse()
+        .ok()?,
+        None => 0,
+    };
+
+    let days_in_month = days_from_civil(year, month + 1, 1) - days_from_civil(year, month, 1);
+    let valid = (1..=12).contains(&month)
+        && (1..=days_in_month).contains(&day)
+        && (hour < 24 || (hour == 24 && minute == 0 && second == 0 && millisecond == 0))
+        && minute < 60
+        && second < 60;
+    if !valid {
+        return None;
+    }
+
+    let mut millis = days_from_civil(year, month, day) * MILLIS_PER_DAY
+        + hour * 3_600_000
+        + minute * 60_000
+        + second * 1000
+        + millisecond;
+    if let Some(offset) = captures.get(8).filter(|offset| offset.as_str() != "Z") {
+        let offset = offset.as_str().replace(':', "");
+        millis -= Offset::parse(&offset)?.millis();
+    }
+    Some(millis)
+}
@@ -0,0 +1,266 @@
+use serde_json::{json, Value};
+
+use crate::{
+    builtins::BuiltIns,
+    evaluate::{EvaluationError, EvaluationResult},
+    tests::make_val,
+    value::JSONataValue,
+};
+
+/// 2017-11-07T15:12:37.121Z, a Tuesday
+const NOW: i64 = 1510067557121;
+
+fn arg(value: Value) -> Option<JSONataValue> {
+    Some(make_val(value))
+}
+
+fn from_millis(millis: i64, picture: Option<&str>, timezone: Option<&str>) -> EvaluationResult {
+    BuiltIns::from_millis(&[
+        arg(json!(millis)),
+        picture.map(|picture| make_val(json!(picture))),
+        timezone.map(|timezone| make_val(json!(timezone))),
+    ])
+}
+
+fn to_millis(timestamp: &str, picture: Option<&str>) -> EvaluationResult {
+    BuiltIns::to_millis(
+        NOW,
+        &[
+            arg(json!(timestamp)),
+            picture.map(|picture| make_val(json!(picture))),
+        ],
+    )
+}
+
+#[test]
+fn from_millis_iso8601() {
+    assert_eq!(
+        from_millis(1510067557121, None, None),
+        Ok(arg(json!("2017-11-07T15:12:37.121Z")))
+    );
+    assert_eq!(
+        from_millis(1510067557121, None, Some("-0500")),
+        Ok(arg(json!("2017-11-07T10:12:37.121-05:00")))
+    );
+    assert_eq!(
+        from_millis(-1, None, None),
+        Ok(arg(json!("1969-12-31T23:59:59.999Z")))
+    );
+    assert_eq!(BuiltIns::from_millis(&[None, None, None]), Ok(None));
+}
+
+#[test]
+fn from_millis_pictures() {
+    let millis = NOW;
+    let cases = vec![
+        ("[M01]/[D01]/[Y0001] [h#1]:[m01][P]", "11/07/2017 3:12pm"),
+        ("[FNn], [D1o] [MNn] [Y]", "Tuesday, 7th November 2017"),
+        ("[D1o] [MNn,3-3] [Y]", "7th Nov 2017"),
+        ("[FNn,*-3] [FN,*-3] [Fn]", "Tue TUE tuesday"),
+        (
+            "[Dw] [MNn] [Yw]",
+            "seven November two thousand and seventeen",
+        ),
+        ("[DWwo] of [MI]", "Seventh of XI"),
+        ("[Y,2]/[M]/[D]", "17/11/7"),
+        ("[Y01]", "17"),
+        ("[d] [W] [X] [w] [x] [F] [F1]", "311 45 2017 2 11 tuesday 2"),
+        ("[H]h[m]m[s]s[f]", "15h12m37s121"),
+        ("[h].[m01][PN]", "3.12PM"),
+        ("[[[Y]]] [C] [E]", "[2017] ISO ISO"),
+        ("[ Y 0001 ]", "2017"),
+        ("[Z] [Z01:01t]", "+00:00 Z"),
+        (
+            "[Y0001]-[M01]-[D01]T[H01]:[m01]:[s01].[f001][Z01:01t]",
+            "2017-11-07T15:12:37.121Z",
+        ),
+    ];
+    for (picture, expected) in cases {
+        let actual = from_millis(millis, Some(picture), None);
+        assert_eq!(actual, Ok(arg(json!(expected))), "{}", picture);
+    }
+}
+
+#[test]
+fn from_millis_timezones() {
+    let millis = NOW;
+    let cases = vec![
+        ("[H01]:[m01]:[s01] [z]", "-0500", "10:12:37 GMT-05:00"),
+        ("[H01]:[m01] [Z]", "+0530", "20:42 +05:30"),
+        ("[H01]:[m01] [Z0]", "+0530", "20:42 +5:30"),
+        ("[H01]:[m01] [Z0]", "-0800", "07:12 -8"),
+        ("[H01]:[m01] [Z0000]", "-0800", "07:12 -0800"),
+        ("[z]", "0000", "GMT+00:00"),
+        ("[D01] [H01]", "+1000", "08 01"),
+        ("[H01]", "not a timezone", "15"),
+    ];
+    for (picture, timezone, expected) in cases {
+        let actual = from_millis(millis, Some(picture), Some(timezone));
+        assert_eq!(actual, Ok(arg(json!(expected))), "{} {}", picture, timezone);
+    }
+}
+
+#[test]
+fn from_millis_out_of_range() {
+    let huge = BuiltIns::from_millis(&[arg(json!(1e300)), arg(json!("[Y]")), arg(json!("+0100"))]);
+    assert_eq!(huge, Err(EvaluationError::NumberOutOfRange(1e300)));
+
+    // the range of a JavaScript `Date`
+    assert!(from_millis(8_640_000_000_000_000, None, None).is_ok());
+    assert!(from_millis(-8_640_000_000_000_001, None, None).is_err());
+
+    // not reachable from JSON, but not a valid time either
+    assert!(super::timestamp(f64::INFINITY).is_err());
+    assert!(super::timestamp(f64::NAN).is_err());
+}
+
+#[test]
+fn from_millis_invalid_pictures() {
+    let cases = vec![
+        ("[Y", EvaluationError::DatePictureUnterminated),
+        ("[Q]", EvaluationError::UnknownDateComponent("Q".into())),
+        (
+            "[DN]",
+            EvaluationError::NameModifierNotApplicable("D".into()),
+        ),
+        ("[Z010101]", EvaluationError::TimezoneTooManyDigits),
+        ("[M0\u{660}]", EvaluationError::MixedDecimalDigitGroups),
+    ];
+    for (picture, expected) in cases {
+        assert_eq!(
+            from_millis(0, Some(picture), None),
+            Err(expected),
+            "{}",
+            picture
+        );
+    }
+}
+
+#[test]
+fn to_millis_iso8601() {
+    let cases = vec![
+        ("2017-11-07T15:07:54.972Z", 1510067274972_i64),
+        ("2017-11-07T10:07:54.972-05:00", 1510067274972),
+        ("2017-11-07T15:07:54.9Z", 1510067274900),
+        ("2017-11-07T16:07:54.972+0100", 1510067274972),
+        ("2017-11-07T15:07:54", 1510067274000),
+        ("2017-11-07", 1510012800000),
+        ("2017", 1483228800000),
+        ("1969-12-31T23:59:59.999Z", -1),
+    ];
+    for (timestamp, expected) in cases {
+        assert_eq!(
+            to_millis(timestamp, None),
+            Ok(arg(json!(expected))),
+            "{}",
+            timestamp
+        );
+    }
+
+    for timestamp in [
+        "foo",
+        "2017-13-01",
+        "2017-02-29",
+        "2017-11-07T25:00:00Z",
+        "2017-11-07T15:07Z",
+        "+002017-11-07T15:07:54Z",
+        "2017-11-07T15:07:54+01",
+    ] {
+        assert_eq!(
+            to_millis(timestamp, None),
+            Err(EvaluationError::InvalidTimestamp(timestamp.into()))
+        );
+    }
+}
+
+#[test]
+fn to_millis_pictures() {
+    let cases = vec![
+        ("2018-03-27", "[Y0001]-[M01]-[D01]", 1522108800000_i64),
+        ("20180327", "[Y0001][M01][D01]", 1522108800000),
+        (
+            "Tuesday, 7th November 2017",
+            "[FNn], [D1o] [MNn] [Y]",
+            1510012800000,
+        ),
+        ("7 nov 2017", "[D] [MNn,3-3] [Y]", 1510012800000),
+        ("2017-11-07 3:12pm", "[Y]-[M]-[D] [h]:[m][P]", 1510067520000),
+        (
+            "2017-11-07 12:00am",
+            "[Y]-[M]-[D] [h]:[m][P]",
+            1510012800000,
+        ),
+        (
+            "2017-11-07 10:12 -05:00",
+            "[Y]-[M]-[D] [H]:[m] [Z]",
+            1510067520000,
+        ),
+        ("2018-032", "[Y]-[d]", 1517443200000),
+        ("2018-W05-4", "[X]-W[W01]-[F1]", 1517443200000),
+        (
+            "seventh of November 2017",
+            "[Dwo] of [MNn] [Y]",
+            1510012800000,
+        ),
+        ("MMXVII", "[YI]", 1483228800000),
+    ];
+    for (timestamp, picture, expected) in cases {
+        let actual = to_millis(timestamp, Some(picture));
+        assert_eq!(actual, Ok(arg(json!(expected))), "{}", picture);
+    }
+}
+
+#[test]
+fn to_millis_defaults_to_now() {
+    // 2017-11-07T13:45:00.000Z
+    assert_eq!(
+        to_millis("13:45", Some("[H]:[m]")),
+        Ok(arg(json!(1510062300000_i64)))
+    );
+    // 2017-03-01T00:00:00.000Z
+    assert_eq!(
+        to_millis("March", Some("[MNn]")),
+        Ok(arg(json!(1488326400000_i64)))
+    );
+}
+
+#[test]
+fn to_millis_round_trips() {
+    let pictures = [
+        "[Y0001]-[M01]-[D01]T[H01]:[m01]:[s01].[f001][Z01:01t]",
+        "[FNn], [D1o] [MNn] [Y] [h]:[m01]:[s01].[f001][P]",
+        "[Y]-[d] [H]:[m]:[s].[f]",
+        "[Yw], [Mw], [Dw], [Hw], [mw], [sw], [fw]",
+    ];
+    for millis in [0, 1510067557121, 951782400000, 4102444799999] {
+        for picture in pictures {
+            let formatted = from_millis(millis, Some(picture), None).unwrap();
+            let formatted = formatted.as_ref().and_then(|s| s.as_value()?.as_str());
+            let parsed = to_millis(formatted.unwrap(), Some(picture));
+            assert_eq!(parsed, Ok(arg(json!(millis))), "{:?}", formatted);
+        }
+    }
+}
+
+#[test]
+fn to_millis_errors() {
+    assert_eq!(
+        to_millis("2018 10", Some("[Y] [D]")),
+        Err(EvaluationError::DatePictureMissingComponents)
+    );
+    assert_eq!(to_millis("hello", Some("[Y]")), Ok(None));
+    assert_eq!(BuiltIns::to_millis(NOW, &[None, None]), Ok(None));
+}
+
+#[test]
+fn now() {
+    assert_eq!(BuiltIns::millis(NOW, &[]), Ok(arg(json!(NOW))));
+    assert_eq!(
+        BuiltIns::now(NOW, &[None, None]),
+        Ok(arg(json!("2017-11-07T15:12:37.121Z")))
+    );
+    assert_eq!(
+        BuiltIns::now(NOW, &[arg(json!("[H01]:[m01]")), arg(json!("+0100"))]),
+        Ok(arg(json!("16:12")))
+    );
+}
@@ -0,0 +1,180 @@
+use serde_json::Value;
+
+use crate::{
+    evaluate::{EvaluationError, EvaluationResult},
+    value::{sequence::Sequence, JSONataValue},
+};
+
+use super::{arg, array_arg, callback_args, function_arg, BuiltIns};
+
+#[cfg(test)]
+mod tests;
+
+impl BuiltIns {
+    /// Returns an array containing the results of applying the `function` parameter
+    /// to each value in the `array` parameter.
+    ///
+    /// The function that is supplied as the second parameter must have the following signature:
+    ///
+    /// `function(value [, index [, array]])`
+    ///
+    /// Each value in the input array is passed in as the first parameter in the supplied function.
+    /// The index (position) of that value in the input array is passed in as the second parameter,
+    /// if specified. The whole input array is passed in as the third parameter, if specified.
+    ///
+    /// ## Examples
+    ///
+    /// ```
+    /// $map([1..5], $string) => ["1", "2", "3", "4", "5"]
+    /// ```
+    /// **Signature**: `$map(array, function)`
+    pub(crate) fn map(args: &[Option<JSONataValue>]) -> EvaluationResult {
+        let (array, func) = match (array_arg(args, 0), function_arg(args, 1)) {
+            (Some(array), Some(func)) => (array, func),
+            _ => return Ok(None),
+        };
+        let collection = Value::Array(array.clone());
+
+        let mut results = Sequence::new();
+        for (index, value) in array.iter().enumerate() {
+            let args = callback_args(func, value, index.into(), &collection);
+            if let Some(result) = func.call(&args, None)? {
+                results.push(result);
+            }
+        }
+        results.collapse()
+    }
+
+    /// Returns an array containing only the values in the `array` parameter that satisfy
+    /// the `function` predicate (i.e. `function` returns Boolean `true` when passed the value).
+    ///
+    /// The function that is supplied as the second parameter must have the following signature:
+    ///
+    /// `function(value [, index [, array]])`
+    ///
+    /// Each value in the input array is passed in as the first parameter in the supplied function.
+    /// The index (position) of that value in the input array is passed in as the second parameter,
+    /// if specified. The whole input array is passed in as the third parameter, if specified.
+    ///
+    /// ## Examples
+    ///
+    /// ```
+    /// $filter(Account.Order.Product, function($v, $i, $a) {
+    ///   $v.Price > $average($a.Price)
+    /// })
+    /// ```
+    /// **Signature**: `$filter(array, function)`
+    pub(crate) fn filter(args: &[Option<JSONataValue>]) -> EvaluationResult {
+        let (array, func) = match (array_arg(args, 0), function_arg(args, 1)) {
+            (Some(array), Some(func)) => (array, func),
+            _ => return Ok(None),
+        };
+        let collection = Value::Array(array.clone());
+
+        let mut results = Sequence::new();
+        for (index, value) in array.iter().enumerate() {
+            let args = callback_args(func, value, index.into(), &collection);
+            if BuiltIns::to_boolean(func.call(&args, None)?.as_ref()) {
+                results.push(value.into());
+            }
+        }
+        results.collapse()
+    }
+
+    /// Returns the one and only one value in the `array` parameter that satisfies the `function`
+    /// predicate (i.e. `function` returns Boolean `true` when passed the value). Throws an
+    /// exception if the number of matching values is not exactly one.
+    ///
+    /// The function that is supplied as the second parameter must have the following signature:
+    ///
+    /// `function(value [, index [, array]])`
+    ///
+    /// If `function` is not specified, then the `array` must contain exactly one value.
+    ///
+    /// ## Examples
+    ///
+    /// ```
+    /// $single(Account.Order.Product, function($v) {
+    ///   $v.SKU = "0406654608"
+    /// })
+    /// ```
+    /// **Signature**: `$single(array [, function])`
+    pub(crate) fn single(args: &[Option<JSONataValue>]) -> EvaluationResult {
+        let array = match array_arg(args, 0) {
+            Some(array) => array,
+            None => return Ok(None),
+        };
+        let func = function_arg(args, 1);
+        let collection = Value::Array(array.clone());
+
+        let mut found = None;
+        for (index, value) in array.iter().enumerate() {
+            let matches = match func {
+                Some(func) => {
+                    let args = callback_args(func, value, index.into(), &collection);
+                    BuiltIns::to_boolean(func.call(&args, None)?.as_ref())
+                }
+                None => true,
+            };
+            if matches {
+                if found.is_some() {
+                    return Err(EvaluationError::SingleMatchedMany);
+                }
+                found = Some(value);
+            }
+        }
+
+        match found {
+            Some(value) => Ok(Some(value.into())),
+            None => Err(EvaluationError::SingleMatchedNone),
+        }
+    }
+
+    /// Returns an aggregated value derived from applying the `function` parameter successively
+    /// to each value in `array` in combination with the result of the previous application of
+    /// the function.
+    ///
+    /// The function must accept at least two arguments, and behaves like an infix operator
+    /// between each value within the `array`. The signature of this supplied function must
+    /// be of the form:
+    ///
+    /// `myfunc($accumulator, $value [, $index [, $array]])`
+    ///
+    /// If the optional `init` parameter is supplied, then that value is used as the initial
+    /// value in the aggregation (fold) process. If not supplied, the initial value is the
+    /// first value in the `array` parameter.
+    ///
+    /// ## Examples
+    ///
+    /// ```
+    /// $reduce([1..5], function($i, $j){$i * $j}) => 120
+    /// ```
+    /// **Signature**: `$reduce(array, function [, init])`
+    pub(crate) fn reduce(args: &[Option<JSONataValue>]) -> EvaluationResult {
+        let (array, func) = match (array_arg(args, 0), function_arg(args, 1)) {
+            (Some(array), Some(func)) => (array, func),
+            _ => return Ok(None),
+        };
+        let arity = func.arity().unwrap_or(4);
+        if arity < 2 {
+            return Err(EvaluationError::ReduceFunctionArity);
+        }
+        let collection = Value::Array(array.clone());
+
+        let (mut accumulator, start) = match (arg(args, 2), array.first()) {
+            (None, Some(first)) => (Some(first.into()), 1),
+            (init, _) => (init.cloned(), 0),
+        };
+        for (index, value) in array.iter().enumerate().skip(start) {
+            let mut args = vec![accumulator, Some(value.into())];
+            if arity >= 3 {
+                args.push(Some(index.into()));
+            }
+            if arity >= 4 {
+                args.push(Some(collection.clone().into()));
+            }
+            accumulator = func.call(&args, None)?;
+        }
+        Ok(accumulator)
+    }
+}
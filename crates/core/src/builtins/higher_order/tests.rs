@@ -0,0 +1,175 @@
+use serde_json::{json, Value};
+
+use crate::{
+    builtins::BuiltIns,
+    evaluate::{EvaluationError, EvaluationResult},
+    tests::{builtin, make_val},
+    value::{signature::Signature, JSONataValue},
+};
+
+fn func<F>(signature: &str, func: F) -> Option<JSONataValue>
+where
+    F: 'static + Fn(&[Option<JSONataValue>]) -> EvaluationResult,
+{
+    let signature = Signature::parse(signature).unwrap();
+    Some(JSONataValue::from_func_with_signature(func, "f", signature))
+}
+
+fn number(arg: &Option<JSONataValue>) -> f64 {
+    arg.as_ref()
+        .and_then(|arg| arg.as_value()?.as_f64())
+        .unwrap()
+}
+
+fn array(value: Value) -> Option<JSONataValue> {
+    Some(make_val(value))
+}
+
+#[test]
+fn map() {
+    let double = func("<n:n>", |args| Ok(Some((number(&args[0]) * 2.0).into())));
+    assert_eq!(
+        BuiltIns::map(&[array(json!([1, 2, 3])), double.clone()]),
+        Ok(array(json!([2, 4, 6])))
+    );
+    assert_eq!(
+        BuiltIns::map(&[array(json!([1])), double.clone()]),
+        Ok(array(json!(2)))
+    );
+    assert_eq!(BuiltIns::map(&[array(json!([])), double]), Ok(None));
+
+    // the index is passed to a function declaring a second parameter
+    let add_index = func("<nn:n>", |args| {
+        Ok(Some((number(&args[0]) + number(&args[1])).into()))
+    });
+    assert_eq!(
+        BuiltIns::map(&[array(json!([10, 10])), add_index]),
+        Ok(array(json!([10, 11])))
+    );
+
+    // undefined results are dropped
+    let undefined = func("<n:n>", |_| Ok(None));
+    assert_eq!(BuiltIns::map(&[array(json!([1, 2])), undefined]), Ok(None));
+}
+
+#[test]
+fn map_without_signature() {
+    let arg_count = Some(JSONataValue::from_func(
+        |args: &[Option<JSONataValue>]| Ok(Some(args.len().into())),
+        "f",
+    ));
+    assert_eq!(
+        BuiltIns::map(&[array(json!(["a", "b"])), arg_count]),
+        Ok(array(json!([3, 3])))
+    );
+}
+
+#[test]
+fn map_builtin() {
+    // the optional `prettify` parameter of $string is not passed the index
+    assert_eq!(
+        BuiltIns::map(&[array(json!([1, 2, 3, 4, 5])), builtin("string")]),
+        Ok(array(json!(["1", "2", "3", "4", "5"])))
+    );
+    assert_eq!(
+        BuiltIns::map(&[array(json!([-1.5, 2])), builtin("abs")]),
+        Ok(array(json!([1.5, 2])))
+    );
+}
+
+#[test]
+fn map_arguments_are_validated() {
+    let first_param_string = func("<sn:s>", |args| Ok(args[0].clone()));
+    assert_eq!(
+        BuiltIns::map(&[array(json!([1])), first_param_string]),
+        Err(EvaluationError::FunctionSignatureMismatch(1, "f".into()))
+    );
+}
+
+#[test]
+fn filter() {
+    let even = func("<n:b>", |args| {
+        Ok(Some((number(&args[0]) % 2.0 == 0.0).into()))
+    });
+    assert_eq!(
+        BuiltIns::filter(&[array(json!([1, 2, 3, 4])), even.clone()]),
+        Ok(array(json!([2, 4])))
+    );
+    assert_eq!(
+        BuiltIns::filter(&[array(json!([1, 2, 3])), even.clone()]),
+        Ok(array(json!(2)))
+    );
+    assert_eq!(BuiltIns::filter(&[array(json!([1, 3])), even]), Ok(None));
+
+    let after_first = func("<xn:b>", |args| Ok(Some((number(&args[1]) > 0.0).into())));
+    assert_eq!(
+        BuiltIns::filter(&[array(json!(["a", "b", "c"])), after_first]),
+        Ok(array(json!(["b", "c"])))
+    );
+}
+
+#[test]
+fn single() {
+    let even = func("<n:b>", |args| {
+        Ok(Some((number(&args[0]) % 2.0 == 0.0).into()))
+    });
+    assert_eq!(
+        BuiltIns::single(&[array(json!([1, 2, 3])), even.clone()]),
+        Ok(array(json!(2)))
+    );
+    assert_eq!(
+        BuiltIns::single(&[array(json!([2, 4])), even.clone()]),
+        Err(EvaluationError::SingleMatchedMany)
+    );
+    assert_eq!(
+        BuiltIns::single(&[array(json!([1, 3])), even]),
+        Err(EvaluationError::SingleMatchedNone)
+    );
+
+    assert_eq!(
+        BuiltIns::single(&[array(json!(["a"])), None]),
+        Ok(array(json!("a")))
+    );
+    assert_eq!(
+        BuiltIns::single(&[array(json!(["a", "b"])), None]),
+        Err(EvaluationError::SingleMatchedMany)
+    );
+}
+
+#[test]
+fn reduce() {
+    let product = func("<nn:n>", |args| {
+        Ok(Some((number(&args[0]) * number(&args[1])).into()))
+    });
+    assert_eq!(
+        BuiltIns::reduce(&[array(json!([1, 2, 3, 4, 5])), product.clone(), None]),
+        Ok(array(json!(120)))
+    );
+    assert_eq!(
+        BuiltIns::reduce(&[array(json!([1, 2])), product.clone(), array(json!(10))]),
+        Ok(array(json!(20)))
+    );
+    assert_eq!(
+        BuiltIns::reduce(&[array(json!([])), product.clone(), array(json!(10))]),
+        Ok(array(json!(10)))
+    );
+    assert_eq!(
+        BuiltIns::reduce(&[array(json!([])), product, None]),
+        Ok(None)
+    );
+
+    // the index is passed to a function declaring a third parameter
+    let sum_indexes = func("<nxn:n>", |args| {
+        Ok(Some((number(&args[0]) + number(&args[2])).into()))
+    });
+    assert_eq!(
+        BuiltIns::reduce(&[array(json!(["a", "b", "c"])), sum_indexes, array(json!(0))]),
+        Ok(array(json!(3)))
+    );
+
+    let identity = func("<n:n>", |args| Ok(args[0].clone()));
+    assert_eq!(
+        BuiltIns::reduce(&[array(json!([1, 2])), identity, None]),
+        Err(EvaluationError::ReduceFunctionArity)
+    );
+}
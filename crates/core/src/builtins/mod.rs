@@ -1,119 +1,248 @@
+use std::rc::Rc;
+
+use serde_json::Value;
+
 use crate::{
-    evaluate::{EvaluationError, EvaluationResult, JSONataVariables},
-    value::JSONataValue,
+    evaluate::{EvaluationResult, Evaluations, JSONataVariables},
+    value::{number::JSONataNumber, signature::Signature, JSONataFunction, JSONataValue},
 };
 
 mod boolean;
 mod date;
+mod higher_order;
 mod numeric;
 mod object;
 mod sequence;
 mod string;
 
-/// Curries a built-in function to parse the input slice into
-/// a single `JSONataValue` argument.
+/// Gets the argument at `index`, or `None` if it is undefined.
 ///
-/// Invalid number of arguments causes an error to be returned.
-/// If the expected number of argument are passed but the argument
-/// is `None`, it skips the built-in processing and returns an
-/// `Ok(None)` as almost all of the JSONata built-ins just propagate
-/// the `None` value.
-#[inline]
-fn one_arg<F>(
-    func: F,
-    func_name: &'static str,
-) -> impl Fn(&[Option<JSONataValue>]) -> EvaluationResult
-where
-    F: Fn(&JSONataValue) -> EvaluationResult,
-{
-    move |args: &[Option<JSONataValue>]| match args.len() {
-        1 => match args.get(0).unwrap() {
-            Some(arg) => func(arg),
-            None => Ok(None),
-        },
-        len => Err(EvaluationError::function_incorrect_num_arguments(
-            func_name, 1, len,
-        )),
+/// Built-ins are only called with arguments which have been validated against
+/// their signature, so the typed getters below return `None` for a value
+/// of another type rather than an error.
+fn arg(args: &[Option<JSONataValue>], index: usize) -> Option<&JSONataValue> {
+    args.get(index).and_then(|arg| arg.as_ref())
+}
+
+fn number_arg(args: &[Option<JSONataValue>], index: usize) -> Option<JSONataNumber> {
+    match arg(args, index)?.as_value()? {
+        Value::Number(n) => Some(n.into()),
+        _ => None,
     }
 }
 
-/// TODO: finish documenting
-///
-/// produces a new function...
-/// similar to one_arg but does not propagate the None, and is used for functions
-/// that specifically need to know if the arg is None or not
-/// FIXME: How can we get the function name in here without being annoying?
-#[inline]
-fn one_arg_no_propagate_none<F>(func: F) -> impl Fn(&[Option<JSONataValue>]) -> EvaluationResult
-where
-    F: Fn(&Option<JSONataValue>) -> EvaluationResult,
-{
-    move |args: &[Option<JSONataValue>]| match args.get(0) {
-        Some(arg) => func(arg),
-        None => Err(EvaluationError::function_incorrect_num_arguments(
-            "FIXME", 1, 0,
-        )),
+fn string_arg(args: &[Option<JSONataValue>], index: usize) -> Option<&str> {
+    arg(args, index)?.as_value()?.as_str()
+}
+
+fn array_arg(args: &[Option<JSONataValue>], index: usize) -> Option<&Vec<Value>> {
+    arg(args, index)?.as_value()?.as_array()
+}
+
+fn function_arg(args: &[Option<JSONataValue>], index: usize) -> Option<&JSONataFunction> {
+    match arg(args, index)? {
+        JSONataValue::Function(func) => Some(func),
+        JSONataValue::Value(_) => None,
     }
 }
 
-/// TODO: Provide a helper function to ensure a value is always a Vec?
-/// could be helpful for sequence built-ins
-// #[inline]
-// fn vecify<F>(func: F) -> impl Fn(&JSONataValue) -> EvaluationResult
-// where
-//     F: Fn(Vec<JSONataValue>) -> EvaluationResult,
-// {
-//     // move |arg: &JSONataValue| {
-//     //     let vecified: Vec<JSONataValue> = match arg {
-//     //         JSONataValue::Value(val) => match val {
-//     //             Value::Array(arr) => arr.iter().map(|val| JSONataValue::from(val)).collect(),
-//     //             v => todo!(), // vec![v],
-//     //         },
-//     //         JSONataValue::Function(f) => {
-//     //             let g = f.clone();
-//     //             vec![JSONataValue::Function(g)]
-//     //         }
-//     //     };
-//     //     func(vecified);
-//     //     todo!()
-//     // }
-// }
+/// Arguments for the function passed to a higher-order built-in, which is
+/// called with as many of `value`, `index` and `collection` as the required
+/// parameters declared by its signature. A function without a signature gets
+/// all three.
+fn callback_args(
+    func: &JSONataFunction,
+    value: &Value,
+    index: JSONataValue,
+    collection: &Value,
+) -> Vec<Option<JSONataValue>> {
+    let arity = func.arity().unwrap_or(3);
+    let mut args = vec![Some(value.into())];
+    if arity >= 2 {
+        args.push(Some(index));
+    }
+    if arity >= 3 {
+        args.push(Some(collection.into()));
+    }
+    args
+}
 
 pub(crate) struct BuiltIns;
 
 impl BuiltIns {
     pub(crate) fn populate_context(variables: &mut JSONataVariables) {
-        // TODO: Add the rest of the built-ins
-        // Add number of required arguments here?
-        BuiltIns::add_builtin(variables, "count", BuiltIns::count);
+        // aggregation
+        BuiltIns::add_builtin(variables, "sum", "<a<n>:n>", BuiltIns::sum);
+        BuiltIns::add_builtin(variables, "max", "<a<n>:n>", BuiltIns::max);
+        BuiltIns::add_builtin(variables, "min", "<a<n>:n>", BuiltIns::min);
+        BuiltIns::add_builtin(variables, "average", "<a<n>:n>", BuiltIns::average);
 
         // boolean
-        BuiltIns::add_builtin(variables, "boolean", one_arg(BuiltIns::boolean, "boolean"));
-        BuiltIns::add_builtin(variables, "not", one_arg(BuiltIns::not, "not"));
+        BuiltIns::add_builtin(variables, "boolean", "<x-:b>", BuiltIns::boolean);
+        BuiltIns::add_builtin(variables, "not", "<x-:b>", BuiltIns::not);
+        BuiltIns::add_builtin(variables, "exists", "<x:b>", BuiltIns::exists);
+
+        // date
+        BuiltIns::add_builtin(variables, "fromMillis", "<n-s?s?:s>", BuiltIns::from_millis);
+
+        // higher-order
+        BuiltIns::add_builtin(variables, "map", "<af>", BuiltIns::map);
+        BuiltIns::add_builtin(variables, "filter", "<af>", BuiltIns::filter);
+        BuiltIns::add_builtin(variables, "single", "<af?>", BuiltIns::single);
+        BuiltIns::add_builtin(variables, "reduce", "<afj?:j>", BuiltIns::reduce);
+
+        // numeric
+        BuiltIns::add_builtin(variables, "number", "<(nsb)-:n>", BuiltIns::number);
+        BuiltIns::add_builtin(variables, "abs", "<n-:n>", BuiltIns::abs);
+        BuiltIns::add_builtin(variables, "floor", "<n-:n>", BuiltIns::floor);
+        BuiltIns::add_builtin(variables, "ceil", "<n-:n>", BuiltIns::ceil);
+        BuiltIns::add_builtin(variables, "round", "<n-n?:n>", BuiltIns::round);
+        BuiltIns::add_builtin(variables, "power", "<n-n:n>", BuiltIns::power);
+        BuiltIns::add_builtin(variables, "sqrt", "<n-:n>", BuiltIns::sqrt);
         BuiltIns::add_builtin(
             variables,
-            "exists",
-            one_arg_no_propagate_none(BuiltIns::exists),
+            "formatNumber",
+            "<n-so?:s>",
+            BuiltIns::format_number,
         );
+        BuiltIns::add_builtin(variables, "formatBase", "<n-n?:s>", BuiltIns::format_base);
+        BuiltIns::add_builtin(
+            variables,
+            "formatInteger",
+            "<n-s:s>",
+            BuiltIns::format_integer,
+        );
+        BuiltIns::add_builtin(
+            variables,
+            "parseInteger",
+            "<s-s:n>",
+            BuiltIns::parse_integer,
+        );
+
+        // object
+        BuiltIns::add_builtin(variables, "keys", "<x-:a<s>>", BuiltIns::keys);
+        BuiltIns::add_builtin(variables, "lookup", "<x-s:x>", BuiltIns::lookup);
+        BuiltIns::add_builtin(variables, "spread", "<x-:a<o>>", BuiltIns::spread);
+        BuiltIns::add_builtin(variables, "merge", "<a<o>:o>", BuiltIns::merge);
+        BuiltIns::add_builtin(variables, "each", "<o-f:a>", BuiltIns::each);
+        BuiltIns::add_builtin(variables, "sift", "<o-f?:o>", BuiltIns::sift);
+        BuiltIns::add_builtin(variables, "error", "<s?:x>", BuiltIns::error);
+        BuiltIns::add_builtin(variables, "assert", "<bs?:x>", BuiltIns::assert);
+        BuiltIns::add_builtin(variables, "type", "<x:s>", BuiltIns::r#type);
 
         // sequence
+        BuiltIns::add_builtin(variables, "count", "<a:n>", BuiltIns::count);
+        BuiltIns::add_builtin(variables, "append", "<xx:a>", BuiltIns::append);
+        BuiltIns::add_builtin(variables, "sort", "<af?:a>", BuiltIns::sort);
+        BuiltIns::add_builtin(variables, "reverse", "<a:a>", BuiltIns::reverse);
+        BuiltIns::add_builtin(variables, "distinct", "<x:x>", BuiltIns::distinct);
+        BuiltIns::add_builtin(variables, "zip", "<a+>", BuiltIns::zip);
+
+        // string
+        BuiltIns::add_builtin(variables, "string", "<x-b?:s>", BuiltIns::string);
+        BuiltIns::add_builtin(variables, "length", "<s-:n>", BuiltIns::length);
+        BuiltIns::add_builtin(variables, "substring", "<s-nn?:s>", BuiltIns::substring);
+        BuiltIns::add_builtin(
+            variables,
+            "substringBefore",
+            "<s-s:s>",
+            BuiltIns::substring_before,
+        );
         BuiltIns::add_builtin(
             variables,
-            "distinct",
-            one_arg(BuiltIns::distinct, "distinct"),
+            "substringAfter",
+            "<s-s:s>",
+            BuiltIns::substring_after,
         );
+        BuiltIns::add_builtin(variables, "uppercase", "<s-:s>", BuiltIns::uppercase);
+        BuiltIns::add_builtin(variables, "lowercase", "<s-:s>", BuiltIns::lowercase);
+        BuiltIns::add_builtin(variables, "trim", "<s-:s>", BuiltIns::trim);
+        BuiltIns::add_builtin(variables, "pad", "<s-ns?:s>", BuiltIns::pad);
+        BuiltIns::add_builtin(variables, "contains", "<s-(sf):b>", BuiltIns::contains);
+        BuiltIns::add_builtin(variables, "split", "<s-(sf)n?:a<s>>", BuiltIns::split);
+        BuiltIns::add_builtin(variables, "join", "<a<s>s?:s>", BuiltIns::join);
+        BuiltIns::add_builtin(variables, "match", "<s-f<s:o>n?:a<o>>", BuiltIns::r#match);
+        BuiltIns::add_builtin(variables, "replace", "<s-(sf)(sf)n?:s>", BuiltIns::replace);
+        BuiltIns::add_builtin(variables, "base64encode", "<s-:s>", BuiltIns::base64_encode);
+        BuiltIns::add_builtin(variables, "base64decode", "<s-:s>", BuiltIns::base64_decode);
+        BuiltIns::add_builtin(
+            variables,
+            "encodeUrlComponent",
+            "<s-:s>",
+            BuiltIns::encode_url_component,
+        );
+        BuiltIns::add_builtin(variables, "encodeUrl", "<s-:s>", BuiltIns::encode_url);
+        BuiltIns::add_builtin(
+            variables,
+            "decodeUrlComponent",
+            "<s-:s>",
+            BuiltIns::decode_url_component,
+        );
+        BuiltIns::add_builtin(variables, "decodeUrl", "<s-:s>", BuiltIns::decode_url);
+    }
+
+    /// Adds the built-ins which depend on the state of the evaluation in progress,
+    /// the time at which it started, its random numbers and the context `$eval`
+    /// evaluates expressions in, which they read from `evaluations` when called.
+    pub(crate) fn populate_evaluation_context(
+        variables: &mut JSONataVariables,
+        evaluations: &Rc<Evaluations>,
+    ) {
+        // date
+        let current = evaluations.clone();
+        BuiltIns::add_builtin(variables, "now", "<s?s?:s>", move |args| {
+            BuiltIns::now(current.current("now")?.timestamp(), args)
+        });
+        let current = evaluations.clone();
+        BuiltIns::add_builtin(variables, "millis", "<:n>", move |args| {
+            BuiltIns::millis(current.current("millis")?.timestamp(), args)
+        });
+        let current = evaluations.clone();
+        BuiltIns::add_builtin(variables, "toMillis", "<s-s?:n>", move |args| {
+            BuiltIns::to_millis(current.current("toMillis")?.timestamp(), args)
+        });
+
+        // numeric
+        let current = evaluations.clone();
+        BuiltIns::add_builtin(variables, "random", "<:n>", move |args| {
+            BuiltIns::random(&mut *current.current("random")?.random(), args)
+        });
+
+        // sequence
+        let current = evaluations.clone();
+        BuiltIns::add_builtin(variables, "shuffle", "<a:a>", move |args| {
+            BuiltIns::shuffle(&mut *current.current("shuffle")?.random(), args)
+        });
+
+        // string
+        let current = evaluations.clone();
+        let signature = Signature::parse("<sx?:x>").expect("valid signature for $eval");
+        let func = move |args: &[Option<JSONataValue>], input: Option<&Value>| {
+            let context = current.current("eval")?;
+            BuiltIns::eval(&context, args, input)
+        };
+        let func = JSONataValue::from_func_with_context(func, "eval", signature);
+        variables.insert("eval".into(), Some(func));
     }
 
     /// Adds the built-in function to a variables hashmap, which is generally available
     /// to the currently running program.
     ///
+    /// The arguments of each call are validated against `signature` before the
+    /// built-in is called, see [`Signature`] for the syntax.
+    ///
     /// FIXME: 'static lifetime may be wrong here.
-    fn add_builtin<N, B: 'static>(variables: &mut JSONataVariables, ident: N, builtin: B)
-    where
-        N: Into<String> + Clone,
-        B: Fn(&[Option<JSONataValue>]) -> EvaluationResult,
+    fn add_builtin<B>(
+        variables: &mut JSONataVariables,
+        ident: &'static str,
+        signature: &'static str,
+        builtin: B,
+    ) where
+        B: 'static + Fn(&[Option<JSONataValue>]) -> EvaluationResult,
     {
-        let func = JSONataValue::from_func(builtin, ident.clone());
-        variables.insert(ident.into(), Some(func).into());
+        let signature = Signature::parse(signature)
+            .unwrap_or_else(|_| panic!("invalid signature for built-in ${}", ident));
+        let func = JSONataValue::from_func_with_signature(builtin, ident, signature);
+        variables.insert(ident.into(), Some(func));
     }
 }
@@ -0,0 +1,476 @@
+use serde_json::{Map, Value};
+
+use crate::{evaluate::EvaluationError, value::number::JSONataNumber};
+
+/// Decimal format used by `$formatNumber`, see the
+/// [decimal format](https://www.w3.org/TR/xpath-functions-31/#defining-decimal-format)
+/// section of the XPath F&O 3.1 specification
+///
+/// Each property may be overridden by the `options` argument. The properties
+/// which mark the active parts of a picture string are single characters, and
+/// only the first character of their option value is used.
+#[derive(Debug, Clone, PartialEq)]
+pub(super) struct DecimalFormat {
+    decimal_separator: char,
+    grouping_separator: char,
+    exponent_separator: char,
+    minus_sign: String,
+    percent: String,
+    per_mille: String,
+    zero_digit: char,
+    digit: char,
+    pattern_separator: char,
+}
+
+impl Default for DecimalFormat {
+    fn default() -> Self {
+        Self {
+            decimal_separator: '.',
+            grouping_separator: ',',
+            exponent_separator: 'e',
+            minus_sign: "-".into(),
+            percent: "%".into(),
+            per_mille: "\u{2030}".into(),
+            zero_digit: '0',
+            digit: '#',
+            pattern_separator: ';',
+        }
+    }
+}
+
+/// A sub-picture of a picture string split into its parts, F&O 4.7.3
+#[derive(Debug)]
+struct SubPicture {
+    picture: String,
+    prefix: String,
+    suffix: String,
+    active: Vec<char>,
+    mantissa: Vec<char>,
+    exponent: Option<Vec<char>>,
+    integer: Vec<char>,
+    fraction: Vec<char>,
+}
+
+/// The variables derived from a sub-picture which control the formatting
+/// of a number, F&O 4.7.4
+#[derive(Debug, Clone)]
+struct PictureVariables {
+    picture: String,
+    prefix: String,
+    suffix: String,
+    integer_grouping: Vec<usize>,
+    regular_grouping: usize,
+    min_integer_size: usize,
+    scaling_factor: usize,
+    fraction_grouping: Vec<usize>,
+    min_fraction_size: usize,
+    max_fraction_size: usize,
+    min_exponent_size: usize,
+}
+
+impl DecimalFormat {
+    /// Creates the default decimal format with the properties in `options`
+    /// overridden, e.g. `{"decimal-separator": ","}`
+    pub fn with_options(options: &Map<String, Value>) -> Self {
+        let mut format = Self::default();
+        for (name, value) in options {
+            let value = match value.as_str() {
+                Some(value) => value,
+                None => continue,
+            };
+            let first = match value.chars().next() {
+                Some(first) => first,
+                None => continue,
+            };
+            match name.as_str() {
+                "decimal-separator" => format.decimal_separator = first,
+                "grouping-separator" => format.grouping_separator = first,
+                "exponent-separator" => format.exponent_separator = first,
+                "minus-sign" => format.minus_sign = value.into(),
+                "percent" => format.percent = value.into(),
+                "per-mille" => format.per_mille = value.into(),
+                "zero-digit" => format.zero_digit = first,
+                "digit" => format.digit = first,
+                "pattern-separator" => format.pattern_separator = first,
+                _ => {}
+            }
+        }
+        format
+    }
+
+    /// Formats `value` as described by `picture`
+    pub fn format(&self, value: f64, picture: &str) -> Result<String, EvaluationError> {
+        let sub_pictures: Vec<&str> = picture.split(self.pattern_separator).collect();
+        if sub_pictures.len() > 2 {
+            return Err(EvaluationError::PictureTooManySubPictures);
+        }
+
+        let mut variables = Vec::with_capacity(2);
+        for sub_picture in sub_pictures {
+            let parts = self.split(sub_picture);
+            self.validate(&parts)?;
+            variables.push(self.analyse(parts));
+        }
+        if variables.len() == 1 {
+            let mut negative = variables[0].clone();
+            negative.prefix = format!("{}{}", self.minus_sign, negative.prefix);
+            variables.push(negative);
+        }
+
+        let pic = if value >= 0.0 {
+            &variables[0]
+        } else {
+            &variables[1]
+        };
+        let adjusted = if pic.picture.contains(&self.percent) {
+            value * 100.0
+        } else if pic.picture.contains(&self.per_mille) {
+            value * 1000.0
+        } else {
+            value
+        };
+
+        let (mantissa, exponent) = if pic.min_exponent_size == 0 {
+            (adjusted, None)
+        } else {
+            // mantissa * 10^exponent = adjusted
+            let max_mantissa = 10_f64.powi(pic.scaling_factor as i32);
+            let min_mantissa = 10_f64.powi(pic.scaling_factor as i32 - 1);
+            let mut mantissa = adjusted.abs();
+            let mut exponent: i32 = 0;
+            if mantissa != 0.0 {
+                while mantissa < min_mantissa {
+                    mantissa *= 10.0;
+                    exponent -= 1;
+                }
+                while mantissa > max_mantissa {
+                    mantissa /= 10.0;
+                    exponent += 1;
+                }
+            }
+            (mantissa, Some(exponent))
+        };
+
+        let rounded = JSONataNumber::from(mantissa)
+            .round(pic.max_fraction_size as i64)
+            .as_f64();
+        let mut digits: Vec<char> = self
+            .make_string(&format!("{:.*}", pic.max_fraction_size, rounded.abs()))
+            .chars()
+            .collect();
+        match digits.iter().position(|c| *c == '.') {
+            Some(position) => digits[position] = self.decimal_separator,
+            None => digits.push(self.decimal_separator),
+        }
+        let leading_zeros = digits.iter().take_while(|c| **c == self.zero_digit).count();
+        digits.drain(..leading_zeros);
+        while digits.last() == Some(&self.zero_digit) {
+            digits.pop();
+        }
+
+        // pad with zeros to the minimum sizes
+        let decimal_position = self.decimal_position(&digits);
+        let pad_left = pic.min_integer_size.saturating_sub(decimal_position);
+        let fraction_size = digits.len() - decimal_position - 1;
+        let pad_right = pic.min_fraction_size.saturating_sub(fraction_size);
+        digits.splice(0..0, std::iter::repeat_n(self.zero_digit, pad_left));
+        digits.extend(std::iter::repeat_n(self.zero_digit, pad_right));
+
+        // insert the grouping separators of the integer part
+        let mut decimal_position = self.decimal_position(&digits);
+        if let Some(group_count) = decimal_position
+            .saturating_sub(1)
+            .checked_div(pic.regular_grouping)
+        {
+            for group in 1..=group_count {
+                let position = decimal_position - group * pic.regular_grouping;
+                digits.insert(position, self.grouping_separator);
+            }
+        } else {
+            for position in &pic.integer_grouping {
+                if *position < decimal_position {
+                    digits.insert(decimal_position - position, self.grouping_separator);
+                    decimal_position += 1;
+                }
+            }
+        }
+
+        // and of the fractional part
+        let decimal_position = self.decimal_position(&digits);
+        for (inserted, position) in pic.fraction_grouping.iter().enumerate() {
+            let position = decimal_position + 1 + position + inserted;
+            if position < digits.len() {
+                digits.insert(position, self.grouping_separator);
+            }
+        }
+
+        if digits.last() == Some(&self.decimal_separator) {
+            digits.pop();
+        }
+
+        let mut formatted: String = digits.into_iter().collect();
+        if let Some(exponent) = exponent {
+            let exponent_digits = self.make_string(&exponent.abs().to_string());
+            let pad = pic
+                .min_exponent_size
+                .saturating_sub(exponent_digits.chars().count());
+            formatted.push(self.exponent_separator);
+            if exponent < 0 {
+                formatted.push_str(&self.minus_sign);
+            }
+            formatted.extend(std::iter::repeat_n(self.zero_digit, pad));
+            formatted.push_str(&exponent_digits);
+        }
+
+        Ok(format!("{}{}{}", pic.prefix, formatted, pic.suffix))
+    }
+
+    fn is_decimal_digit(&self, c: char) -> bool {
+        let zero = self.zero_digit as u32;
+        (zero..zero + 10).contains(&(c as u32))
+    }
+
+    fn is_digit(&self, c: char) -> bool {
+        self.is_decimal_digit(c) || c == self.digit
+    }
+
+    fn is_active(&self, c: char) -> bool {
+        self.is_digit(c)
+            || c == self.decimal_separator
+            || c == self.exponent_separator
+            || c == self.grouping_separator
+            || c == self.pattern_separator
+    }
+
+    /// Replaces the ASCII digits of a formatted number with the digits of the
+    /// decimal digit family of the format
+    fn make_string(&self, s: &str) -> String {
+        s.chars()
+            .map(|c| match c.to_digit(10) {
+                Some(digit) => char::from_u32(self.zero_digit as u32 + digit).unwrap_or(c),
+                None => c,
+            })
+            .collect()
+    }
+
+    fn decimal_position(&self, digits: &[char]) -> usize {
+        digits
+            .iter()
+            .position(|c| *c == self.decimal_separator)
+            .unwrap_or(digits.len())
+    }
+
+    fn split(&self, picture: &str) -> SubPicture {
+        let chars: Vec<char> = picture.chars().collect();
+
+        // the prefix and suffix are the passive characters before the first and
+        // after the last active character, where an exponent separator is passive
+        let is_boundary = |c: &char| self.is_active(*c) && *c != self.exponent_separator;
+        let start = chars.iter().position(is_boundary).unwrap_or(chars.len());
+        let end = chars
+            .iter()
+            .rposition(is_boundary)
+            .map_or(start, |end| end + 1);
+        let active = chars[start..end].to_vec();
+
+        let exponent_position = chars[start..]
+            .iter()
+            .position(|c| *c == self.exponent_separator)
+            .map(|position| start + position)
+            .filter(|position| *position <= end);
+        let (mantissa, exponent) = match exponent_position {
+            Some(position) => (
+                chars[start..position].to_vec(),
+                Some(chars[(position + 1).min(end)..end].to_vec()),
+            ),
+            None => (active.clone(), None),
+        };
+
+        let (integer, fraction) = match mantissa.iter().position(|c| *c == self.decimal_separator) {
+            Some(position) => (
+                mantissa[..position].to_vec(),
+                mantissa[position + 1..].to_vec(),
+            ),
+            None => (mantissa.clone(), Vec::new()),
+        };
+
+        SubPicture {
+            picture: picture.into(),
+            prefix: chars[..start].iter().collect(),
+            suffix: chars[end..].iter().collect(),
+            active,
+            mantissa,
+            exponent,
+            integer,
+            fraction,
+        }
+    }
+
+    /// Validates a sub-picture, returning the last of the errors found as
+    /// jsonata-js does
+    fn validate(&self, parts: &SubPicture) -> Result<(), EvaluationError> {
+        let picture: Vec<char> = parts.picture.chars().collect();
+        let count = |pattern: &str| parts.picture.matches(pattern).count();
+        let mut error = None;
+
+        let decimal_separators = picture
+            .iter()
+            .filter(|c| **c == self.decimal_separator)
+            .count();
+        if decimal_separators > 1 {
+            error = Some(EvaluationError::PictureMultipleDecimalSeparators);
+        }
+        if count(&self.percent) > 1 {
+            error = Some(EvaluationError::PictureMultiplePercents);
+        }
+        if count(&self.per_mille) > 1 {
+            error = Some(EvaluationError::PictureMultiplePerMilles);
+        }
+        if count(&self.percent) > 0 && count(&self.per_mille) > 0 {
+            error = Some(EvaluationError::PicturePercentAndPerMille);
+        }
+        if !parts.mantissa.iter().any(|c| self.is_digit(*c)) {
+            error = Some(EvaluationError::PictureMantissaWithoutDigits);
+        }
+        if parts.active.iter().any(|c| !self.is_active(*c)) {
+            error = Some(EvaluationError::PicturePassiveBetweenActive);
+        }
+
+        match picture.iter().position(|c| *c == self.decimal_separator) {
+            Some(position) => {
+                let before = position.checked_sub(1).map(|before| picture[before]);
+                let after = picture.get(position + 1).copied();
+                if before == Some(self.grouping_separator) || after == Some(self.grouping_separator)
+                {
+                    error = Some(EvaluationError::PictureGroupingAdjacentToDecimal);
+                }
+            }
+            None => {
+                if parts.integer.last() == Some(&self.grouping_separator) {
+                    error = Some(EvaluationError::PictureGroupingAtEndOfInteger);
+                }
+            }
+        }
+        if picture
+            .windows(2)
+            .any(|pair| pair[0] == self.grouping_separator && pair[1] == self.grouping_separator)
+        {
+            error = Some(EvaluationError::PictureAdjacentGroupings);
+        }
+
+        if let Some(position) = parts.integer.iter().position(|c| *c == self.digit) {
+            if parts.integer[..position]
+                .iter()
+                .any(|c| self.is_decimal_digit(*c))
+            {
+                error = Some(EvaluationError::PictureOptionalAfterMandatoryInteger);
+            }
+        }
+        if let Some(position) = parts.fraction.iter().rposition(|c| *c == self.digit) {
+            if parts.fraction[position..]
+                .iter()
+                .any(|c| self.is_decimal_digit(*c))
+            {
+                error = Some(EvaluationError::PictureMandatoryAfterOptionalFraction);
+            }
+        }
+
+        if let Some(exponent) = &parts.exponent {
+            if !exponent.is_empty() && (count(&self.percent) > 0 || count(&self.per_mille) > 0) {
+                error = Some(EvaluationError::PicturePercentWithExponent);
+            }
+            if exponent.is_empty() || exponent.iter().any(|c| !self.is_decimal_digit(*c)) {
+                error = Some(EvaluationError::PictureExponentNotDigits);
+            }
+        }
+
+        match error {
+            Some(error) => Err(error),
+            None => Ok(()),
+        }
+    }
+
+    fn analyse(&self, parts: SubPicture) -> PictureVariables {
+        let count_digits = |chars: &[char]| chars.iter().filter(|c| self.is_digit(**c)).count();
+        let count_decimal_digits =
+            |chars: &[char]| chars.iter().filter(|c| self.is_decimal_digit(**c)).count();
+        let separators = |chars: &[char]| -> Vec<usize> {
+            chars
+                .iter()
+                .enumerate()
+                .filter(|(_, c)| **c == self.grouping_separator)
+                .map(|(position, _)| position)
+                .collect()
+        };
+
+        // the number of digits to the right of each grouping separator of the
+        // integer part, and to the left of each of the fractional part
+        let integer_grouping: Vec<usize> = separators(&parts.integer)
+            .into_iter()
+            .map(|position| count_digits(&parts.integer[position..]))
+            .collect();
+        let fraction_grouping: Vec<usize> = separators(&parts.fraction)
+            .into_iter()
+            .map(|position| count_digits(&parts.fraction[..position]))
+            .collect();
+
+        let mut min_integer_size = count_decimal_digits(&parts.integer);
+        let scaling_factor = min_integer_size;
+        let mut min_fraction_size = count_decimal_digits(&parts.fraction);
+        let mut max_fraction_size = count_digits(&parts.fraction);
+
+        let exponent_present = parts.exponent.is_some();
+        if min_integer_size == 0 && max_fraction_size == 0 {
+            if exponent_present {
+                min_fraction_size = 1;
+                max_fraction_size = 1;
+            } else {
+                min_integer_size = 1;
+            }
+        }
+        if exponent_present && min_integer_size == 0 && parts.integer.contains(&self.digit) {
+            min_integer_size = 1;
+        }
+        if min_integer_size == 0 && min_fraction_size == 0 {
+            min_fraction_size = 1;
+        }
+        let min_exponent_size = parts.exponent.as_deref().map_or(0, count_decimal_digits);
+
+        PictureVariables {
+            regular_grouping: DecimalFormat::regular_grouping(&integer_grouping),
+            integer_grouping,
+            fraction_grouping,
+            min_integer_size,
+            scaling_factor,
+            min_fraction_size,
+            max_fraction_size,
+            min_exponent_size,
+            picture: parts.picture,
+            prefix: parts.prefix,
+            suffix: parts.suffix,
+        }
+    }
+
+    /// Returns the size of the groups if the grouping separators are at regular
+    /// intervals, e.g. 3 for `#,###,##0`, otherwise 0
+    fn regular_grouping(positions: &[usize]) -> usize {
+        fn gcd(a: usize, b: usize) -> usize {
+            if b == 0 {
+                a
+            } else {
+                gcd(b, a % b)
+            }
+        }
+
+        let factor = match positions.iter().copied().reduce(gcd) {
+            Some(factor) if factor > 0 => factor,
+            _ => return 0,
+        };
+        let regular = (1..=positions.len()).all(|index| positions.contains(&(index * factor)));
+        if regular {
+            factor
+        } else {
+            0
+        }
+    }
+}
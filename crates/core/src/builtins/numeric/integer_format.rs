@@ -0,0 +1,643 @@
+use std::convert::TryFrom;
+
+use crate::evaluate::EvaluationError;
+
+const FEW: [&str; 20] = [
+    "Zero",
+    "One",
+    "Two",
+    "Three",
+    "Four",
+    "Five",
+    "Six",
+    "Seven",
+    "Eight",
+    "Nine",
+    "Ten",
+    "Eleven",
+    "Twelve",
+    "Thirteen",
+    "Fourteen",
+    "Fifteen",
+    "Sixteen",
+    "Seventeen",
+    "Eighteen",
+    "Nineteen",
+];
+
+const ORDINALS: [&str; 20] = [
+    "Zeroth",
+    "First",
+    "Second",
+    "Third",
+    "Fourth",
+    "Fifth",
+    "Sixth",
+    "Seventh",
+    "Eighth",
+    "Ninth",
+    "Tenth",
+    "Eleventh",
+    "Twelfth",
+    "Thirteenth",
+    "Fourteenth",
+    "Fifteenth",
+    "Sixteenth",
+    "Seventeenth",
+    "Eighteenth",
+    "Nineteenth",
+];
+
+const DECADES: [&str; 9] = [
+    "Twenty", "Thirty", "Forty", "Fifty", "Sixty", "Seventy", "Eighty", "Ninety", "Hundred",
+];
+
+const MAGNITUDES: [&str; 4] = ["Thousand", "Million", "Billion", "Trillion"];
+
+const ROMAN_NUMERALS: [(u64, &str); 13] = [
+    (1000, "m"),
+    (900, "cm"),
+    (500, "d"),
+    (400, "cd"),
+    (100, "c"),
+    (90, "xc"),
+    (50, "l"),
+    (40, "xl"),
+    (10, "x"),
+    (9, "ix"),
+    (5, "v"),
+    (4, "iv"),
+    (1, "i"),
+];
+
+/// Largest number which is written in Roman numerals, which repeat `m` for
+/// each thousand
+const ROMAN_MAX: u64 = 9_999_999;
+
+/// The zero digits of the Unicode decimal digit groups which may be used in a
+/// decimal digit pattern
+const DECIMAL_GROUPS: [u32; 37] = [
+    0x30, 0x0660, 0x06F0, 0x07C0, 0x0966, 0x09E6, 0x0A66, 0x0AE6, 0x0B66, 0x0BE6, 0x0C66, 0x0CE6,
+    0x0D66, 0x0DE6, 0x0E50, 0x0ED0, 0x0F20, 0x1040, 0x1090, 0x17E0, 0x1810, 0x1946, 0x19D0, 0x1A80,
+    0x1A90, 0x1B50, 0x1BB0, 0x1C40, 0x1C50, 0xA620, 0xA8D0, 0xA900, 0xA9D0, 0xA9F0, 0xAA50, 0xABF0,
+    0xFF10,
+];
+
+/// An integer picture string of `$formatInteger` and `$parseInteger`, see
+/// [fn:format-integer](https://www.w3.org/TR/xpath-functions-31/#func-format-integer)
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) struct IntegerFormat {
+    primary: Primary,
+    case: Case,
+    ordinal: bool,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum Primary {
+    /// A decimal digit pattern, e.g. `#,##0`
+    Decimal(DigitPattern),
+    /// `A` or `a`, i.e. a, b, ..., z, aa, ab, ...
+    Letters,
+    /// `I` or `i`
+    Roman,
+    /// `W`, `w` or `Ww`
+    Words,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Case {
+    Upper,
+    Lower,
+    Title,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+struct DigitPattern {
+    /// The zero digit of the decimal digit group of the pattern
+    zero: u32,
+    mandatory_digits: usize,
+    optional_digits: usize,
+    grouping: Grouping,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum Grouping {
+    /// The same separator after every `size` digits, e.g. `#,##0`
+    Regular { size: usize, separator: char },
+    /// Separators after the given numbers of digits from the right
+    Irregular(Vec<(usize, char)>),
+}
+
+impl IntegerFormat {
+    /// Analyses a picture string, which is a primary format token optionally
+    /// followed by `;` and a format modifier, where `o` requests ordinals
+    pub fn from_picture(picture: &str) -> Result<Self, EvaluationError> {
+        let (primary, modifier) = match picture.rfind(';') {
+            Some(position) => (&picture[..position], Some(&picture[position + 1..])),
+            None => (picture, None),
+        };
+        let ordinal = modifier.is_some_and(|modifier| modifier.starts_with('o'));
+
+        let (primary, case) = match primary {
+            "A" => (Primary::Letters, Case::Upper),
+            "a" => (Primary::Letters, Case::Lower),
+            "I" => (Primary::Roman, Case::Upper),
+            "i" => (Primary::Roman, Case::Lower),
+            "W" => (Primary::Words, Case::Upper),
+            "Ww" => (Primary::Words, Case::Title),
+            "w" => (Primary::Words, Case::Lower),
+            _ => (
+                Primary::Decimal(DigitPattern::from_picture(primary)?),
+                Case::Lower,
+            ),
+        };
+
+        Ok(Self {
+            primary,
+            case,
+            ordinal,
+        })
+    }
+
+    /// Formats an integer, which is an error if its magnitude does not fit in
+    /// a u64, or is above [`ROMAN_MAX`] for Roman numerals
+    pub fn format(&self, value: i128) -> Result<String, EvaluationError> {
+        let magnitude = u64::try_from(value.unsigned_abs())
+            .map_err(|_| EvaluationError::NumberOutOfRange(value as f64))?;
+        let formatted = match &self.primary {
+            Primary::Decimal(pattern) => pattern.format(magnitude, self.ordinal),
+            Primary::Letters => to_letters(magnitude, self.case == Case::Upper),
+            Primary::Roman if magnitude > ROMAN_MAX => {
+                return Err(EvaluationError::NumberOutOfRange(value as f64))
+            }
+            Primary::Roman => match self.case {
+                Case::Upper => to_roman(magnitude).to_uppercase(),
+                _ => to_roman(magnitude),
+            },
+            Primary::Words => {
+                let words = to_words(magnitude, self.ordinal);
+                match self.case {
+                    Case::Upper => words.to_uppercase(),
+                    Case::Lower => words.to_lowercase(),
+                    Case::Title => words,
+                }
+            }
+        };
+
+        if value < 0 {
+            Ok(format!("-{}", formatted))
+        } else {
+            Ok(formatted)
+        }
+    }
+
+    /// The number of mandatory digits of a decimal digit pattern, or 0 for other formats
+    pub fn mandatory_digits(&self) -> usize {
+        match &self.primary {
+            Primary::Decimal(pattern) => pattern.mandatory_digits,
+            _ => 0,
+        }
+    }
+
+    /// The number of optional digits (`#`) of a decimal digit pattern, or 0 for other formats
+    pub fn optional_digits(&self) -> usize {
+        match &self.primary {
+            Primary::Decimal(pattern) => pattern.optional_digits,
+            _ => 0,
+        }
+    }
+
+    /// Sets the number of mandatory digits of a decimal digit pattern, e.g. from
+    /// the width modifier of a date picture, and does nothing for other formats
+    pub fn set_mandatory_digits(&mut self, digits: usize) {
+        if let Primary::Decimal(pattern) = &mut self.primary {
+            pattern.mandatory_digits = digits;
+        }
+    }
+
+    /// The grouping separator of a decimal digit pattern whose separators are
+    /// regularly spaced, e.g. `:` for `01:01`
+    pub fn regular_separator(&self) -> Option<char> {
+        match &self.primary {
+            Primary::Decimal(DigitPattern {
+                grouping: Grouping::Regular { separator, .. },
+                ..
+            }) => Some(*separator),
+            _ => None,
+        }
+    }
+
+    /// A regular expression matching the strings formatted with this picture,
+    /// where a decimal number without grouping separators has between `digits.0`
+    /// and `digits.1` digits, if given
+    pub fn regex(&self, digits: Option<(usize, usize)>) -> String {
+        let mut regex = match &self.primary {
+            Primary::Decimal(pattern) => pattern.regex(digits),
+            Primary::Letters if self.case == Case::Upper => "[A-Z]+".into(),
+            Primary::Letters => "[a-z]+".into(),
+            Primary::Roman if self.case == Case::Upper => "[MDCLXVI]+".into(),
+            Primary::Roman => "[mdclxvi]+".into(),
+            Primary::Words => {
+                let mut words = word_list();
+                // longest first, so that e.g. `seventeen` is not matched as `seven`
+                words.sort_by_key(|word| std::cmp::Reverse(word.len()));
+                format!("(?:{}|and|[-, ])+", words.join("|"))
+            }
+        };
+        if self.ordinal {
+            if let Primary::Decimal(_) = self.primary {
+                regex.push_str("(?:st|nd|rd|th)");
+            }
+        }
+        regex
+    }
+
+    /// Parses a string formatted with this picture, the inverse of [`IntegerFormat::format`]
+    pub fn parse(&self, s: &str) -> Option<i64> {
+        let (negative, s) = match s.strip_prefix('-') {
+            Some(s) => (true, s),
+            None => (false, s),
+        };
+        let magnitude = match &self.primary {
+            Primary::Decimal(pattern) => pattern.parse(s, self.ordinal),
+            Primary::Letters => from_letters(s, self.case == Case::Upper),
+            Primary::Roman => match self.case {
+                Case::Upper => from_roman(s),
+                _ => from_roman(&s.to_uppercase()),
+            },
+            Primary::Words => from_words(s),
+        }?;
+
+        if negative {
+            0_i64.checked_sub_unsigned(magnitude)
+        } else {
+            i64::try_from(magnitude).ok()
+        }
+    }
+}
+
+impl DigitPattern {
+    fn from_picture(picture: &str) -> Result<Self, EvaluationError> {
+        let mut zero = None;
+        let mut mandatory_digits = 0;
+        let mut optional_digits = 0;
+        let mut position = 0;
+        let mut separators = Vec::new();
+
+        // from the right, so that separators are positioned by the digits after them
+        for c in picture.chars().rev() {
+            if let Some(group) = decimal_group(c) {
+                mandatory_digits += 1;
+                position += 1;
+                match zero {
+                    None => zero = Some(group),
+                    Some(zero) if zero != group => {
+                        return Err(EvaluationError::MixedDecimalDigitGroups)
+                    }
+                    Some(_) => {}
+                }
+            } else if c == '#' {
+                optional_digits += 1;
+                position += 1;
+            } else {
+                separators.push((position, c));
+            }
+        }
+
+        // a picture without any decimal digits is a numbering sequence, e.g. `α`
+        let zero =
+            zero.ok_or_else(|| EvaluationError::UnsupportedIntegerSequence(picture.into()))?;
+
+        Ok(Self {
+            zero,
+            mandatory_digits,
+            optional_digits,
+            grouping: Grouping::from_separators(separators),
+        })
+    }
+
+    fn format(&self, magnitude: u64, ordinal: bool) -> String {
+        let digits = format!("{:0>width$}", magnitude, width = self.mandatory_digits);
+        let mut digits: Vec<char> = digits
+            .chars()
+            .map(|c| {
+                let offset = c as u32 - '0' as u32;
+                char::from_u32(self.zero + offset).unwrap_or(c)
+            })
+            .collect();
+
+        match &self.grouping {
+            Grouping::Regular { size, separator } => {
+                let groups = (digits.len() - 1) / size;
+                for group in (1..=groups).rev() {
+                    let position = digits.len() - group * size;
+                    digits.insert(position, *separator);
+                }
+            }
+            Grouping::Irregular(separators) => {
+                // leftmost first, so that the positions from the right stay valid
+                for (position, separator) in separators.iter().rev() {
+                    if 0 < *position && *position < digits.len() {
+                        let position = digits.len() - position;
+                        digits.insert(position, *separator);
+                    }
+                }
+            }
+        }
+
+        let mut formatted: String = digits.into_iter().collect();
+        if ordinal {
+            formatted.push_str(ordinal_suffix(&formatted));
+        }
+        formatted
+    }
+
+    fn parse(&self, s: &str, ordinal: bool) -> Option<u64> {
+        let s = if ordinal {
+            ["st", "nd", "rd", "th"]
+                .iter()
+                .find_map(|suffix| s.strip_suffix(suffix))?
+        } else {
+            s
+        };
+
+        let separators: Vec<char> = match &self.grouping {
+            Grouping::Regular { separator, .. } => vec![*separator],
+            Grouping::Irregular(separators) => separators.iter().map(|(_, c)| *c).collect(),
+        };
+        let digits: Option<String> = s
+            .chars()
+            .filter(|c| !separators.contains(c))
+            .map(|c| {
+                let offset = (c as u32).checked_sub(self.zero).filter(|d| *d < 10)?;
+                char::from_digit(offset, 10)
+            })
+            .collect();
+
+        digits?.parse().ok()
+    }
+
+    fn regex(&self, digits: Option<(usize, usize)>) -> String {
+        let zero = char::from_u32(self.zero).unwrap_or('0');
+        let nine = char::from_u32(self.zero + 9).unwrap_or('9');
+        let separators: String = match &self.grouping {
+            Grouping::Regular { separator, .. } => regex::escape(&separator.to_string()),
+            Grouping::Irregular(separators) => separators
+                .iter()
+                .map(|(_, c)| regex::escape(&c.to_string()))
+                .collect(),
+        };
+
+        if !separators.is_empty() {
+            format!("[{}-{}{}]+", zero, nine, separators)
+        } else {
+            match digits {
+                Some((min, max)) => format!("[{}-{}]{{{},{}}}", zero, nine, min, max),
+                None => format!("[{}-{}]+", zero, nine),
+            }
+        }
+    }
+}
+
+impl Grouping {
+    /// Separators are regular if they are all the same character and are
+    /// positioned at every multiple of a group size
+    fn from_separators(separators: Vec<(usize, char)>) -> Self {
+        fn gcd(a: usize, b: usize) -> usize {
+            if b == 0 {
+                a
+            } else {
+                gcd(b, a % b)
+            }
+        }
+
+        if let Some((_, separator)) = separators.first().copied() {
+            let size = separators
+                .iter()
+                .map(|(position, _)| *position)
+                .reduce(gcd)
+                .unwrap_or(0);
+            let same_character = separators.iter().all(|(_, c)| *c == separator);
+            let positions: Vec<usize> = separators.iter().map(|(position, _)| *position).collect();
+            let regular =
+                size > 0 && (1..=positions.len()).all(|index| positions.contains(&(index * size)));
+            if same_character && regular {
+                return Grouping::Regular { size, separator };
+            }
+        }
+        Grouping::Irregular(separators)
+    }
+}
+
+fn decimal_group(c: char) -> Option<u32> {
+    let code = c as u32;
+    DECIMAL_GROUPS
+        .iter()
+        .copied()
+        .find(|zero| (*zero..*zero + 10).contains(&code))
+}
+
+/// The English suffix of an ordinal number, e.g. `st` for 1 and 21 but `th` for 11
+fn ordinal_suffix(digits: &str) -> &'static str {
+    let mut chars = digits.chars().rev();
+    let last = chars.next();
+    let tens = chars.next();
+    if tens == Some('1') {
+        return "th";
+    }
+    match last {
+        Some('1') => "st",
+        Some('2') => "nd",
+        Some('3') => "rd",
+        _ => "th",
+    }
+}
+
+/// Writes a number in English words in title case, e.g. `Two Thousand, Seven
+/// Hundred and Eighty-Nine`
+fn to_words(value: u64, ordinal: bool) -> String {
+    fn lookup(num: u64, prev: bool, ordinal: bool) -> String {
+        let mut words = String::new();
+        if num <= 19 {
+            if prev {
+                words.push_str(" and ");
+            }
+            let table = if ordinal { &ORDINALS } else { &FEW };
+            words.push_str(table[num as usize]);
+        } else if num < 100 {
+            if prev {
+                words.push_str(" and ");
+            }
+            words.push_str(DECADES[(num / 10) as usize - 2]);
+            let remainder = num % 10;
+            if remainder > 0 {
+                words.push('-');
+                words.push_str(&lookup(remainder, false, ordinal));
+            } else if ordinal {
+                // e.g. Twenty => Twentieth
+                words.pop();
+                words.push_str("ieth");
+            }
+        } else if num < 1000 {
+            if prev {
+                words.push_str(", ");
+            }
+            words.push_str(FEW[(num / 100) as usize]);
+            words.push_str(" Hundred");
+            let remainder = num % 100;
+            if remainder > 0 {
+                words.push_str(&lookup(remainder, true, ordinal));
+            } else if ordinal {
+                words.push_str("th");
+            }
+        } else {
+            // beyond trillions, the mantissa is itself a large number
+            let magnitude = ((num.ilog10() / 3) as usize).min(MAGNITUDES.len());
+            let factor = 10_u64.pow(magnitude as u32 * 3);
+            let mantissa = num / factor;
+            let remainder = num % factor;
+            if prev {
+                words.push_str(", ");
+            }
+            words.push_str(&lookup(mantissa, false, false));
+            words.push(' ');
+            words.push_str(MAGNITUDES[magnitude - 1]);
+            if remainder > 0 {
+                words.push_str(&lookup(remainder, true, ordinal));
+            } else if ordinal {
+                words.push_str("th");
+            }
+        }
+        words
+    }
+
+    lookup(value, false, ordinal)
+}
+
+fn word_value(word: &str) -> Option<u64> {
+    let is = |candidate: &str| candidate.eq_ignore_ascii_case(word);
+
+    if let Some(index) = FEW.iter().position(|w| is(w)) {
+        return Some(index as u64);
+    }
+    if let Some(index) = ORDINALS.iter().position(|w| is(w)) {
+        return Some(index as u64);
+    }
+    for (index, decade) in DECADES.iter().enumerate() {
+        let ordinal = format!("{}ieth", &decade[..decade.len() - 1]);
+        if is(decade) || is(&ordinal) {
+            return Some((index as u64 + 2) * 10);
+        }
+    }
+    if is("Hundredth") {
+        return Some(100);
+    }
+    for (index, magnitude) in MAGNITUDES.iter().enumerate() {
+        if is(magnitude) || is(&format!("{}th", magnitude)) {
+            return Some(1000_u64.pow(index as u32 + 1));
+        }
+    }
+    None
+}
+
+/// All of the words which may be written by [`to_words`], in lowercase
+fn word_list() -> Vec<String> {
+    let mut words: Vec<String> = FEW
+        .iter()
+        .chain(ORDINALS.iter())
+        .chain(DECADES.iter())
+        .chain(MAGNITUDES.iter())
+        .map(|word| word.to_lowercase())
+        .collect();
+    for decade in DECADES {
+        words.push(format!("{}ieth", &decade[..decade.len() - 1]).to_lowercase());
+    }
+    for magnitude in MAGNITUDES {
+        words.push(format!("{}th", magnitude).to_lowercase());
+    }
+    words.push("hundredth".into());
+    words
+}
+
+/// Parses a number written in English words, in any case
+fn from_words(s: &str) -> Option<u64> {
+    let s = s.replace(", ", " ").replace('-', " ");
+    let mut segments = vec![0_u64];
+    for word in s
+        .split_whitespace()
+        .filter(|word| !word.eq_ignore_ascii_case("and"))
+    {
+        let value = word_value(word)?;
+        let top = segments.pop().unwrap_or(0);
+        if value < 100 {
+            // a new segment starts after a magnitude, e.g. the `two` of `one thousand, two hundred`
+            if top >= 1000 {
+                segments.push(top);
+                segments.push(value);
+            } else {
+                segments.push(top + value);
+            }
+        } else {
+            segments.push(top.checked_mul(value)?);
+        }
+    }
+    segments
+        .into_iter()
+        .try_fold(0_u64, |sum, segment| sum.checked_add(segment))
+}
+
+/// Writes a number in lowercase Roman numerals
+fn to_roman(mut value: u64) -> String {
+    let mut roman = String::new();
+    for (numeral_value, numeral) in ROMAN_NUMERALS {
+        while value >= numeral_value {
+            roman.push_str(numeral);
+            value -= numeral_value;
+        }
+    }
+    roman
+}
+
+/// Parses uppercase Roman numerals
+fn from_roman(roman: &str) -> Option<u64> {
+    let mut decimal: i128 = 0;
+    let mut max = 1;
+    for digit in roman.chars().rev() {
+        let value = match digit {
+            'M' => 1000,
+            'D' => 500,
+            'C' => 100,
+            'L' => 50,
+            'X' => 10,
+            'V' => 5,
+            'I' => 1,
+            _ => return None,
+        };
+        if value < max {
+            decimal -= value;
+        } else {
+            max = value;
+            decimal += value;
+        }
+    }
+    u64::try_from(decimal).ok()
+}
+
+/// Writes a number as letters, i.e. 1 is `a`, 26 is `z` and 27 is `aa`
+fn to_letters(mut value: u64, upper: bool) -> String {
+    let a = if upper { b'A' } else { b'a' };
+    let mut letters = Vec::new();
+    while value > 0 {
+        letters.push((a + ((value - 1) % 26) as u8) as char);
+        value = (value - 1) / 26;
+    }
+    letters.iter().rev().collect()
+}
+
+fn from_letters(letters: &str, upper: bool) -> Option<u64> {
+    let a = if upper { 'A' } else { 'a' };
+    letters.chars().try_fold(0_u64, |decimal, letter| {
+        let offset = (letter as u32).checked_sub(a as u32).filter(|d| *d < 26)?;
+        decimal.checked_mul(26)?.checked_add(offset as u64 + 1)
+    })
+}
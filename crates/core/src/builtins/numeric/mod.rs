@@ -1,11 +1,17 @@
-use serde_json::Value;
+use std::convert::TryFrom;
+
+use serde_json::{Number, Value};
 
 use crate::{
-    evaluate::{EvaluationError, EvaluationResult},
+    evaluate::{EvaluationError, EvaluationResult, RandomSource},
     value::{number::JSONataNumber, JSONataValue},
 };
 
-use super::BuiltIns;
+use self::{decimal_format::DecimalFormat, integer_format::IntegerFormat};
+use super::{arg, number_arg, string_arg, BuiltIns};
+
+mod decimal_format;
+pub(super) mod integer_format;
 
 #[cfg(test)]
 mod tests;
@@ -30,8 +36,42 @@ impl BuiltIns {
     /// ```
     /// **Signature**: `$number(arg)`
     pub(super) fn number(args: &[Option<JSONataValue>]) -> EvaluationResult {
-        let arg = args.get(0).unwrap();
-        todo!()
+        let arg = match arg(args, 0) {
+            Some(arg) => arg,
+            None => return Ok(None),
+        };
+        match arg {
+            JSONataValue::Value(Value::Number(n)) => Ok(Some(Value::Number(n.clone()).into())),
+            JSONataValue::Value(Value::Bool(b)) => Ok(Some((*b as u64).into())),
+            JSONataValue::Value(Value::String(s)) => match BuiltIns::parse_number(s) {
+                Some(n) => Ok(Some(Value::Number(n).into())),
+                None => Err(EvaluationError::CannotConvertToNumber(s.as_str().into())),
+            },
+            JSONataValue::Value(val) => Err(EvaluationError::CannotConvertToNumber(val.clone())),
+            JSONataValue::Function(func) => {
+                Err(EvaluationError::CannotConvertToNumber(func.ident().into()))
+            }
+        }
+    }
+
+    /// Parses a string which represents a legal JSON number, or an integer
+    /// in hexadecimal (`0x`), octal (`0o`) or binary (`0b`) notation
+    fn parse_number(s: &str) -> Option<Number> {
+        let radix = match s.get(..2) {
+            Some("0x") | Some("0X") => Some(16),
+            Some("0o") | Some("0O") => Some(8),
+            Some("0b") | Some("0B") => Some(2),
+            _ => None,
+        };
+        if let Some(radix) = radix {
+            return u64::from_str_radix(&s[2..], radix).ok().map(Number::from);
+        }
+
+        // the JSON grammar allows surrounding whitespace
+        if s.trim() != s {
+            return None;
+        }
+        serde_json::from_str(s).ok()
     }
 
     /// Returns the absolute value of the `number` parameter, i.e. if the number is negative,
@@ -48,25 +88,7 @@ impl BuiltIns {
     /// ```
     /// **Signature**: `$abs(number)`
     pub(super) fn abs(args: &[Option<JSONataValue>]) -> EvaluationResult {
-        let number = args.get(0).unwrap(); // arg will exist
-        if let Some(number) = number {
-            match number {
-                JSONataValue::Value(val) => match val {
-                    Value::Number(n) => {
-                        let n: JSONataNumber = n.into();
-                        Ok(Some(n.abs().into()))
-                    }
-                    _ => Err(EvaluationError::function_invalid_argument(
-                        "abs", 1, "number",
-                    )),
-                },
-                JSONataValue::Function(_) => Err(EvaluationError::function_invalid_argument(
-                    "abs", 1, "number",
-                )),
-            }
-        } else {
-            Ok(None)
-        }
+        Ok(number_arg(args, 0).map(|number| number.abs().into()))
     }
 
     /// Returns the value of `number` rounded down to the nearest integer that is smaller or
@@ -84,26 +106,8 @@ impl BuiltIns {
     /// $floor(-5.3) => -6
     /// ```
     /// **Signature**: `$floor(number)`
-    pub(super) fn floor(args: &[JSONataValue]) -> EvaluationResult {
-        let number = args.get(0);
-        if let Some(number) = number {
-            match number {
-                JSONataValue::Value(val) => match val {
-                    Value::Number(n) => {
-                        let n: JSONataNumber = n.into();
-                        Ok(Some(n.floor().into()))
-                    }
-                    _ => Err(EvaluationError::function_invalid_argument(
-                        "floor", 1, "number",
-                    )),
-                },
-                JSONataValue::Function(_) => Err(EvaluationError::function_invalid_argument(
-                    "floor", 1, "number",
-                )),
-            }
-        } else {
-            Ok(None)
-        }
+    pub(super) fn floor(args: &[Option<JSONataValue>]) -> EvaluationResult {
+        Ok(number_arg(args, 0).map(|number| number.floor().into()))
     }
 
     /// Returns the value of `number` rounded up to the nearest integer that is greater
@@ -122,7 +126,7 @@ impl BuiltIns {
     /// ```
     /// **Signature**: `$ceil(number)`
     pub(super) fn ceil(args: &[Option<JSONataValue>]) -> EvaluationResult {
-        todo!()
+        Ok(number_arg(args, 0).map(|number| number.ceil().into()))
     }
 
     /// Returns the value of the `number` parameter rounded to the number of decimal places
@@ -152,7 +156,12 @@ impl BuiltIns {
     /// ```
     /// **Signature**: `$round(number [, precision])`
     pub(super) fn round(args: &[Option<JSONataValue>]) -> EvaluationResult {
-        todo!()
+        let number = match number_arg(args, 0) {
+            Some(number) => number,
+            None => return Ok(None),
+        };
+        let precision = number_arg(args, 1).map_or(0, |precision| precision.as_f64() as i64);
+        Ok(Some(number.round(precision).into()))
     }
 
     /// Returns the value of base raised to the power of exponent (baseexponent).
@@ -170,7 +179,27 @@ impl BuiltIns {
     /// ```
     /// **Signature**: `$power(base, exponent)`
     pub(super) fn power(args: &[Option<JSONataValue>]) -> EvaluationResult {
-        todo!()
+        let (base, exponent) = match (number_arg(args, 0), number_arg(args, 1)) {
+            (Some(base), Some(exponent)) => (base, exponent),
+            _ => return Ok(None),
+        };
+
+        // integer powers stay exact while they fit
+        let base_int = Number::from(base.clone()).as_i64();
+        let exponent_int = Number::from(exponent.clone()).as_u64();
+        if let (Some(b), Some(e)) = (base_int, exponent_int) {
+            if let Some(result) = u32::try_from(e).ok().and_then(|e| b.checked_pow(e)) {
+                return Ok(Some(Value::from(result).into()));
+            }
+        }
+
+        let (base, exponent) = (base.as_f64(), exponent.as_f64());
+        let result = base.powf(exponent);
+        if result.is_finite() {
+            Ok(Some(result.into()))
+        } else {
+            Err(EvaluationError::PowerOutOfRange(base, exponent))
+        }
     }
 
     /// Returns the square root of the value of the number parameter.
@@ -188,7 +217,15 @@ impl BuiltIns {
     /// ```
     /// **Signature**: `$sqrt(number)`
     pub(super) fn sqrt(args: &[Option<JSONataValue>]) -> EvaluationResult {
-        todo!()
+        let number = match number_arg(args, 0) {
+            Some(number) => number.as_f64(),
+            None => return Ok(None),
+        };
+        if number < 0.0 {
+            Err(EvaluationError::SqrtOfNegative(number))
+        } else {
+            Ok(Some(number.sqrt().into()))
+        }
     }
 
     /// Returns a pseudo random number greater than or equal to zero and less than one (0 ≤ n < 1)
@@ -200,8 +237,11 @@ impl BuiltIns {
     /// $random() => 0.6558078550072
     /// ```
     /// **Signature**: `$random()`
-    pub(super) fn random(args: &[Option<JSONataValue>]) -> EvaluationResult {
-        todo!()
+    pub(super) fn random(
+        rng: &mut dyn RandomSource,
+        _args: &[Option<JSONataValue>],
+    ) -> EvaluationResult {
+        Ok(Some(rng.next_f64().into()))
     }
 
     /// Casts the `number` to a string and formats it to a decimal representation as
@@ -233,7 +273,17 @@ impl BuiltIns {
     /// ```
     /// **Signature**: `$formatNumber(number, picture [, options])`
     pub(super) fn format_number(args: &[Option<JSONataValue>]) -> EvaluationResult {
-        todo!()
+        let (number, picture) = match (number_arg(args, 0), string_arg(args, 1)) {
+            (Some(number), Some(picture)) => (number, picture),
+            _ => return Ok(None),
+        };
+        let format = match arg(args, 2).and_then(|options| options.as_value()?.as_object()) {
+            Some(options) => DecimalFormat::with_options(options),
+            None => DecimalFormat::default(),
+        };
+
+        let formatted = format.format(number.as_f64(), picture)?;
+        Ok(Some(formatted.into()))
     }
 
     /// Casts the `number` to a string and formats it to an integer represented in the
@@ -248,7 +298,20 @@ impl BuiltIns {
     /// ```
     /// **Signature**: `$formatBase(number [, radix])`
     pub(super) fn format_base(args: &[Option<JSONataValue>]) -> EvaluationResult {
-        todo!()
+        let number = match number_arg(args, 0) {
+            Some(number) => number,
+            None => return Ok(None),
+        };
+        let radix = match number_arg(args, 1) {
+            Some(radix) => radix.round(0),
+            None => 10.into(),
+        };
+        let radix_f = radix.as_f64();
+        if !(2.0..=36.0).contains(&radix_f) {
+            return Err(EvaluationError::InvalidRadix(radix.to_value()));
+        }
+
+        Ok(Some(number.to_string_radix(radix_f as u32)?.into()))
     }
 
     /// Casts the `number` to a string and formats it to an integer representation as
@@ -267,7 +330,27 @@ impl BuiltIns {
     /// ```
     /// **Signature**: `$formatInteger(number, picture)`
     pub(super) fn format_integer(args: &[Option<JSONataValue>]) -> EvaluationResult {
-        todo!()
+        let (number, picture) = match (number_arg(args, 0), string_arg(args, 1)) {
+            (Some(number), Some(picture)) => (number, picture),
+            _ => return Ok(None),
+        };
+        let format = IntegerFormat::from_picture(picture)?;
+
+        // integers are kept exact, a float is exact once floored if it is
+        // within the range of a u64, which is checked by the format
+        let value = number.floor().to_value();
+        let value = match (value.as_i64(), value.as_u64()) {
+            (Some(i), _) => i128::from(i),
+            (_, Some(u)) => i128::from(u),
+            _ => {
+                let f = value.as_f64().unwrap_or_default();
+                if f.abs() >= u64::MAX as f64 {
+                    return Err(EvaluationError::NumberOutOfRange(f));
+                }
+                f as i128
+            }
+        };
+        Ok(Some(format.format(value)?.into()))
     }
 
     /// Parses the contents of the `string` parameter to an integer (as a JSON number)
@@ -284,6 +367,14 @@ impl BuiltIns {
     /// ```
     /// **Signature**: `$parseInteger(string, picture)`
     pub(super) fn parse_integer(args: &[Option<JSONataValue>]) -> EvaluationResult {
-        todo!()
+        let (s, picture) = match (string_arg(args, 0), string_arg(args, 1)) {
+            (Some(s), Some(picture)) => (s, picture),
+            _ => return Ok(None),
+        };
+        let format = IntegerFormat::from_picture(picture)?;
+
+        Ok(format
+            .parse(s)
+            .map(|value| JSONataNumber::from(value).into()))
     }
 }
@@ -1,9 +1,37 @@
+use proptest::prelude::*;
+use rand::SeedableRng;
+use rand_chacha::ChaCha8Rng;
 use serde_json::json;
 
-use crate::{builtins::BuiltIns, value::JSONataValue};
+use crate::{
+    builtins::BuiltIns,
+    evaluate::{EvaluationError, EvaluationResult},
+    tests::make_val,
+    value::JSONataValue,
+};
 
 #[test]
-fn number() {}
+fn number() {
+    let cases = vec![
+        (json!(5), json!(5)),
+        (json!("5"), json!(5)),
+        (json!("-1.5e2"), json!(-150.0)),
+        (json!("0x1F"), json!(31)),
+        (json!("0b101"), json!(5)),
+        (json!(true), json!(1)),
+        (json!(false), json!(0)),
+    ];
+    for (input, expected) in cases {
+        let actual = BuiltIns::number(&[Some(make_val(input))]);
+        assert_eq!(actual, Ok(Some(make_val(expected))));
+    }
+
+    for input in [json!("five"), json!(" 5"), json!("05"), json!("1e999")] {
+        let actual = BuiltIns::number(&[Some(make_val(input.clone()))]);
+        assert_eq!(actual, Err(EvaluationError::CannotConvertToNumber(input)));
+    }
+    assert_eq!(BuiltIns::number(&[None]), Ok(None));
+}
 
 #[test]
 fn abs() {
@@ -28,31 +56,342 @@ fn abs() {
 }
 
 #[test]
-fn floor() {}
+fn floor() {
+    let cases = vec![
+        (json!(5), json!(5)),
+        (json!(5.3), json!(5)),
+        (json!(5.8), json!(5)),
+        (json!(-5.3), json!(-6)),
+    ];
+    for (input, expected) in cases {
+        let actual = BuiltIns::floor(&[Some(make_val(input))]);
+        assert_eq!(actual, Ok(Some(make_val(expected))));
+    }
+}
 
 #[test]
-fn ceil() {}
+fn ceil() {
+    let cases = vec![
+        (json!(5), json!(5)),
+        (json!(5.3), json!(6)),
+        (json!(-5.3), json!(-5)),
+    ];
+    for (input, expected) in cases {
+        let actual = BuiltIns::ceil(&[Some(make_val(input))]);
+        assert_eq!(actual, Ok(Some(make_val(expected))));
+    }
+}
 
 #[test]
-fn round() {}
+fn round() {
+    let cases = vec![
+        (json!(123.456), None, json!(123)),
+        (json!(123.456), Some(json!(2)), json!(123.46)),
+        (json!(123.456), Some(json!(-1)), json!(120)),
+        (json!(11.5), None, json!(12)),
+        (json!(12.5), None, json!(12)),
+        (json!(125), Some(json!(-1)), json!(120)),
+    ];
+    for (number, precision, expected) in cases {
+        let actual = BuiltIns::round(&[Some(make_val(number)), precision.map(make_val)]);
+        assert_eq!(actual, Ok(Some(make_val(expected))));
+    }
+}
 
 #[test]
-fn power() {}
+fn power() {
+    let cases = vec![
+        (json!(2), json!(8), json!(256)),
+        (json!(-3), json!(3), json!(-27)),
+        (json!(2), json!(0.5), json!(2_f64.sqrt())),
+        (json!(2), json!(-2), json!(0.25)),
+    ];
+    for (base, exponent, expected) in cases {
+        let actual = BuiltIns::power(&[Some(make_val(base)), Some(make_val(exponent))]);
+        assert_eq!(actual, Ok(Some(make_val(expected))));
+    }
+
+    assert_eq!(
+        BuiltIns::power(&[Some(make_val(json!(10))), Some(make_val(json!(400)))]),
+        Err(EvaluationError::PowerOutOfRange(10.0, 400.0))
+    );
+    assert!(BuiltIns::power(&[Some(make_val(json!(-8))), Some(make_val(json!(0.5)))]).is_err());
+}
 
 #[test]
-fn sqrt() {}
+fn sqrt() {
+    assert_eq!(
+        BuiltIns::sqrt(&[Some(make_val(json!(4)))]),
+        Ok(Some(make_val(json!(2))))
+    );
+    assert_eq!(
+        BuiltIns::sqrt(&[Some(make_val(json!(-1)))]),
+        Err(EvaluationError::SqrtOfNegative(-1.0))
+    );
+}
 
 #[test]
-fn random() {}
+fn random() {
+    let draw = |rng: &mut ChaCha8Rng| {
+        BuiltIns::random(rng, &[])
+            .unwrap()
+            .and_then(|n| n.as_value()?.as_f64())
+            .unwrap()
+    };
+
+    let mut rng = ChaCha8Rng::seed_from_u64(42);
+    let numbers: Vec<f64> = (0..100).map(|_| draw(&mut rng)).collect();
+    assert!(numbers.iter().all(|n| (0.0..1.0).contains(n)));
+    assert!(numbers.windows(2).any(|pair| pair[0] != pair[1]));
+
+    // the numbers drawn for a seed are fixed by the algorithm
+    assert_eq!(
+        numbers[..3],
+        [0.6818961923066714, 0.950275407672484, 0.4275164028565197]
+    );
+
+    // the same seed draws the same numbers
+    let mut rng = ChaCha8Rng::seed_from_u64(42);
+    assert_eq!(
+        (0..100).map(|_| draw(&mut rng)).collect::<Vec<_>>(),
+        numbers
+    );
+}
+
+fn format_number_with(
+    number: serde_json::Value,
+    picture: &str,
+    options: Option<serde_json::Value>,
+) -> Result<Option<JSONataValue>, EvaluationError> {
+    BuiltIns::format_number(&[
+        Some(make_val(number)),
+        Some(make_val(json!(picture))),
+        options.map(make_val),
+    ])
+}
 
 #[test]
-fn format_number() {}
+fn format_number() {
+    let cases = vec![
+        (json!(12345.6), "#,###.00", "12,345.60"),
+        (json!(1234.5678), "00.000e0", "12.346e2"),
+        (json!(34.555), "#0.00;(#0.00)", "34.56"),
+        (json!(-34.555), "#0.00;(#0.00)", "(34.56)"),
+        (json!(0.14), "01%", "14%"),
+        (json!(1234.5678), "#,##0.00", "1,234.57"),
+        (json!(-1234.5), "#,##0.0", "-1,234.5"),
+        (json!(1000000), "#,###", "1,000,000"),
+        (json!(1234567.89), "#,##,##0.00", "12,34,567.89"),
+        (json!(0.5), "#.00", ".50"),
+        (json!(0), "#", "0"),
+        (json!(0.234), "0.0e0", "2.3e-1"),
+        (json!(5), "Total: 000", "Total: 005"),
+    ];
+    for (number, picture, expected) in cases {
+        let actual = format_number_with(number, picture, None);
+        assert_eq!(actual, Ok(Some(make_val(json!(expected)))), "{}", picture);
+    }
+    assert_eq!(format_number_with(json!(null), "#", None), Ok(None));
+}
 
 #[test]
-fn format_base() {}
+fn format_number_options() {
+    let cases = vec![
+        (json!(0.14), "###pm", json!({"per-mille": "pm"}), "140pm"),
+        (
+            json!(1234.5678),
+            "\u{2460}\u{2460}.\u{2460}\u{2460}\u{2460}e\u{2460}",
+            json!({"zero-digit": "\u{245f}"}),
+            "\u{2460}\u{2461}.\u{2462}\u{2463}\u{2465}e\u{2461}",
+        ),
+        (
+            json!(1234.5),
+            "#.##0,00",
+            json!({"decimal-separator": ",", "grouping-separator": "."}),
+            "1.234,50",
+        ),
+        (json!(-5), "0", json!({"minus-sign": "~"}), "~5"),
+    ];
+    for (number, picture, options, expected) in cases {
+        let actual = format_number_with(number, picture, Some(options));
+        assert_eq!(actual, Ok(Some(make_val(json!(expected)))), "{}", picture);
+    }
+}
 
 #[test]
-fn format_integer() {}
+fn format_number_invalid_pictures() {
+    let cases = vec![
+        ("0;0;0", EvaluationError::PictureTooManySubPictures),
+        ("0.0.0", EvaluationError::PictureMultipleDecimalSeparators),
+        ("0%%", EvaluationError::PictureMultiplePercents),
+        (
+            "0\u{2030}\u{2030}",
+            EvaluationError::PictureMultiplePerMilles,
+        ),
+        ("0%\u{2030}", EvaluationError::PicturePercentAndPerMille),
+        ("%", EvaluationError::PictureMantissaWithoutDigits),
+        ("0 0", EvaluationError::PicturePassiveBetweenActive),
+        ("#,.00", EvaluationError::PictureGroupingAdjacentToDecimal),
+        ("#,", EvaluationError::PictureGroupingAtEndOfInteger),
+        ("#,,##0", EvaluationError::PictureAdjacentGroupings),
+        ("0#", EvaluationError::PictureOptionalAfterMandatoryInteger),
+        (
+            ".#0",
+            EvaluationError::PictureMandatoryAfterOptionalFraction,
+        ),
+        ("0e0%", EvaluationError::PicturePercentWithExponent),
+        ("0e#", EvaluationError::PictureExponentNotDigits),
+    ];
+    for (picture, expected) in cases {
+        let actual = format_number_with(json!(1), picture, None);
+        assert_eq!(actual, Err(expected), "{}", picture);
+    }
+}
+
+#[test]
+fn format_base() {
+    let cases = vec![
+        (json!(100), Some(json!(2)), "1100100"),
+        (json!(2555), Some(json!(16)), "9fb"),
+        (json!(-2555), Some(json!(16)), "-9fb"),
+        (json!(99.5), None, "100"),
+        (json!(10), Some(json!(2.4)), "1010"),
+    ];
+    for (number, radix, expected) in cases {
+        let actual = BuiltIns::format_base(&[Some(make_val(number)), radix.map(make_val)]);
+        assert_eq!(actual, Ok(Some(make_val(json!(expected)))));
+    }
+
+    for radix in [json!(1), json!(37), json!(36.6)] {
+        let actual = BuiltIns::format_base(&[Some(make_val(json!(10))), Some(make_val(radix))]);
+        assert!(matches!(actual, Err(EvaluationError::InvalidRadix(_))));
+    }
+    assert_eq!(BuiltIns::format_base(&[None, None]), Ok(None));
+}
+
+fn format_integer_with(number: serde_json::Value, picture: &str) -> EvaluationResult {
+    BuiltIns::format_integer(&[Some(make_val(number)), Some(make_val(json!(picture)))])
+}
+
+fn parse_integer_with(s: &str, picture: &str) -> EvaluationResult {
+    BuiltIns::parse_integer(&[Some(make_val(json!(s))), Some(make_val(json!(picture)))])
+}
 
 #[test]
-fn parse_integer() {}
+fn format_integer() {
+    let cases = vec![
+        (
+            json!(2789),
+            "w",
+            "two thousand, seven hundred and eighty-nine",
+        ),
+        (json!(1999), "I", "MCMXCIX"),
+        (json!(1999), "i", "mcmxcix"),
+        (json!(12), "W", "TWELVE"),
+        (json!(1000001), "Ww", "One Million and One"),
+        (json!(21), "w;o", "twenty-first"),
+        (json!(40), "w;o", "fortieth"),
+        (json!(100), "Ww;o", "One Hundredth"),
+        (json!(1), "A", "A"),
+        (json!(27), "a", "aa"),
+        (json!(702), "A", "ZZ"),
+        (json!(12345678), "#,##0", "12,345,678"),
+        (json!(1234567), "#,##,##0", "12,34,567"),
+        (json!(5), "000", "005"),
+        (json!(-5), "000", "-005"),
+        (json!(12.7), "0", "12"),
+        (json!(-12.2), "0", "-13"),
+        (json!(11), "0;o", "11th"),
+        (json!(22), "0;o", "22nd"),
+        (json!(103), "0;o", "103rd"),
+        (json!(u64::MAX), "0", "18446744073709551615"),
+        (json!(i64::MIN), "0", "-9223372036854775808"),
+        (json!(1e19), "#,##0", "10,000,000,000,000,000,000"),
+        (
+            json!(1234),
+            "#\u{66c}##\u{660}",
+            "\u{661}\u{66c}\u{662}\u{663}\u{664}",
+        ),
+    ];
+    for (number, picture, expected) in cases {
+        let actual = format_integer_with(number, picture);
+        assert_eq!(actual, Ok(Some(make_val(json!(expected)))), "{}", picture);
+    }
+
+    assert_eq!(
+        format_integer_with(json!(1), "\u{3b1}"),
+        Err(EvaluationError::UnsupportedIntegerSequence(
+            "\u{3b1}".into()
+        ))
+    );
+    assert_eq!(
+        format_integer_with(json!(1), "0\u{660}"),
+        Err(EvaluationError::MixedDecimalDigitGroups)
+    );
+
+    // numbers are not clamped to the range which can be formatted
+    for (number, picture) in [
+        (1e300, "0"),
+        (1e30, "w"),
+        (-1e30, "A"),
+        (1e30, "I"),
+        (1e7, "I"),
+    ] {
+        assert_eq!(
+            format_integer_with(json!(number), picture),
+            Err(EvaluationError::NumberOutOfRange(number)),
+            "{} {}",
+            number,
+            picture
+        );
+    }
+}
+
+#[test]
+fn parse_integer() {
+    let cases = vec![
+        (
+            "twelve thousand, four hundred and seventy-six",
+            "w",
+            json!(12476),
+        ),
+        ("12,345,678", "#,##0", json!(12345678)),
+        ("MCMXCIX", "I", json!(1999)),
+        ("ZZ", "A", json!(702)),
+        ("TWENTY-FIRST", "W;o", json!(21)),
+        ("One Million, Five Hundred Thousand", "Ww", json!(1500000)),
+        ("103rd", "0;o", json!(103)),
+        ("-42", "0", json!(-42)),
+    ];
+    for (s, picture, expected) in cases {
+        let actual = parse_integer_with(s, picture);
+        assert_eq!(actual, Ok(Some(make_val(expected))), "{}", s);
+    }
+
+    assert_eq!(parse_integer_with("twelve apples", "w"), Ok(None));
+    assert_eq!(parse_integer_with("12a", "0"), Ok(None));
+}
+
+proptest! {
+    #[test]
+    fn integers_round_trip(
+        value in -999_999_999_999_999_i64..=999_999_999_999_999,
+        picture in prop::sample::select(vec![
+            "0", "#,##0", "0000", "#,##,##0", "0;o", "#,##0;o", "\u{660}", "w", "W", "Ww", "w;o",
+            "A", "a",
+        ]),
+    ) {
+        let formatted = format_integer_with(json!(value), picture).unwrap();
+        let formatted = formatted.as_ref().and_then(|s| s.as_value()?.as_str()).unwrap();
+        let parsed = parse_integer_with(formatted, picture);
+        prop_assert_eq!(parsed, Ok(Some(make_val(json!(value)))), "{}", formatted);
+    }
+
+    #[test]
+    fn roman_numerals_round_trip(value in -9999_i64..=9999, picture in "[Ii]") {
+        let formatted = format_integer_with(json!(value), &picture).unwrap();
+        let formatted = formatted.as_ref().and_then(|s| s.as_value()?.as_str()).unwrap();
+        let parsed = parse_integer_with(formatted, &picture);
+        prop_assert_eq!(parsed, Ok(Some(make_val(json!(value)))), "{}", formatted);
+    }
+}
@@ -1,8 +1,11 @@
-use serde_json::Value;
+use serde_json::{Map, Value};
 
-use crate::{evaluate::EvaluationResult, value::JSONataValue};
+use crate::{
+    evaluate::{EvaluationError, EvaluationResult},
+    value::{sequence::Sequence, JSONataValue},
+};
 
-use super::BuiltIns;
+use super::{arg, array_arg, callback_args, function_arg, string_arg, BuiltIns};
 
 #[cfg(test)]
 mod tests;
@@ -11,23 +14,59 @@ impl BuiltIns {
     /// Returns an array containing the keys in the object. If the argument
     /// is an array of objects, then the array returned contains a
     /// de-duplicated list of all the keys in all of the objects.
+    ///
+    /// Keys are in the order they were first found in.
     pub(crate) fn keys(args: &[Option<JSONataValue>]) -> EvaluationResult {
-        todo!()
+        let mut keys = Vec::new();
+        if let Some(value) = arg(args, 0).and_then(JSONataValue::as_value) {
+            collect_keys(value, &mut keys);
+        }
+
+        let mut results = Sequence::new();
+        for key in keys {
+            results.push(key.into());
+        }
+        results.collapse()
     }
 
     /// Returns the value associated with `key` in `object`. If the first argument is
     /// an array of objects, then all of the objects in the array are searched,
     /// and the values associated with all occurrences of `key` are returned.
     pub(crate) fn lookup(args: &[Option<JSONataValue>]) -> EvaluationResult {
-        todo!()
+        let (value, key) = match (
+            arg(args, 0).and_then(JSONataValue::as_value),
+            string_arg(args, 1),
+        ) {
+            (Some(value), Some(key)) => (value, key),
+            _ => return Ok(None),
+        };
+
+        match value {
+            Value::Array(_) => {
+                let mut results = Sequence::new();
+                collect_lookups(value, key, &mut results);
+                results.collapse()
+            }
+            Value::Object(object) => Ok(object.get(key).map(JSONataValue::from)),
+            _ => Ok(None),
+        }
     }
 
     /// Splits an `object` containing key/value pairs into an array of objects,
     /// each of which has a single key/value pair from the input `object`.
     /// If the parameter is an array of objects, then the resultant array contains
     /// an object for every key/value pair in every object in the supplied array.
+    ///
+    /// Any other value is returned unchanged.
     pub(crate) fn spread(args: &[Option<JSONataValue>]) -> EvaluationResult {
-        todo!()
+        match arg(args, 0) {
+            Some(JSONataValue::Value(value @ (Value::Array(_) | Value::Object(_)))) => {
+                let mut results = Sequence::new();
+                collect_spread(value, &mut results);
+                results.collapse()
+            }
+            value => Ok(value.cloned()),
+        }
     }
 
     /// Merges an array of objects into a single object containing all the key/value pairs
@@ -37,7 +76,18 @@ impl BuiltIns {
     ///
     /// TODO: This applies to arrays, should probably go under the sequence builtins instead?
     pub(crate) fn merge(args: &[Option<JSONataValue>]) -> EvaluationResult {
-        todo!()
+        let objects = match array_arg(args, 0) {
+            Some(objects) => objects,
+            None => return Ok(None),
+        };
+
+        let mut merged = Map::new();
+        for object in objects.iter().filter_map(Value::as_object) {
+            for (key, value) in object {
+                merged.insert(key.clone(), value.clone());
+            }
+        }
+        Ok(Some(Value::Object(merged).into()))
     }
 
     /// Returns an array containing the values return by the function when applied to
@@ -63,24 +113,106 @@ impl BuiltIns {
     ///   "Postcode: SO21 2JN"
     /// ]
     /// ```
+    ///
+    /// Like `$sift`, the function is called with as many of the value, key and object as the
+    /// parameters declared by its signature. Undefined results are left out.
     pub(crate) fn each(args: &[Option<JSONataValue>]) -> EvaluationResult {
-        todo!()
+        let object = arg(args, 0).and_then(JSONataValue::as_value);
+        let (object, func) = match (object, function_arg(args, 1)) {
+            (Some(Value::Object(object)), Some(func)) => (object, func),
+            _ => return Ok(None),
+        };
+        let collection = Value::Object(object.clone());
+
+        let mut results = Sequence::new();
+        for (key, value) in object {
+            let args = callback_args(func, value, key.as_str().into(), &collection);
+            if let Some(result) = func.call(&args, None)? {
+                results.push(result);
+            }
+        }
+        results.collapse()
+    }
+
+    /// Returns an object that contains only the key/value pairs from the `object` parameter
+    /// that satisfy the predicate `function` passed in as the second parameter.
+    ///
+    /// If `object` is not specified, then the context value is used as the value of `object`.
+    /// It is an error if `object` is not an object.
+    ///
+    /// The function that is supplied as the second parameter must have the following signature:
+    ///
+    /// `function(value [, key [, object]])`
+    ///
+    /// Each value in the input object is passed in as the first parameter in the supplied function.
+    /// The key (property name) of that value in the input object is passed in as the second
+    /// parameter, if specified. The whole input object is passed in as the third parameter,
+    /// if specified.
+    ///
+    /// ## Example
+    ///
+    /// ```
+    /// Account.Order.Product.$sift(function($v, $k) {$k ~> /^Product/})
+    /// ```
+    pub(crate) fn sift(args: &[Option<JSONataValue>]) -> EvaluationResult {
+        let object = arg(args, 0).and_then(JSONataValue::as_value);
+        let (object, func) = match (object, function_arg(args, 1)) {
+            (Some(Value::Object(object)), Some(func)) => (object, func),
+            _ => return Ok(None),
+        };
+        let collection = Value::Object(object.clone());
+
+        let mut sifted = Map::new();
+        for (key, value) in object {
+            let args = callback_args(func, value, key.as_str().into(), &collection);
+            if BuiltIns::to_boolean(func.call(&args, None)?.as_ref()) {
+                sifted.insert(key.clone(), value.clone());
+            }
+        }
+
+        if sifted.is_empty() {
+            Ok(None)
+        } else {
+            Ok(Some(Value::Object(sifted).into()))
+        }
     }
 
     /// Deliberately throws an error with an optional `message`
     ///
+    /// The error is an [`EvaluationError::UserError`] with the code D3137, which holds
+    /// the message and the range of the call that raised it.
     pub(crate) fn error(args: &[Option<JSONataValue>]) -> EvaluationResult {
-        todo!()
+        let message = string_arg(args, 0)
+            .filter(|message| !message.is_empty())
+            .unwrap_or("$error() function evaluated");
+        Err(EvaluationError::UserError {
+            message: message.into(),
+            range: None,
+        })
     }
 
     /// If condition is true, the function returns undefined. If the condition
     /// is false, an exception is thrown with the message as the message of the exception.
     ///
-    /// TODO: Is message optional? In JSONata exerciser, not passing message results in error
-    /// "$assert() statement failed"
-    /// which seems like a caught exception, I think we'd rather check the arguments up-front
+    /// The error is an [`EvaluationError::AssertionFailed`] with the code D3141. As in
+    /// jsonata-js, an undefined condition fails and the message defaults to
+    /// "$assert() statement failed".
     pub(crate) fn assert(args: &[Option<JSONataValue>]) -> EvaluationResult {
-        todo!()
+        let condition = matches!(
+            arg(args, 0).and_then(JSONataValue::as_value),
+            Some(Value::Bool(true))
+        );
+        if condition {
+            return Ok(None);
+        }
+
+        let message = string_arg(args, 1)
+            .filter(|message| !message.is_empty())
+            .unwrap_or("$assert() statement failed");
+        Err(EvaluationError::AssertionFailed {
+            message: message.into(),
+            range: None,
+        })
     }
 
     /// Evaluates the type of value and returns one of the following strings:
@@ -116,3 +248,61 @@ impl BuiltIns {
         }
     }
 }
+
+/// Appends the keys of `value` which are not already in `keys`, searching each
+/// item of an array
+fn collect_keys(value: &Value, keys: &mut Vec<String>) {
+    match value {
+        Value::Array(items) => {
+            for item in items {
+                collect_keys(item, keys);
+            }
+        }
+        Value::Object(object) => {
+            for key in object.keys() {
+                if !keys.contains(key) {
+                    keys.push(key.clone());
+                }
+            }
+        }
+        _ => {}
+    }
+}
+
+/// Pushes the values of `key` in each object within the array `value`, with
+/// the items of array values pushed individually
+fn collect_lookups(value: &Value, key: &str, results: &mut Sequence) {
+    match value {
+        Value::Array(items) => {
+            for item in items {
+                collect_lookups(item, key, results);
+            }
+        }
+        Value::Object(object) => {
+            if let Some(found) = object.get(key) {
+                results.push_flattened(found.into());
+            }
+        }
+        _ => {}
+    }
+}
+
+/// Pushes an object for each key/value pair of `value`, or of each object in
+/// it if it is an array. Other items of an array are pushed as they are.
+fn collect_spread(value: &Value, results: &mut Sequence) {
+    match value {
+        Value::Array(items) => {
+            for item in items {
+                collect_spread(item, results);
+            }
+        }
+        Value::Object(object) => {
+            for (key, value) in object {
+                let mut pair = Map::new();
+                pair.insert(key.clone(), value.clone());
+                results.push(Value::Object(pair).into());
+            }
+        }
+        value => results.push(value.into()),
+    }
+}
@@ -0,0 +1,203 @@
+use serde_json::json;
+
+use crate::{
+    builtins::BuiltIns,
+    evaluate::EvaluationError,
+    tests::{builtin, make_val},
+    value::{signature::Signature, JSONataValue},
+};
+
+#[test]
+fn sift() {
+    let signature = Signature::parse("<xs:b>").unwrap();
+    let key_starts_with_p = JSONataValue::from_func_with_signature(
+        |args: &[Option<JSONataValue>]| {
+            let key = args[1].as_ref().and_then(|key| key.as_value()?.as_str());
+            Ok(Some(key.unwrap().starts_with('P').into()))
+        },
+        "f",
+        signature,
+    );
+    let object = make_val(json!({ "Price": 5, "Product": "Hat", "SKU": "0406" }));
+
+    assert_eq!(
+        BuiltIns::sift(&[Some(object), Some(key_starts_with_p.clone())]),
+        Ok(Some(make_val(json!({ "Price": 5, "Product": "Hat" }))))
+    );
+    assert_eq!(
+        BuiltIns::sift(&[
+            Some(make_val(json!({ "SKU": "0406" }))),
+            Some(key_starts_with_p)
+        ]),
+        Ok(None)
+    );
+}
+
+#[test]
+fn keys() {
+    let cases = vec![
+        (
+            json!({ "b": 1, "a": 2, "c": 3 }),
+            Some(json!(["b", "a", "c"])),
+        ),
+        (json!({ "a": 1 }), Some(json!("a"))),
+        (
+            json!([{ "b": 1, "a": 2 }, 5, { "c": 3, "b": 4 }, [{ "d": 5 }]]),
+            Some(json!(["b", "a", "c", "d"])),
+        ),
+        (json!({}), None),
+        (json!("a"), None),
+    ];
+    for (input, expected) in cases {
+        assert_eq!(
+            BuiltIns::keys(&[Some(make_val(input.clone()))]),
+            Ok(expected.map(make_val)),
+            "{}",
+            input
+        );
+    }
+    assert_eq!(BuiltIns::keys(&[None]), Ok(None));
+}
+
+#[test]
+fn lookup() {
+    let cases = vec![
+        (json!({ "a": [1, 2], "b": 3 }), Some(json!([1, 2]))),
+        (json!({ "b": 3 }), None),
+        (
+            json!([{ "a": 1 }, { "b": 2 }, [{ "a": [2, 3] }], "a"]),
+            Some(json!([1, 2, 3])),
+        ),
+        (json!([{ "a": 1 }, { "b": 2 }]), Some(json!(1))),
+        (json!(5), None),
+    ];
+    for (input, expected) in cases {
+        assert_eq!(
+            BuiltIns::lookup(&[Some(make_val(input.clone())), Some(make_val(json!("a")))]),
+            Ok(expected.map(make_val)),
+            "{}",
+            input
+        );
+    }
+}
+
+#[test]
+fn spread() {
+    let cases = vec![
+        (
+            json!({ "b": 1, "a": 2 }),
+            Some(json!([{ "b": 1 }, { "a": 2 }])),
+        ),
+        (json!({ "a": 1 }), Some(json!({ "a": 1 }))),
+        (
+            json!([{ "a": 1, "b": 2 }, 3, [{ "c": 4 }]]),
+            Some(json!([{ "a": 1 }, { "b": 2 }, 3, { "c": 4 }])),
+        ),
+        (json!({}), None),
+        (json!("a"), Some(json!("a"))),
+    ];
+    for (input, expected) in cases {
+        assert_eq!(
+            BuiltIns::spread(&[Some(make_val(input.clone()))]),
+            Ok(expected.map(make_val)),
+            "{}",
+            input
+        );
+    }
+}
+
+#[test]
+fn merge() {
+    let objects = json!([{ "b": 1, "a": 2 }, { "c": 3 }, { "b": 4 }]);
+    let merged = BuiltIns::merge(&[Some(make_val(objects))])
+        .unwrap()
+        .unwrap();
+    assert_eq!(merged, make_val(json!({ "b": 4, "a": 2, "c": 3 })));
+    // keys keep the position of their first occurrence
+    assert_eq!(merged.to_string(), r#"{"b":4,"a":2,"c":3}"#);
+
+    assert_eq!(
+        BuiltIns::merge(&[Some(make_val(json!([])))]),
+        Ok(Some(make_val(json!({}))))
+    );
+    assert_eq!(BuiltIns::merge(&[None]), Ok(None));
+}
+
+#[test]
+fn each() {
+    let signature = Signature::parse("<xs:s>").unwrap();
+    let describe = JSONataValue::from_func_with_signature(
+        |args: &[Option<JSONataValue>]| {
+            let value = args[0]
+                .as_ref()
+                .and_then(|value| value.as_value()?.as_str());
+            let key = args[1].as_ref().and_then(|key| key.as_value()?.as_str());
+            Ok(value.map(|value| format!("{}: {}", key.unwrap(), value).into()))
+        },
+        "f",
+        signature,
+    );
+    let address = make_val(json!({ "Street": "Hursley Park", "City": "Winchester", "Floor": 2 }));
+
+    assert_eq!(
+        BuiltIns::each(&[Some(address), Some(describe.clone())]),
+        Ok(Some(make_val(json!([
+            "Street: Hursley Park",
+            "City: Winchester"
+        ]))))
+    );
+    assert_eq!(
+        BuiltIns::each(&[Some(make_val(json!({}))), Some(describe)]),
+        Ok(None)
+    );
+}
+
+#[test]
+fn each_builtin() {
+    let object = make_val(json!({ "a": 1, "b": [true] }));
+    assert_eq!(
+        BuiltIns::each(&[Some(object), builtin("string")]),
+        Ok(Some(make_val(json!(["1", "[true]"]))))
+    );
+}
+
+#[test]
+fn error() {
+    assert_eq!(
+        BuiltIns::error(&[Some(make_val(json!("Order has no items")))]),
+        Err(EvaluationError::UserError {
+            message: "Order has no items".into(),
+            range: None,
+        })
+    );
+    assert_eq!(
+        BuiltIns::error(&[None]),
+        Err(EvaluationError::UserError {
+            message: "$error() function evaluated".into(),
+            range: None,
+        })
+    );
+}
+
+#[test]
+fn assert() {
+    let message = Some(make_val(json!("Price must be positive")));
+    assert_eq!(
+        BuiltIns::assert(&[Some(make_val(json!(true))), message.clone()]),
+        Ok(None)
+    );
+    assert_eq!(
+        BuiltIns::assert(&[Some(make_val(json!(false))), message]),
+        Err(EvaluationError::AssertionFailed {
+            message: "Price must be positive".into(),
+            range: None,
+        })
+    );
+    assert_eq!(
+        BuiltIns::assert(&[None, Some(make_val(json!("")))]),
+        Err(EvaluationError::AssertionFailed {
+            message: "$assert() statement failed".into(),
+            range: None,
+        })
+    );
+}
@@ -1,14 +1,16 @@
-use serde_json::Value;
+use std::{collections::HashMap, convert::TryFrom};
 
-use rand::seq::SliceRandom;
-use rand::thread_rng;
+use serde_json::Value;
 
-use crate::{evaluate::EvaluationResult, value::JSONataValue};
+use crate::{
+    evaluate::{EvaluationError, EvaluationResult, RandomSource},
+    value::{canonical_hash, number::JSONataNumber, JSONataValue},
+};
 
 #[cfg(test)]
 mod tests;
 
-use super::BuiltIns;
+use super::{arg, array_arg, function_arg, BuiltIns};
 
 impl BuiltIns {
     /// Ensures that the provided value is turned
@@ -40,27 +42,10 @@ impl BuiltIns {
     /// ```
     ///
     /// **Signature**: `$count(array)`
-    /// TODO: $count(i_dont_exist) returns 0 in try.jsonata.org ... do we want that?
     pub(crate) fn count(args: &[Option<JSONataValue>]) -> EvaluationResult {
-        let array = args.get(0).unwrap(); // arg will exist
-        if let Some(val) = array {
-            Ok(Some(
-                match val {
-                    JSONataValue::Value(val) => match val {
-                        Value::Null => 1,
-                        Value::Bool(_) => 1,
-                        Value::Number(_) => 1,
-                        Value::String(_) => 1,
-                        Value::Array(v) => v.len(),
-                        Value::Object(_) => 1,
-                    },
-                    JSONataValue::Function(_) => 1,
-                }
-                .into(),
-            ))
-        } else {
-            Ok(Some(0.into()))
-        }
+        Ok(Some(
+            array_arg(args, 0).map_or(0, |array| array.len()).into(),
+        ))
     }
 
     /// Returns an array containing the values in array1
@@ -68,25 +53,30 @@ impl BuiltIns {
     /// is not an array, then it is treated as a singleton
     /// array containing that value.
     ///
-    /// TODO: The following are implementation notes from try.jsonata.org
-    /// but are not documented as behaviors
+    /// As in jsonata-js, if either parameter is undefined then the other is returned
+    /// as it is, even if it is not an array.
     ///
-    /// - If one argument is None are the other argument is Some, the other
-    ///    argument is returned as-is (even for non-array args)
-    /// - If both arguments are None, the return value is None.
+    /// ## Examples
+    ///
+    /// ```
+    /// $append([1,2], [3,4]) => [1, 2, 3, 4]
+    /// $append(1, [2]) => [1, 2]
+    /// $append("a", $missing) => "a"
+    /// ```
     pub(crate) fn append(args: &[Option<JSONataValue>]) -> EvaluationResult {
-        todo!();
-        // let array1 = args.get(0).unwrap(); // arg will exist
-        // let array2 = args.get(1);
-        // match (array1, array2) {
+        let (first, second) = match (arg(args, 0), arg(args, 1)) {
+            (Some(first), Some(second)) => (first, second),
+            (first, second) => return Ok(first.or(second).cloned()),
+        };
 
-        // }
-        // if array1.is_none() && array2.is_none() {
-        //     return None;
-        // }
-        // let mut v = BuiltIns::vecify(array1);
-        // v.append(&mut BuiltIns::vecify(array2));
-        // Some(v.into())
+        let mut appended = Vec::new();
+        for value in [first, second] {
+            match Value::try_from(value.clone())? {
+                Value::Array(items) => appended.extend(items),
+                value => appended.push(value),
+            }
+        }
+        Ok(Some(appended.into()))
     }
 
     /// Returns an array containing all the values in the `array` parameter,
@@ -114,9 +104,38 @@ impl BuiltIns {
     ///
     /// The sorting algorithm is stable which means that values within the original array which are
     /// the same according to the comparator function will remain in the original order in the sorted array.
-    /// FIXME: Come back to this when functions are implemented
+    ///
+    /// As in jsonata-js, strings are compared by their UTF-16 code units, and sorting an array
+    /// of mixed types without a comparator raises D3070.
     pub(crate) fn sort(args: &[Option<JSONataValue>]) -> EvaluationResult {
-        todo!()
+        let array = match array_arg(args, 0) {
+            Some(array) => array,
+            None => return Ok(None),
+        };
+        if array.len() <= 1 {
+            return Ok(Some(array.clone().into()));
+        }
+
+        let sorted = match function_arg(args, 1) {
+            Some(comparator) => merge_sort(array.clone(), &mut |left, right| {
+                let args = [Some(left.into()), Some(right.into())];
+                Ok(BuiltIns::to_boolean(comparator.call(&args, None)?.as_ref()))
+            })?,
+            None if array.iter().all(Value::is_number) => {
+                merge_sort(array.clone(), &mut |left, right| {
+                    Ok(JSONataNumber::try_from(left)? > JSONataNumber::try_from(right)?)
+                })?
+            }
+            None if array.iter().all(Value::is_string) => {
+                merge_sort(array.clone(), &mut |left, right| {
+                    let left = left.as_str().unwrap_or_default().encode_utf16();
+                    let right = right.as_str().unwrap_or_default().encode_utf16();
+                    Ok(left.gt(right))
+                })?
+            }
+            None => return Err(EvaluationError::SortNotComparable),
+        };
+        Ok(Some(sorted.into()))
     }
 
     /// Returns an array containing all the values from the array parameter, but in reverse order.
@@ -127,37 +146,37 @@ impl BuiltIns {
     /// $reverse(["Hello", "World"]) => ["World", "Hello"]
     /// [1..5] ~> $reverse() => [5, 4, 3, 2, 1]
     /// ```
-    /// TODO: How does this work for non array values?
+    ///
+    /// Any other value is treated as a singleton array containing that value.
     pub(crate) fn reverse(args: &[Option<JSONataValue>]) -> EvaluationResult {
-        let val = args.get(0).unwrap(); // arg will exist
-        if let Some(val) = val {
-            match val {
-                JSONataValue::Value(_) => todo!(),
-                JSONataValue::Function(_) => todo!(),
-                // Value::Array(mut vec) => {
-                //     vec.reverse();
-                //     vec
-                // }
-                // v => vec![v],
-            }
-        } else {
-            Ok(None)
-        }
+        Ok(array_arg(args, 0).map(|array| array.iter().rev().cloned().collect::<Vec<_>>().into()))
     }
 
     /// Returns an array containing all the values from the array parameter,
     /// but shuffled into random order.
-    pub(crate) fn shuffle(args: &[Option<JSONataValue>]) -> EvaluationResult {
-        let arr = args.get(0).unwrap(); // arg will exist
-        if let Some(arr) = arr {
-            match arr {
-                JSONataValue::Value(_) => todo!(),
-                JSONataValue::Function(f) => todo!(),
-            }
+    ///
+    /// ## Examples
+    ///
+    /// ```
+    /// $shuffle([1..4]) => [3, 1, 4, 2]
+    /// ```
+    /// **Signature**: `$shuffle(array)`
+    pub(crate) fn shuffle(
+        rng: &mut dyn RandomSource,
+        args: &[Option<JSONataValue>],
+    ) -> EvaluationResult {
+        let mut array = match array_arg(args, 0) {
+            Some(array) => array.clone(),
+            None => return Ok(None),
+        };
+
+        // Fisher-Yates, drawing from `rng` so that a seeded evaluation
+        // always shuffles the same way
+        for i in (1..array.len()).rev() {
+            let j = (rng.next_f64() * (i + 1) as f64) as usize;
+            array.swap(i, j);
         }
-        let mut vec: Vec<u32> = (0..10).collect();
-        vec.shuffle(&mut thread_rng());
-        todo!()
+        Ok(Some(array.into()))
     }
 
     /// Returns an array containing all the values from the array parameter, but
@@ -170,8 +189,32 @@ impl BuiltIns {
     /// $distinct([1,2,3,3,4,3,5]) => [1, 2, 3, 4, 5]
     /// $distinct(Account.Order.Product.Description.Colour) => [ "Purple", "Orange", "Black" ]
     /// ```
-    pub(super) fn distinct(array: &JSONataValue) -> EvaluationResult {
-        todo!()
+    ///
+    /// Items are bucketed by a hash which is the same for equal values, so only items
+    /// with the same hash are compared. The first of each set of equal items is kept,
+    /// and any value other than an array is returned as it is.
+    pub(crate) fn distinct(args: &[Option<JSONataValue>]) -> EvaluationResult {
+        let array = match arg(args, 0) {
+            Some(JSONataValue::Value(Value::Array(array))) => array,
+            value => return Ok(value.cloned()),
+        };
+
+        let mut distinct: Vec<JSONataValue> = Vec::new();
+        let mut buckets: HashMap<u64, Vec<usize>> = HashMap::new();
+        for item in array {
+            let bucket = buckets.entry(canonical_hash(item)).or_default();
+            let item = JSONataValue::from(item);
+            if !bucket.iter().any(|index| distinct[*index] == item) {
+                bucket.push(distinct.len());
+                distinct.push(item);
+            }
+        }
+
+        let distinct = distinct
+            .into_iter()
+            .map(Value::try_from)
+            .collect::<Result<Vec<_>, _>>()?;
+        Ok(Some(distinct.into()))
     }
 
     /// Returns a convolved (zipped) array containing grouped arrays of values from
@@ -186,37 +229,39 @@ impl BuiltIns {
     /// $zip([1,2,3], [4,5,6]) => [[1,4] ,[2,5], [3,6]]
     /// $zip([1,2,3],[4,5],[7,8,9]) => [[1,4,7], [2,5,8]]
     /// ```
-    pub(crate) fn zip(arrays: &[Value]) {
-        todo!()
+    ///
+    /// Any other value is treated as a singleton array containing that value, and an
+    /// undefined argument results in an empty array.
+    pub(crate) fn zip(args: &[Option<JSONataValue>]) -> EvaluationResult {
+        let arrays = (0..args.len())
+            .map(|index| array_arg(args, index).map_or(&[][..], Vec::as_slice))
+            .collect::<Vec<_>>();
+        let length = arrays.iter().map(|array| array.len()).min().unwrap_or(0);
+
+        let zipped = (0..length)
+            .map(|index| arrays.iter().map(|array| array[index].clone()).collect())
+            .collect::<Vec<Value>>();
+        Ok(Some(zipped.into()))
     }
 
     /// Returns the arithmetic sum of an array of numbers.
     ///
     /// It is an error if the input array contains an item which isn't a number.
-    pub(crate) fn sum(array: Value) -> EvaluationResult {
-        // TODO: understand how try_fold works to return an error
-        todo!()
-        // match array {
-        //     Value::Number(_) => Ok(Some(array)),
-        //     Value::Array(v) => v
-        //         .iter()
-        //         .try_fold(JSONataNumber::default(), |acc, &val| match val {
-        //             Value::Number(n) => {
-        //                 let j_num = JSONataNumber::from(n);
-        //                 acc + j_num
-        //             }
-        //             _ => Err(EvaluationError::FunctionInvalidArgument(
-        //                 1,
-        //                 "sum".to_string(),
-        //                 "an array of numbers".to_string(),
-        //             )),
-        //         }),
-        //     _ => Err(EvaluationError::FunctionInvalidArgument(
-        //         1,
-        //         "sum".to_string(),
-        //         "an array of numbers".to_string(),
-        //     )),
-        // }
+    ///
+    /// Integers are summed exactly while the total fits in an i64 or u64, rather than
+    /// accumulating the rounding errors of floating point addition. It is an error if
+    /// the total is too large to be a number.
+    ///
+    /// ## Example
+    ///
+    /// ```
+    /// $sum([5,1,3,7,4]) => 20
+    /// ```
+    pub(crate) fn sum(args: &[Option<JSONataValue>]) -> EvaluationResult {
+        match numbers_arg(args, 0) {
+            Some(numbers) => Ok(Some(checked_sum(numbers)?.into())),
+            None => Ok(None),
+        }
     }
 
     /// Returns the maximum number in an array of numbers. It is an error if the
@@ -227,8 +272,13 @@ impl BuiltIns {
     /// ```
     /// $max([5,1,3,7,4]) => 7
     /// ```
-    pub(crate) fn max(array: Value) {
-        todo!()
+    pub(crate) fn max(args: &[Option<JSONataValue>]) -> EvaluationResult {
+        let max = numbers_arg(args, 0).and_then(|numbers| {
+            numbers
+                .into_iter()
+                .reduce(|max, n| if n > max { n } else { max })
+        });
+        Ok(max.map(JSONataValue::from))
     }
 
     /// Returns the minimum number in an array of numbers. It is an error if the
@@ -239,8 +289,13 @@ impl BuiltIns {
     /// ```
     /// $min([5,1,3,7,4]) => 1
     /// ```
-    pub(crate) fn min(array: Value) {
-        todo!()
+    pub(crate) fn min(args: &[Option<JSONataValue>]) -> EvaluationResult {
+        let min = numbers_arg(args, 0).and_then(|numbers| {
+            numbers
+                .into_iter()
+                .reduce(|min, n| if n < min { n } else { min })
+        });
+        Ok(min.map(JSONataValue::from))
     }
 
     /// Returns the mean number in an array of numbers. It is an error if the
@@ -251,7 +306,65 @@ impl BuiltIns {
     /// ```
     /// $average([5,1,3,7,4]) => 4
     /// ```
-    pub(crate) fn average(array: Value) {
-        todo!()
+    pub(crate) fn average(args: &[Option<JSONataValue>]) -> EvaluationResult {
+        let numbers = match numbers_arg(args, 0) {
+            Some(numbers) if !numbers.is_empty() => numbers,
+            _ => return Ok(None),
+        };
+        let count = JSONataNumber::from(numbers.len() as u64);
+        let sum = checked_sum(numbers)?;
+        Ok(Some((sum / count).into()))
+    }
+}
+
+/// Sums numbers, raising D1001 if the total is not finite
+fn checked_sum(numbers: Vec<JSONataNumber>) -> Result<JSONataNumber, EvaluationError> {
+    numbers
+        .into_iter()
+        .try_fold(JSONataNumber::from(0_u64), JSONataNumber::checked_add)
+}
+
+/// Sorts `items` with a stable merge sort, where `swap` returns whether its
+/// left value belongs after its right value
+///
+/// A user comparator need not be a consistent ordering, which the sorts of
+/// the standard library may panic on, and it may raise an error.
+fn merge_sort<F>(mut items: Vec<Value>, swap: &mut F) -> Result<Vec<Value>, EvaluationError>
+where
+    F: FnMut(&Value, &Value) -> Result<bool, EvaluationError>,
+{
+    if items.len() <= 1 {
+        return Ok(items);
     }
+
+    let right = items.split_off(items.len() / 2);
+    let left = merge_sort(items, swap)?;
+    let right = merge_sort(right, swap)?;
+
+    let mut merged = Vec::with_capacity(left.len() + right.len());
+    let mut left = left.into_iter().peekable();
+    let mut right = right.into_iter().peekable();
+    while let (Some(l), Some(r)) = (left.peek(), right.peek()) {
+        let next = if swap(l, r)? {
+            right.next()
+        } else {
+            left.next()
+        };
+        merged.extend(next);
+    }
+    merged.extend(left);
+    merged.extend(right);
+    Ok(merged)
+}
+
+/// The numbers in the array at `index`, or `None` if it is undefined. The
+/// signatures of the aggregation built-ins require every item to be a number.
+fn numbers_arg(args: &[Option<JSONataValue>], index: usize) -> Option<Vec<JSONataNumber>> {
+    let array = array_arg(args, index)?;
+    Some(
+        array
+            .iter()
+            .filter_map(|item| JSONataNumber::try_from(item).ok())
+            .collect(),
+    )
 }
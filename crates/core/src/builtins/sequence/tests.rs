@@ -1,8 +1,9 @@
+use rand::SeedableRng;
+use rand_chacha::ChaCha8Rng;
 use serde_json::json;
 
 use super::*;
 
-#[ignore]
 #[test]
 fn test_append() {
     // Nones
@@ -25,3 +26,280 @@ fn test_append() {
         Ok(Some(vec![Value::Bool(true), Value::Bool(false)].into())),
     );
 }
+
+#[test]
+fn shuffle() {
+    let shuffle = |seed: u64, array: Value| {
+        let mut rng = ChaCha8Rng::seed_from_u64(seed);
+        BuiltIns::shuffle(&mut rng, &[Some(array.into())])
+    };
+
+    let array: Vec<Value> = (1..=20).map(|n| json!(n)).collect();
+    let shuffled = shuffle(7, Value::Array(array.clone())).unwrap().unwrap();
+    let mut sorted = shuffled.as_value().unwrap().as_array().unwrap().clone();
+    assert_ne!(sorted, array);
+    sorted.sort_by_key(|n| n.as_i64());
+    assert_eq!(sorted, array);
+
+    // the same seed shuffles the same way
+    assert_eq!(shuffle(7, Value::Array(array)), Ok(Some(shuffled)));
+
+    assert_eq!(shuffle(7, json!([])), Ok(Some(json!([]).into())));
+    assert_eq!(
+        BuiltIns::shuffle(&mut ChaCha8Rng::seed_from_u64(7), &[None]),
+        Ok(None)
+    );
+}
+
+#[test]
+fn aggregations() {
+    // (numbers, sum, max, min, average)
+    let cases = vec![
+        (
+            json!([5, 1, 3, 7, 4]),
+            json!(20),
+            json!(7),
+            json!(1),
+            json!(4.0),
+        ),
+        (
+            json!([2.5, -3, 1]),
+            json!(0.5),
+            json!(2.5),
+            json!(-3),
+            json!(0.5 / 3.0),
+        ),
+        (json!([]), json!(0), Value::Null, Value::Null, Value::Null),
+    ];
+    let defined = |value: Value| (!value.is_null()).then(|| value.into());
+    for (numbers, sum, max, min, average) in cases {
+        let args = [Some(numbers.into())];
+        assert_eq!(BuiltIns::sum(&args), Ok(defined(sum)));
+        assert_eq!(BuiltIns::max(&args), Ok(defined(max)));
+        assert_eq!(BuiltIns::min(&args), Ok(defined(min)));
+        assert_eq!(BuiltIns::average(&args), Ok(defined(average)));
+    }
+    assert_eq!(BuiltIns::sum(&[None]), Ok(None));
+    assert_eq!(BuiltIns::average(&[None]), Ok(None));
+}
+
+#[test]
+fn sum_integers_exactly() {
+    let cases = vec![
+        (
+            json!([9007199254740993_u64, 1]),
+            json!(9007199254740994_u64),
+        ),
+        (json!([i64::MAX, 1]), json!(9223372036854775808_u64)),
+        (json!([i64::MIN, -1, 1]), json!(i64::MIN)),
+        (json!([u64::MAX, 1]), json!(18446744073709551616.0)),
+        (json!([u64::MAX, i64::MIN]), json!(9223372036854775807_i64)),
+    ];
+    for (numbers, expected) in cases {
+        assert_eq!(
+            BuiltIns::sum(&[Some(numbers.clone().into())]),
+            Ok(Some(expected.into())),
+            "{}",
+            numbers
+        );
+    }
+}
+
+#[test]
+fn sum_out_of_range() {
+    assert_eq!(
+        BuiltIns::sum(&[Some(json!([1e308, 1e308]).into())]),
+        Err(EvaluationError::NumberOutOfRange(f64::INFINITY))
+    );
+    assert_eq!(
+        BuiltIns::average(&[Some(json!([-1e308, -1e308]).into())]),
+        Err(EvaluationError::NumberOutOfRange(f64::NEG_INFINITY))
+    );
+}
+
+#[test]
+fn max_and_min_of_extreme_integers() {
+    let numbers = json!([i64::MIN, 1.5, u64::MAX, -2.5]);
+    assert_eq!(
+        BuiltIns::max(&[Some(numbers.clone().into())]),
+        Ok(Some(json!(u64::MAX).into()))
+    );
+    assert_eq!(
+        BuiltIns::min(&[Some(numbers.into())]),
+        Ok(Some(json!(i64::MIN).into()))
+    );
+    assert_eq!(
+        BuiltIns::max(&[Some(json!([i64::MIN, -1e300]).into())]),
+        Ok(Some(json!(i64::MIN).into()))
+    );
+}
+
+#[test]
+fn append_singletons() {
+    let append =
+        |first: Value, second: Value| BuiltIns::append(&[Some(first.into()), Some(second.into())]);
+    assert_eq!(append(json!(1), json!([2])), Ok(Some(json!([1, 2]).into())));
+    assert_eq!(append(json!([1]), json!(2)), Ok(Some(json!([1, 2]).into())));
+    assert_eq!(append(json!([]), json!([])), Ok(Some(json!([]).into())));
+    assert_eq!(
+        BuiltIns::append(&[Some(json!("a").into()), None]),
+        Ok(Some(json!("a").into()))
+    );
+}
+
+#[test]
+fn sort() {
+    let sort = |array: Value| BuiltIns::sort(&[Some(array.into()), None]);
+    let cases = vec![
+        (json!([3, 1.5, -2, 10]), json!([-2, 1.5, 3, 10])),
+        (
+            json!(["b", "B", "a", "\u{ff61}", "😀"]),
+            json!(["B", "a", "b", "😀", "\u{ff61}"]),
+        ),
+        (json!([1]), json!([1])),
+        (json!([]), json!([])),
+    ];
+    for (array, expected) in cases {
+        assert_eq!(sort(array.clone()), Ok(Some(expected.into())), "{}", array);
+    }
+    assert_eq!(
+        sort(json!([1, "a"])),
+        Err(EvaluationError::SortNotComparable)
+    );
+    assert_eq!(
+        sort(json!([[1], [2]])),
+        Err(EvaluationError::SortNotComparable)
+    );
+    assert_eq!(BuiltIns::sort(&[None, None]), Ok(None));
+}
+
+#[test]
+fn sort_with_comparator() {
+    // orders by weight only, so equal weights keep their original order
+    let heavier = JSONataValue::from_func(
+        |args: &[Option<JSONataValue>]| {
+            let weight = |arg: &Option<JSONataValue>| {
+                arg.as_ref()
+                    .and_then(|arg| arg.as_value()?.get("weight")?.as_f64())
+            };
+            Ok(Some((weight(&args[0]) > weight(&args[1])).into()))
+        },
+        "heavier",
+    );
+    let products = json!([
+        { "name": "a", "weight": 2 },
+        { "name": "b", "weight": 1 },
+        { "name": "c", "weight": 2 },
+        { "name": "d", "weight": 0.5 },
+        { "name": "e", "weight": 1 },
+    ]);
+    let sorted = BuiltIns::sort(&[Some(products.into()), Some(heavier)]).unwrap();
+    let names = sorted
+        .as_ref()
+        .and_then(|sorted| sorted.as_value()?.as_array())
+        .map(|sorted| {
+            sorted
+                .iter()
+                .map(|product| product["name"].as_str().unwrap())
+                .collect::<String>()
+        });
+    assert_eq!(names.as_deref(), Some("dbeac"));
+
+    let failing = JSONataValue::from_func(
+        |_: &[Option<JSONataValue>]| {
+            Err(EvaluationError::UserError {
+                message: "no".into(),
+                range: None,
+            })
+        },
+        "failing",
+    );
+    assert_eq!(
+        BuiltIns::sort(&[Some(json!([2, 1]).into()), Some(failing)]),
+        Err(EvaluationError::UserError {
+            message: "no".into(),
+            range: None,
+        })
+    );
+}
+
+#[test]
+fn reverse() {
+    assert_eq!(
+        BuiltIns::reverse(&[Some(json!(["Hello", "World"]).into())]),
+        Ok(Some(json!(["World", "Hello"]).into()))
+    );
+    assert_eq!(
+        BuiltIns::reverse(&[Some(json!([]).into())]),
+        Ok(Some(json!([]).into()))
+    );
+    assert_eq!(BuiltIns::reverse(&[None]), Ok(None));
+}
+
+#[test]
+fn zip() {
+    let zip = |arrays: Vec<Option<Value>>| {
+        let args: Vec<_> = arrays
+            .into_iter()
+            .map(|array| array.map(JSONataValue::from))
+            .collect();
+        BuiltIns::zip(&args)
+    };
+    let cases = vec![
+        (
+            vec![Some(json!([1, 2, 3])), Some(json!([4, 5, 6]))],
+            json!([[1, 4], [2, 5], [3, 6]]),
+        ),
+        (
+            vec![
+                Some(json!([1, 2, 3])),
+                Some(json!([4, 5])),
+                Some(json!([7, 8, 9])),
+            ],
+            json!([[1, 4, 7], [2, 5, 8]]),
+        ),
+        (vec![Some(json!([1, 2]))], json!([[1], [2]])),
+        (vec![Some(json!([1, 2])), None], json!([])),
+    ];
+    for (arrays, expected) in cases {
+        assert_eq!(zip(arrays), Ok(Some(expected.into())));
+    }
+}
+
+#[test]
+fn distinct() {
+    let cases = vec![
+        (json!([1, 2, 3, 3, 4, 3, 5]), json!([1, 2, 3, 4, 5])),
+        (json!([1, 1.0, "1", [1], [1.0]]), json!([1, "1", [1]])),
+        (
+            json!([{ "a": 1, "b": [2] }, { "b": [2.0], "a": 1 }, { "a": 1 }]),
+            json!([{ "a": 1, "b": [2] }, { "a": 1 }]),
+        ),
+        (
+            json!([null, false, 0, "", null]),
+            json!([null, false, 0, ""]),
+        ),
+        (json!([]), json!([])),
+        (json!("a"), json!("a")),
+    ];
+    for (input, expected) in cases {
+        assert_eq!(
+            BuiltIns::distinct(&[Some(input.clone().into())]),
+            Ok(Some(expected.into())),
+            "{}",
+            input
+        );
+    }
+    assert_eq!(BuiltIns::distinct(&[None]), Ok(None));
+}
+
+#[test]
+fn distinct_large_array() {
+    let items: Vec<Value> = (0..200_000)
+        .map(|n| json!({ "id": n % 1000, "tags": [n % 7] }))
+        .collect();
+    let distinct = BuiltIns::distinct(&[Some(items.into())]).unwrap().unwrap();
+    let distinct = distinct.as_value().and_then(Value::as_array).unwrap();
+    assert_eq!(distinct.len(), 7000);
+    assert_eq!(distinct[0], json!({ "id": 0, "tags": [0] }));
+}
@@ -0,0 +1,157 @@
+//! Base 64 and URI encodings with the semantics of the ECMAScript functions
+//! `btoa`, `atob`, `encodeURI`, `encodeURIComponent`, `decodeURI` and
+//! `decodeURIComponent`, which jsonata-js calls directly
+
+const BASE64_ALPHABET: &[u8; 64] =
+    b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789+/";
+
+/// Characters which `encodeURIComponent` does not escape
+const URI_UNRESERVED: &str = "-_.!~*'()";
+
+/// Characters which `encodeURI` does not escape and `decodeURI` does not
+/// unescape, in addition to the unreserved characters
+const URI_RESERVED: &str = ";,/?:@&=+$#";
+
+/// Encodes each character of `s` as a byte of binary data, as `btoa` does,
+/// returning `None` if a character is outside of the range 0x00 to 0xFF
+pub(super) fn base64_encode(s: &str) -> Option<String> {
+    let bytes = s
+        .chars()
+        .map(|c| u8::try_from(c).ok())
+        .collect::<Option<Vec<_>>>()?;
+
+    let mut encoded = String::with_capacity(bytes.len().div_ceil(3) * 4);
+    for chunk in bytes.chunks(3) {
+        let bits = chunk.iter().enumerate().fold(0u32, |bits, (i, byte)| {
+            bits | (*byte as u32) << (16 - 8 * i)
+        });
+        for i in 0..4 {
+            if i <= chunk.len() {
+                let index = (bits >> (18 - 6 * i)) & 0x3F;
+                encoded.push(BASE64_ALPHABET[index as usize] as char);
+            } else {
+                encoded.push('=');
+            }
+        }
+    }
+    Some(encoded)
+}
+
+/// Decodes `s` to a string with a character for each byte, as `atob` does
+///
+/// Follows the forgiving base 64 decoding of the WHATWG Infra standard, which
+/// ignores ASCII whitespace and allows the padding to be left out. Returns
+/// `None` if `s` is not base 64.
+pub(super) fn base64_decode(s: &str) -> Option<String> {
+    let mut data: Vec<u8> = s
+        .bytes()
+        .filter(|byte| !matches!(byte, b'\t' | b'\n' | b'\x0C' | b'\r' | b' '))
+        .collect();
+    if data.len().is_multiple_of(4) {
+        for _ in 0..2 {
+            if data.last() == Some(&b'=') {
+                data.pop();
+            }
+        }
+    }
+    if data.len() % 4 == 1 {
+        return None;
+    }
+
+    let mut decoded = String::with_capacity(data.len() / 4 * 3);
+    for chunk in data.chunks(4) {
+        let mut bits = 0u32;
+        for (i, byte) in chunk.iter().enumerate() {
+            let index = BASE64_ALPHABET.iter().position(|c| c == byte)?;
+            bits |= (index as u32) << (18 - 6 * i);
+        }
+        // the bits left over after the last whole byte are discarded
+        for i in 0..chunk.len() - 1 {
+            decoded.push(char::from((bits >> (16 - 8 * i)) as u8));
+        }
+    }
+    Some(decoded)
+}
+
+/// Escapes the UTF-8 bytes of each character of `s`, except for letters,
+/// digits and the unreserved characters, as `encodeURIComponent` does
+///
+/// Unlike a JavaScript string, `s` cannot hold a lone surrogate, so encoding
+/// never fails.
+pub(super) fn encode_uri_component(s: &str) -> String {
+    encode_uri_with(s, "")
+}
+
+/// Escapes `s` like [`encode_uri_component`], leaving the reserved
+/// characters of a URI unescaped, as `encodeURI` does
+pub(super) fn encode_uri(s: &str) -> String {
+    encode_uri_with(s, URI_RESERVED)
+}
+
+fn encode_uri_with(s: &str, unescaped: &str) -> String {
+    let mut encoded = String::with_capacity(s.len());
+    for c in s.chars() {
+        if c.is_ascii_alphanumeric() || URI_UNRESERVED.contains(c) || unescaped.contains(c) {
+            encoded.push(c);
+        } else {
+            let mut bytes = [0; 4];
+            for byte in c.encode_utf8(&mut bytes).bytes() {
+                encoded.push_str(&format!("%{:02X}", byte));
+            }
+        }
+    }
+    encoded
+}
+
+/// Replaces each escape sequence in `s` with the character it represents, as
+/// `decodeURIComponent` does, returning `None` if an escape sequence is
+/// malformed or is not the UTF-8 encoding of a character
+pub(super) fn decode_uri_component(s: &str) -> Option<String> {
+    decode_uri_with(s, "")
+}
+
+/// Decodes `s` like [`decode_uri_component`], leaving the escape sequences
+/// of the reserved characters of a URI as they are, as `decodeURI` does
+pub(super) fn decode_uri(s: &str) -> Option<String> {
+    decode_uri_with(s, URI_RESERVED)
+}
+
+fn decode_uri_with(s: &str, reserved: &str) -> Option<String> {
+    let bytes = s.as_bytes();
+    let octet = |index: usize| -> Option<u8> {
+        if bytes.get(index) != Some(&b'%') {
+            return None;
+        }
+        let hex = s.get(index + 1..index + 3)?;
+        u8::from_str_radix(hex, 16)
+            .ok()
+            .filter(|_| hex.bytes().all(|b| b.is_ascii_hexdigit()))
+    };
+
+    let mut decoded = String::with_capacity(s.len());
+    let mut index = 0;
+    while let Some(offset) = s[index..].find('%') {
+        decoded.push_str(&s[index..index + offset]);
+        index += offset;
+
+        let first = octet(index)?;
+        let length = match first.leading_ones() {
+            0 => 1,
+            length @ 2..=4 => length as usize,
+            _ => return None,
+        };
+        let octets = (0..length)
+            .map(|i| octet(index + 3 * i))
+            .collect::<Option<Vec<_>>>()?;
+        let c = std::str::from_utf8(&octets).ok()?;
+
+        if reserved.contains(c) {
+            decoded.push_str(&s[index..index + 3]);
+        } else {
+            decoded.push_str(c);
+        }
+        index += 3 * length;
+    }
+    decoded.push_str(&s[index..]);
+    Some(decoded)
+}
@@ -0,0 +1,88 @@
+use serde_json::Value;
+
+use crate::{
+    evaluate::EvaluationError,
+    value::{regex::RegexMatch, JSONataFunction},
+};
+
+/// Finds up to `limit` successive matches of the matcher function `pattern`
+/// within `s`
+///
+/// Searching continues from the end of the previous match, and stops once the
+/// end of `s` is reached. As in jsonata-js, an empty match is only allowed as
+/// the first match, since searching again from its end would find it forever.
+///
+/// A matcher function which is not a regular expression is called with the
+/// rest of `s` after the previous match, and must return an object with the
+/// `match`, `start` and `end` of its first match, or undefined.
+pub(super) fn find_matches(
+    pattern: &JSONataFunction,
+    s: &str,
+    limit: usize,
+) -> Result<Vec<RegexMatch>, EvaluationError> {
+    let mut matches = Vec::new();
+    let mut position = 0;
+    while matches.len() < limit {
+        let found = match pattern.regex() {
+            Some(regex) => regex
+                .captures_at(s, position)
+                .map(|captures| RegexMatch::from_captures(&captures)),
+            None => call_matcher(pattern, s, position)?,
+        };
+        let found = match found {
+            Some(found) => found,
+            None => break,
+        };
+
+        if found.start == found.end && !matches.is_empty() {
+            return Err(EvaluationError::RegexMatchesEmptyString);
+        }
+        position = found.end;
+        matches.push(found);
+        if position >= s.len() {
+            break;
+        }
+    }
+    Ok(matches)
+}
+
+/// Calls a matcher function with the rest of `s` from `position`, and returns
+/// its match with byte offsets into `s`
+fn call_matcher(
+    pattern: &JSONataFunction,
+    s: &str,
+    position: usize,
+) -> Result<Option<RegexMatch>, EvaluationError> {
+    let rest = &s[position..];
+    let result = match pattern.call(&[Some(rest.into())], None)? {
+        Some(result) => result,
+        None => return Ok(None),
+    };
+    let invalid = || EvaluationError::InvalidMatcherResult(pattern.ident().into());
+
+    let object = result
+        .as_value()
+        .and_then(Value::as_object)
+        .ok_or_else(invalid)?;
+    let offset = |key: &str| {
+        let chars = object.get(key)?.as_u64()? as usize;
+        match rest.char_indices().nth(chars) {
+            Some((offset, _)) => Some(position + offset),
+            None if chars == rest.chars().count() => Some(s.len()),
+            None => None,
+        }
+    };
+    let (start, end) = match (offset("start"), offset("end")) {
+        (Some(start), Some(end)) if start <= end => (start, end),
+        _ => return Err(invalid()),
+    };
+    let groups = match object.get("groups") {
+        Some(Value::Array(groups)) => groups
+            .iter()
+            .map(|group| group.as_str().map(String::from))
+            .collect(),
+        _ => Vec::new(),
+    };
+
+    Ok(Some(RegexMatch { start, end, groups }))
+}
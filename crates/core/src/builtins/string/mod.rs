@@ -1,15 +1,37 @@
+//! String built-ins
+//!
+//! Lengths and positions within strings are counted in Unicode code points, as
+//! jsonata-js does by iterating over strings with `for...of`. A character outside
+//! the Basic Multilingual Plane, such as an emoji, counts as one character even
+//! though it is two UTF-16 code units in a JavaScript string. Combining marks
+//! count as characters of their own, e.g. "é" written as "e" followed by U+0301
+//! has a length of 2, and strings are not normalized.
+
+use std::convert::TryFrom;
+
 use serde_json::Value;
 
 use crate::{
-    evaluate::{EvaluationError, EvaluationResult},
-    value::JSONataValue,
+    evaluate::{EvaluationContext, EvaluationError, EvaluationResult},
+    expression::Expression,
+    value::{
+        regex::RegexMatch, sequence::Sequence, stringify::stringify, JSONataFunction, JSONataValue,
+    },
 };
 
+use self::matcher::find_matches;
+use super::{arg, array_arg, function_arg, number_arg, string_arg, BuiltIns};
+
+/// The widest padding of `$pad`, which is the longest string that the
+/// JavaScript engine of Node.js can build
+const PAD_MAX_WIDTH: f64 = 536_870_888.0;
+
+mod encoding;
+mod matcher;
+
 #[cfg(test)]
 mod tests;
 
-use super::BuiltIns;
-
 impl BuiltIns {
     /// Casts the `arg` parameter to a string using the following casting rules
     ///
@@ -30,17 +52,18 @@ impl BuiltIns {
     /// $string(5) => "5"
     /// [1..5].$string() => ["1", "2", "3", "4", "5"]
     /// ```
-    /// `Signature: $string(arg, prettify)`
+    /// **Signature**: `$string(arg [, prettify])`
     pub(crate) fn string(args: &[Option<JSONataValue>]) -> EvaluationResult {
-        let arg = args.get(0).unwrap(); // arg will exist
-        if let Some(arg) = arg {
-            Ok(Some(match arg {
-                JSONataValue::Value(val) => val.to_string().into(),
-                JSONataValue::Function(_) => "".into(),
-            }))
-        } else {
-            Ok(None)
-        }
+        let prettify = arg(args, 1)
+            .and_then(|prettify| prettify.as_value()?.as_bool())
+            .unwrap_or(false);
+        let string = match arg(args, 0) {
+            Some(JSONataValue::Value(Value::String(s))) => s.clone(),
+            Some(JSONataValue::Value(val)) => stringify(val, prettify)?,
+            Some(JSONataValue::Function(_)) => String::new(),
+            None => return Ok(None),
+        };
+        Ok(Some(string.into()))
     }
 
     /// Returns the number of characters in the string str. If str is not specified
@@ -53,24 +76,7 @@ impl BuiltIns {
     /// $length("Hello World") => 11
     /// ```
     pub(crate) fn length(args: &[Option<JSONataValue>]) -> EvaluationResult {
-        let arg = args.get(0).unwrap(); // arg will exist
-        if let Some(arg) = arg {
-            if let JSONataValue::Value(val) = arg {
-                if let Value::String(s) = val {
-                    Ok(Some(s.len().into()))
-                } else {
-                    Err(EvaluationError::function_invalid_argument(
-                        "length", 1, "string",
-                    ))
-                }
-            } else {
-                Err(EvaluationError::function_invalid_argument(
-                    "length", 1, "string",
-                ))
-            }
-        } else {
-            Ok(None)
-        }
+        Ok(string_arg(args, 0).map(|s| s.chars().count().into()))
     }
 
     /// Returns a string containing the characters in the first parameter str starting at position
@@ -92,7 +98,29 @@ impl BuiltIns {
     /// $substring("Hello World", -4, 2) => "or"
     /// ```
     pub(crate) fn substring(args: &[Option<JSONataValue>]) -> EvaluationResult {
-        todo!()
+        let (s, start) = match (string_arg(args, 0), number_arg(args, 1)) {
+            (Some(s), Some(start)) => (s, start.as_f64() as i64),
+            _ => return Ok(None),
+        };
+        let len = s.chars().count() as i64;
+
+        // a negative start counts from the end of the string
+        let start = if start < 0 {
+            (len + start).max(0)
+        } else {
+            start
+        };
+        let end = match number_arg(args, 2) {
+            Some(length) => start.saturating_add(length.as_f64() as i64),
+            None => len,
+        };
+
+        let substring: String = s
+            .chars()
+            .skip(start as usize)
+            .take((end - start).max(0) as usize)
+            .collect();
+        Ok(Some(substring.into()))
     }
 
     /// Returns the substring before the first occurrence of the character sequence chars in str.
@@ -106,7 +134,12 @@ impl BuiltIns {
     /// $substringBefore("Hello World", " ") => "Hello"
     /// ```
     pub(crate) fn substring_before(args: &[Option<JSONataValue>]) -> EvaluationResult {
-        todo!()
+        let (s, chars) = match (string_arg(args, 0), string_arg(args, 1)) {
+            (Some(s), Some(chars)) => (s, chars),
+            _ => return Ok(None),
+        };
+        let before = s.find(chars).map_or(s, |position| &s[..position]);
+        Ok(Some(before.into()))
     }
 
     /// Returns the substring after the first occurrence of the character sequence chars in str.
@@ -120,7 +153,14 @@ impl BuiltIns {
     /// $substringAfter("Hello World", " ") => "World"
     /// ```
     pub(crate) fn substring_after(args: &[Option<JSONataValue>]) -> EvaluationResult {
-        todo!()
+        let (s, chars) = match (string_arg(args, 0), string_arg(args, 1)) {
+            (Some(s), Some(chars)) => (s, chars),
+            _ => return Ok(None),
+        };
+        let after = s
+            .find(chars)
+            .map_or(s, |position| &s[position + chars.len()..]);
+        Ok(Some(after.into()))
     }
 
     /// Returns a string with all the characters of str converted to uppercase. If str is not
@@ -133,7 +173,7 @@ impl BuiltIns {
     /// $uppercase("Hello World") => "HELLO WORLD"
     /// ```
     pub(crate) fn uppercase(args: &[Option<JSONataValue>]) -> EvaluationResult {
-        todo!()
+        Ok(string_arg(args, 0).map(|s| s.to_uppercase().into()))
     }
 
     /// Returns a string with all the characters of str converted to lowercase. If str is not
@@ -146,7 +186,7 @@ impl BuiltIns {
     /// $lowercase("Hello World") => "hello world"
     /// ```
     pub(crate) fn lowercase(args: &[Option<JSONataValue>]) -> EvaluationResult {
-        todo!()
+        Ok(string_arg(args, 0).map(|s| s.to_lowercase().into()))
     }
 
     /// Normalizes and trims all whitespace characters in str by applying the following steps:
@@ -164,7 +204,13 @@ impl BuiltIns {
     /// $trim(" Hello \n World ") => "Hello World"
     /// ```
     pub(crate) fn trim(args: &[Option<JSONataValue>]) -> EvaluationResult {
-        todo!()
+        Ok(string_arg(args, 0).map(|s| {
+            s.split([' ', '\t', '\n', '\r'])
+                .filter(|word| !word.is_empty())
+                .collect::<Vec<_>>()
+                .join(" ")
+                .into()
+        }))
     }
 
     /// Returns a copy of the string str with extra padding, if necessary, so that its
@@ -173,6 +219,8 @@ impl BuiltIns {
     /// it is padded to the left. The optional char argument specifies the padding character(s)
     /// to use. If not specified, it defaults to the space character.
     ///
+    /// It is an error if the absolute value of width is larger than [`PAD_MAX_WIDTH`].
+    ///
     /// ## Examples
     ///
     /// ```
@@ -182,7 +230,28 @@ impl BuiltIns {
     /// $formatBase(35, 2) ~> $pad(-8, '0') => "00100011"
     /// ```
     pub(crate) fn pad(args: &[Option<JSONataValue>]) -> EvaluationResult {
-        todo!()
+        let (s, width) = match (string_arg(args, 0), number_arg(args, 1)) {
+            (Some(s), Some(width)) => (s, width.as_f64()),
+            _ => return Ok(None),
+        };
+        if width.abs() > PAD_MAX_WIDTH {
+            return Err(EvaluationError::PadWidthTooLarge(width));
+        }
+        let width = width as i64;
+        let pad_char = match string_arg(args, 2) {
+            Some(pad_char) if !pad_char.is_empty() => pad_char,
+            _ => " ",
+        };
+
+        let pad_len = width.unsigned_abs() as usize;
+        let pad_len = pad_len.saturating_sub(s.chars().count());
+        let padding: String = pad_char.chars().cycle().take(pad_len).collect();
+        let padded = if width > 0 {
+            format!("{}{}", s, padding)
+        } else {
+            format!("{}{}", padding, s)
+        };
+        Ok(Some(padded.into()))
     }
 
     /// Returns true if str is matched by pattern, otherwise it returns false. If str is
@@ -203,8 +272,19 @@ impl BuiltIns {
     /// $contains("Hello World", /wo/) => false
     /// $contains("Hello World", /wo/i) => true
     /// Phone[$contains(number, /^077/)] => { "type": "mobile", "number": "077 7700 1234" }
+    /// ```
+    /// **Signature**: `$contains(str, pattern)`
     pub(crate) fn contains(args: &[Option<JSONataValue>]) -> EvaluationResult {
-        todo!()
+        let s = match string_arg(args, 0) {
+            Some(s) => s,
+            None => return Ok(None),
+        };
+        let contains = match arg(args, 1) {
+            Some(JSONataValue::Value(Value::String(pattern))) => s.contains(pattern.as_str()),
+            Some(JSONataValue::Function(pattern)) => !find_matches(pattern, s, 1)?.is_empty(),
+            _ => return Ok(None),
+        };
+        Ok(Some(contains.into()))
     }
 
     /// Splits the str parameter into an array of substrings. If str is not specified, then the
@@ -228,7 +308,43 @@ impl BuiltIns {
     /// $split("too much, punctuation. hard; to read", /[ ,.;]+/) => ["too", "much", "punctuation", "hard", "to", "read"]
     ///
     pub(crate) fn split(args: &[Option<JSONataValue>]) -> EvaluationResult {
-        todo!()
+        let s = match string_arg(args, 0) {
+            Some(s) => s,
+            None => return Ok(None),
+        };
+        let limit = match number_arg(args, 2).map(|limit| limit.as_f64()) {
+            Some(limit) if limit < 0.0 => return Err(EvaluationError::SplitNegativeLimit),
+            Some(limit) => limit as usize,
+            None => usize::MAX,
+        };
+
+        let parts: Vec<Value> = match arg(args, 1) {
+            Some(JSONataValue::Value(Value::String(separator))) if separator.is_empty() => s
+                .chars()
+                .take(limit)
+                .map(|c| c.to_string().into())
+                .collect(),
+            Some(JSONataValue::Value(Value::String(separator))) => s
+                .split(separator.as_str())
+                .take(limit)
+                .map(Value::from)
+                .collect(),
+            Some(JSONataValue::Function(separator)) => {
+                let matches = find_matches(separator, s, limit)?;
+                let mut parts = Vec::new();
+                let mut position = 0;
+                for found in &matches {
+                    parts.push(s[position..found.start].into());
+                    position = found.end;
+                }
+                if matches.len() < limit {
+                    parts.push(s[position..].into());
+                }
+                parts
+            }
+            _ => return Ok(None),
+        };
+        Ok(Some(Value::Array(parts).into()))
     }
 
     /// Joins an array of component strings into a single concatenated string with each component
@@ -246,7 +362,13 @@ impl BuiltIns {
     /// $split("too much, punctuation. hard; to read", /[ ,.;]+/, 3) ~> $join(', ') => "too, much, punctuation"
     ///
     pub(crate) fn join(args: &[Option<JSONataValue>]) -> EvaluationResult {
-        todo!()
+        let strings = match array_arg(args, 0) {
+            Some(strings) => strings,
+            None => return Ok(None),
+        };
+        let separator = string_arg(args, 1).unwrap_or("");
+        let strings: Vec<&str> = strings.iter().filter_map(Value::as_str).collect();
+        Ok(Some(strings.join(separator).into()))
     }
 
     /// Applies the `str` string to the pattern regular expression and returns an array of objects,
@@ -286,8 +408,29 @@ impl BuiltIns {
     ///   }
     /// ]
     /// ```
+    /// The optional `limit` parameter is the maximum number of matches to return.
+    ///
+    /// **Signature**: `$match(str, pattern [, limit])`
     pub(crate) fn r#match(args: &[Option<JSONataValue>]) -> EvaluationResult {
-        todo!()
+        let s = match string_arg(args, 0) {
+            Some(s) => s,
+            None => return Ok(None),
+        };
+        let pattern = match function_arg(args, 1) {
+            Some(pattern) => pattern,
+            None => return Ok(None),
+        };
+        let limit = match number_arg(args, 2).map(|limit| limit.as_f64()) {
+            Some(limit) if limit < 0.0 => return Err(EvaluationError::MatchNegativeLimit),
+            Some(limit) => limit.ceil() as usize,
+            None => usize::MAX,
+        };
+
+        let mut sequence = Sequence::new();
+        for found in find_matches(pattern, s, limit)? {
+            sequence.push(found.to_value(s).into());
+        }
+        sequence.collapse()
     }
 
     /// Finds occurrences of pattern within str and replaces them with replacement.
@@ -318,8 +461,135 @@ impl BuiltIns {
     /// The optional limit parameter, is a number that specifies the maximum number of
     /// replacements to make before stopping. The remainder of the input beyond this limit
     /// will be copied to the output unchanged.
+    ///
+    /// ## Examples
+    ///
+    /// ```
+    /// $replace("John Smith and John Jones", "John", "Mr") => "Mr Smith and Mr Jones"
+    /// $replace("John Smith and John Jones", "John", "Mr", 1) => "Mr Smith and John Jones"
+    /// $replace("abracadabra", /a.*?a/, "*") => "*c*bra"
+    /// $replace("John Smith", /(\w+)\s(\w+)/, "$2, $1") => "Smith, John"
+    /// $replace("265USD", /([0-9]+)USD/, "$$$1") => "$265"
+    /// ```
+    /// **Signature**: `$replace(str, pattern, replacement [, limit])`
     pub(crate) fn replace(args: &[Option<JSONataValue>]) -> EvaluationResult {
-        todo!()
+        let s = match string_arg(args, 0) {
+            Some(s) => s,
+            None => return Ok(None),
+        };
+        let limit = match number_arg(args, 3).map(|limit| limit.as_f64()) {
+            Some(limit) if limit < 0.0 => return Err(EvaluationError::ReplaceNegativeLimit),
+            Some(limit) => limit.ceil() as usize,
+            None => usize::MAX,
+        };
+
+        let literal = matches!(arg(args, 1), Some(JSONataValue::Value(_)));
+        let matches = match arg(args, 1) {
+            Some(JSONataValue::Value(Value::String(pattern))) if pattern.is_empty() => {
+                return Err(EvaluationError::ReplaceEmptyPattern)
+            }
+            Some(JSONataValue::Value(Value::String(pattern))) => s
+                .match_indices(pattern.as_str())
+                .take(limit)
+                .map(|(start, matched)| RegexMatch {
+                    start,
+                    end: start + matched.len(),
+                    groups: Vec::new(),
+                })
+                .collect(),
+            Some(JSONataValue::Function(pattern)) => find_matches(pattern, s, limit)?,
+            _ => return Ok(None),
+        };
+
+        let mut result = String::new();
+        let mut position = 0;
+        for found in matches {
+            result.push_str(&s[position..found.start]);
+            match arg(args, 2) {
+                // `$` references are only expanded for the matches of a regex
+                Some(JSONataValue::Value(Value::String(replacement))) if literal => {
+                    result.push_str(replacement)
+                }
+                Some(JSONataValue::Value(Value::String(replacement))) => {
+                    let matched = &s[found.start..found.end];
+                    result.push_str(&BuiltIns::substitute(replacement, matched, &found.groups));
+                }
+                Some(JSONataValue::Function(replacement)) => {
+                    result.push_str(&BuiltIns::replacement(replacement, s, &found)?);
+                }
+                _ => return Ok(None),
+            }
+            position = found.end;
+        }
+        result.push_str(&s[position..]);
+        Ok(Some(result.into()))
+    }
+
+    /// Expands the `$` references in a `replacement` string for a match,
+    /// following jsonata-js
+    ///
+    /// `$$` is a literal `$`, `$0` is the whole match and `$N` is the Nth
+    /// captured group. As many digits are read for N as there are in the number
+    /// of groups, and one fewer if that refers to a group which does not exist.
+    fn substitute(replacement: &str, matched: &str, groups: &[Option<String>]) -> String {
+        let max_digits = groups.len().max(1).to_string().len();
+
+        let mut result = String::new();
+        let mut rest = replacement;
+        while let Some(index) = rest.find('$') {
+            result.push_str(&rest[..index]);
+            rest = &rest[index + 1..];
+
+            if let Some(after) = rest.strip_prefix('$') {
+                result.push('$');
+                rest = after;
+            } else if let Some(after) = rest.strip_prefix('0') {
+                result.push_str(matched);
+                rest = after;
+            } else {
+                let digits = |count: usize| -> Option<usize> {
+                    let digits: String = rest
+                        .chars()
+                        .take(count)
+                        .take_while(char::is_ascii_digit)
+                        .collect();
+                    digits.parse().ok()
+                };
+                let group = match digits(max_digits) {
+                    Some(group) if max_digits > 1 && group > groups.len() => digits(max_digits - 1),
+                    group => group,
+                };
+                match group {
+                    Some(group) => {
+                        if let Some(Some(captured)) = groups.get(group.wrapping_sub(1)) {
+                            result.push_str(captured);
+                        }
+                        rest = &rest[group.to_string().len()..];
+                    }
+                    None => result.push('$'),
+                }
+            }
+        }
+        result.push_str(rest);
+        result
+    }
+
+    /// Calls a `replacement` function with the `$match` object of a match, and
+    /// returns the string it evaluates to
+    fn replacement(
+        replacement: &JSONataFunction,
+        s: &str,
+        found: &RegexMatch,
+    ) -> Result<String, EvaluationError> {
+        let matched = found.to_value(s);
+        match replacement.call(&[Some(matched.into())], None)? {
+            Some(JSONataValue::Value(Value::String(replaced))) => Ok(replaced),
+            Some(JSONataValue::Value(value)) => Err(EvaluationError::ReplacementNotString(value)),
+            Some(JSONataValue::Function(func)) => Err(
+                EvaluationError::FunctionCannotConvertToValue(func.ident().into()),
+            ),
+            None => Err(EvaluationError::ReplacementNotString(Value::Null)),
+        }
     }
 
     /// Parses and evaluates the string expr which contains literal JSON or a JSONata expression
@@ -332,8 +602,30 @@ impl BuiltIns {
     /// ```
     ///
     /// Optionally override the context by specifying the second parameter
-    pub(crate) fn eval(args: &[Option<JSONataValue>]) -> EvaluationResult {
-        todo!()
+    ///
+    /// The expression is evaluated in a child scope of the caller, so it sees the
+    /// variables in scope where `$eval` was called as well as the bindings and
+    /// registered functions of the host, but its own variables do not leak out.
+    /// It shares the clock and random numbers of the calling evaluation. A syntax error in
+    /// the expression raises D3120, and an error while evaluating it D3121.
+    pub(crate) fn eval(
+        context: &EvaluationContext,
+        args: &[Option<JSONataValue>],
+        input: Option<&Value>,
+    ) -> EvaluationResult {
+        let expr = match string_arg(args, 0) {
+            Some(expr) => expr,
+            None => return Ok(None),
+        };
+        let input = match arg(args, 1) {
+            Some(focus) => Value::try_from(focus.clone())?,
+            None => input.cloned().unwrap_or(Value::Null),
+        };
+
+        let expr = Expression::compile(expr)
+            .map_err(|diagnostics| EvaluationError::EvalSyntaxError(diagnostics.to_string()))?;
+        expr.evaluate_in(&input, context, context.caller())
+            .map_err(|error| EvaluationError::EvalDynamicError(error.to_string()))
     }
 
     /// Converts an ASCII string to a base 64 representation. Each each character in the string
@@ -346,19 +638,46 @@ impl BuiltIns {
     /// ```
     /// $base64encode("myuser:mypass") => "bXl1c2VyOm15cGFzcw=="
     /// ```
+    ///
+    /// As with `btoa` in JavaScript, a character outside of the 0x00 to 0xFF range is an error.
     pub(crate) fn base64_encode(args: &[Option<JSONataValue>]) -> EvaluationResult {
-        todo!()
+        let s = match string_arg(args, 0) {
+            Some(s) => s,
+            None => return Ok(None),
+        };
+        match encoding::base64_encode(s) {
+            Some(encoded) => Ok(Some(encoded.into())),
+            None => Err(EvaluationError::InvalidBase64(
+                "base64encode".into(),
+                s.into(),
+            )),
+        }
     }
 
-    /// Converts base 64 encoded bytes to a string, using a UTF-8 Unicode codepage.
+    /// Converts base 64 encoded bytes to a string, with a character for each byte.
     ///
     /// ## Examples
     ///
     /// ```
     /// $base64decode("bXl1c2VyOm15cGFzcw==") => "myuser:mypass"
     /// ```
+    ///
+    /// As with `atob` in JavaScript, whitespace and missing padding are allowed, and each byte
+    /// is decoded to the character in the 0x00 to 0xFF range with that code, so decoding UTF-8
+    /// bytes does not give back the characters that they encode. A string which is not base 64
+    /// is an error.
     pub(crate) fn base64_decode(args: &[Option<JSONataValue>]) -> EvaluationResult {
-        todo!()
+        let s = match string_arg(args, 0) {
+            Some(s) => s,
+            None => return Ok(None),
+        };
+        match encoding::base64_decode(s) {
+            Some(decoded) => Ok(Some(decoded.into())),
+            None => Err(EvaluationError::InvalidBase64(
+                "base64decode".into(),
+                s.into(),
+            )),
+        }
     }
 
     /// Encodes a Uniform Resource Locator (URL) component by replacing each instance of
@@ -370,9 +689,10 @@ impl BuiltIns {
     /// ```
     /// $encodeUrlComponent("?x=test") => "%3Fx%3Dtest"
     /// ```
-
+    ///
+    /// Escapes the same characters as `encodeURIComponent` in JavaScript.
     pub(crate) fn encode_url_component(args: &[Option<JSONataValue>]) -> EvaluationResult {
-        todo!()
+        Ok(string_arg(args, 0).map(|s| encoding::encode_uri_component(s).into()))
     }
 
     /// Encodes a Uniform Resource Locator (URL) by replacing each instance of certain
@@ -384,8 +704,11 @@ impl BuiltIns {
     /// ```
     /// $encodeUrl("https://mozilla.org/?x=шеллы") => "https://mozilla.org/?x=%D1%88%D0%B5%D0%BB%D0%BB%D1%8B"
     /// ```
+    ///
+    /// Escapes the same characters as `encodeURI` in JavaScript, which leaves the reserved
+    /// characters `;,/?:@&=+$#` unescaped.
     pub(crate) fn encode_url(args: &[Option<JSONataValue>]) -> EvaluationResult {
-        todo!()
+        Ok(string_arg(args, 0).map(|s| encoding::encode_uri(s).into()))
     }
 
     /// Decodes a Uniform Resource Locator (URL) component previously created by `encodeUrlComponent`.
@@ -395,8 +718,20 @@ impl BuiltIns {
     /// ```
     /// $decodeUrlComponent("%3Fx%3Dtest") => "?x=test"
     /// ```
+    ///
+    /// A malformed escape sequence, or one which is not UTF-8, raises D3140.
     pub(crate) fn decode_url_component(args: &[Option<JSONataValue>]) -> EvaluationResult {
-        todo!()
+        let s = match string_arg(args, 0) {
+            Some(s) => s,
+            None => return Ok(None),
+        };
+        match encoding::decode_uri_component(s) {
+            Some(decoded) => Ok(Some(decoded.into())),
+            None => Err(EvaluationError::MalformedUrl(
+                "decodeUrlComponent".into(),
+                s.into(),
+            )),
+        }
     }
 
     /// Decodes a Uniform Resource Locator (URL) previously created by `encodeUrl`.
@@ -405,7 +740,17 @@ impl BuiltIns {
     /// ```
     /// $decodeUrl("https://mozilla.org/?x=%D1%88%D0%B5%D0%BB%D0%BB%D1%8B") => "https://mozilla.org/?x=шеллы"
     /// ```
+    ///
+    /// As with `decodeURI` in JavaScript, the escape sequences of the reserved characters
+    /// `;,/?:@&=+$#` are left as they are. A malformed escape sequence raises D3140.
     pub(crate) fn decode_url(args: &[Option<JSONataValue>]) -> EvaluationResult {
-        todo!()
+        let s = match string_arg(args, 0) {
+            Some(s) => s,
+            None => return Ok(None),
+        };
+        match encoding::decode_uri(s) {
+            Some(decoded) => Ok(Some(decoded.into())),
+            None => Err(EvaluationError::MalformedUrl("decodeUrl".into(), s.into())),
+        }
     }
 }
@@ -1,6 +1,12 @@
 use serde_json::json;
 
-use crate::{builtins::BuiltIns, tests::make_val};
+use crate::{
+    builtins::BuiltIns,
+    environment::Environment,
+    evaluate::{EvaluationContext, EvaluationError},
+    tests::make_val,
+    value::JSONataValue,
+};
 
 #[test]
 fn string() {
@@ -8,11 +14,31 @@ fn string() {
         (make_val(json!(null)), "null"),
         (make_val(json!(true)), "true"),
         (make_val(json!(false)), "false"),
+        (make_val(json!("hello")), "hello"),
+        (make_val(json!(5)), "5"),
+        (make_val(json!(0.1 + 0.2)), "0.3"),
+        (make_val(json!(1e21)), "1e+21"),
+        (
+            make_val(json!([1, "a", { "b": null }])),
+            r#"[1,"a",{"b":null}]"#,
+        ),
+        (JSONataValue::from_func(|_| Ok(None), "f"), ""),
     ];
     for (input, expected) in cases {
-        let actual = BuiltIns::string(&[Some(input)]);
+        let actual = BuiltIns::string(&[Some(input), None]);
         assert_eq!(actual, Ok(Some(expected.into())));
     }
+    assert_eq!(BuiltIns::string(&[None, None]), Ok(None));
+}
+
+#[test]
+fn string_prettify() {
+    let input = make_val(json!({ "a": [1, 2] }));
+    let actual = BuiltIns::string(&[Some(input), Some(true.into())]);
+    assert_eq!(
+        actual,
+        Ok(Some("{\n  \"a\": [\n    1,\n    2\n  ]\n}".into()))
+    );
 }
 
 #[test]
@@ -20,10 +46,561 @@ fn length() {
     let cases = vec![
         (make_val(json!("hello")), 5),
         (make_val(json!("test test")), 9),
-        // TODO: test unicode
+        (make_val(json!("")), 0),
+        // astral plane characters are a single character
+        (make_val(json!("😀👍")), 2),
+        (make_val(json!("日本語")), 3),
+        (make_val(json!("שלום")), 4),
+        // combining marks are characters of their own
+        (make_val(json!("e\u{301}")), 2),
     ];
     for (input, expected) in cases {
         let actual = BuiltIns::length(&[Some(input)]);
         assert_eq!(actual, Ok(Some(expected.into())));
     }
 }
+
+#[test]
+fn uppercase_lowercase() {
+    let hello = make_val(json!("Hello World"));
+    assert_eq!(
+        BuiltIns::uppercase(&[Some(hello.clone())]),
+        Ok(Some("HELLO WORLD".into()))
+    );
+    assert_eq!(
+        BuiltIns::lowercase(&[Some(hello)]),
+        Ok(Some("hello world".into()))
+    );
+}
+
+#[test]
+fn trim() {
+    let cases = vec![
+        (" Hello \n World ", "Hello World"),
+        ("\t\r\n", ""),
+        ("a  b", "a b"),
+    ];
+    for (input, expected) in cases {
+        let actual = BuiltIns::trim(&[Some(input.into())]);
+        assert_eq!(actual, Ok(Some(expected.into())));
+    }
+}
+
+#[test]
+fn join() {
+    let strings = make_val(json!(["a", "b", "c"]));
+    assert_eq!(
+        BuiltIns::join(&[Some(strings.clone()), None]),
+        Ok(Some("abc".into()))
+    );
+    assert_eq!(
+        BuiltIns::join(&[Some(strings), Some(", ".into())]),
+        Ok(Some("a, b, c".into()))
+    );
+}
+
+#[test]
+fn substring() {
+    let cases = vec![
+        ("Hello World", 3, None, "lo World"),
+        ("Hello World", 3, Some(5), "lo Wo"),
+        ("Hello World", -4, None, "orld"),
+        ("Hello World", -4, Some(2), "or"),
+        ("Hello World", -20, Some(2), "He"),
+        ("Hello World", 20, None, ""),
+        ("Hello World", 3, Some(-1), ""),
+        ("😀👍🎉", 1, Some(1), "👍"),
+        ("😀👍🎉", -1, None, "🎉"),
+        ("日本語のテキスト", 2, Some(3), "語のテ"),
+        ("مرحبا بالعالم", 0, Some(5), "مرحبا"),
+    ];
+    for (s, start, length, expected) in cases {
+        let actual = BuiltIns::substring(&[
+            Some(s.into()),
+            Some(start.into()),
+            length.map(JSONataValue::from),
+        ]);
+        assert_eq!(actual, Ok(Some(expected.into())));
+    }
+}
+
+#[test]
+fn substring_before_after() {
+    let cases = vec![
+        ("Hello World", " ", "Hello", "World"),
+        ("Hello World", "x", "Hello World", "Hello World"),
+        ("😀👍🎉", "👍", "😀", "🎉"),
+        ("東京都渋谷区", "都", "東京", "渋谷区"),
+        ("שלום עולם", " ", "שלום", "עולם"),
+    ];
+    for (s, chars, before, after) in cases {
+        let args = [Some(s.into()), Some(chars.into())];
+        assert_eq!(BuiltIns::substring_before(&args), Ok(Some(before.into())));
+        assert_eq!(BuiltIns::substring_after(&args), Ok(Some(after.into())));
+    }
+}
+
+#[test]
+fn pad() {
+    let cases = vec![
+        ("foo", 5, None, "foo  "),
+        ("foo", -5, None, "  foo"),
+        ("foo", -5, Some("#"), "##foo"),
+        ("foo", 8, Some("ab"), "fooababa"),
+        ("foo", 2, None, "foo"),
+        ("😀", 3, Some("👍"), "😀👍👍"),
+        ("日本", -4, Some("・"), "・・日本"),
+    ];
+    for (s, width, pad_char, expected) in cases {
+        let actual = BuiltIns::pad(&[
+            Some(s.into()),
+            Some(width.into()),
+            pad_char.map(JSONataValue::from),
+        ]);
+        assert_eq!(actual, Ok(Some(expected.into())));
+    }
+
+    for width in [1e300, -1e9] {
+        let error = BuiltIns::pad(&[Some("x".into()), Some(width.into())]).unwrap_err();
+        assert_eq!(error, EvaluationError::PadWidthTooLarge(width));
+        assert_eq!(error.code(), "D0000");
+    }
+}
+
+#[test]
+fn split() {
+    let cases = vec![
+        ("so many words", " ", None, json!(["so", "many", "words"])),
+        ("so many words", " ", Some(2), json!(["so", "many"])),
+        ("so many words", " ", Some(0), json!([])),
+        ("a,b,", ",", None, json!(["a", "b", ""])),
+        ("😀👍🎉", "", None, json!(["😀", "👍", "🎉"])),
+        ("日本語", "", Some(2), json!(["日", "本"])),
+        ("e\u{301}", "", None, json!(["e", "\u{301}"])),
+        ("שלום, עולם", ", ", None, json!(["שלום", "עולם"])),
+    ];
+    for (s, separator, limit, expected) in cases {
+        let actual = BuiltIns::split(&[
+            Some(s.into()),
+            Some(separator.into()),
+            limit.map(JSONataValue::from),
+        ]);
+        assert_eq!(actual, Ok(Some(make_val(expected))));
+    }
+
+    assert_eq!(
+        BuiltIns::split(&[Some("a b".into()), Some(" ".into()), Some((-1).into())]),
+        Err(EvaluationError::SplitNegativeLimit)
+    );
+}
+
+fn regex(pattern: &str, flags: &str) -> Option<JSONataValue> {
+    Some(JSONataValue::from_regex(pattern, flags).unwrap())
+}
+
+#[test]
+fn contains() {
+    let cases = vec![
+        (Some("bra".into()), true),
+        (regex("a.*a", ""), true),
+        (regex("ar.*a", ""), false),
+        (regex("^abr", ""), true),
+        (regex("^bra", ""), false),
+        (regex("x*", ""), true),
+    ];
+    for (pattern, expected) in cases {
+        let actual = BuiltIns::contains(&[Some("abracadabra".into()), pattern]);
+        assert_eq!(actual, Ok(Some(expected.into())));
+    }
+
+    let hello = Some(JSONataValue::from("Hello World"));
+    assert_eq!(
+        BuiltIns::contains(&[hello.clone(), regex("wo", "")]),
+        Ok(Some(false.into()))
+    );
+    assert_eq!(
+        BuiltIns::contains(&[hello, regex("wo", "i")]),
+        Ok(Some(true.into()))
+    );
+    assert_eq!(BuiltIns::contains(&[None, regex("a", "")]), Ok(None));
+}
+
+#[test]
+fn split_regex() {
+    let s = "too much, punctuation. hard; to read";
+    let words = json!(["too", "much", "punctuation", "hard", "to", "read"]);
+    let cases = vec![
+        (s, None, words),
+        (s, Some(3), json!(["too", "much", "punctuation"])),
+        (s, Some(0), json!([])),
+        ("no-separators", None, json!(["no-separators"])),
+        (",a,", None, json!(["", "a", ""])),
+    ];
+    for (s, limit, expected) in cases {
+        let actual = BuiltIns::split(&[
+            Some(s.into()),
+            regex("[ ,.;]+", ""),
+            limit.map(JSONataValue::from),
+        ]);
+        assert_eq!(actual, Ok(Some(make_val(expected))), "{}", s);
+    }
+
+    assert_eq!(
+        BuiltIns::split(&[Some("abc".into()), regex("x*", ""), None]),
+        Err(EvaluationError::RegexMatchesEmptyString)
+    );
+}
+
+#[test]
+fn r#match() {
+    let actual = BuiltIns::r#match(&[Some("ababbabbcc".into()), regex("a(b+)", ""), None]);
+    let expected = json!([
+        { "match": "ab", "index": 0, "groups": ["b"] },
+        { "match": "abb", "index": 2, "groups": ["bb"] },
+        { "match": "abb", "index": 5, "groups": ["bb"] },
+    ]);
+    assert_eq!(actual, Ok(Some(make_val(expected))));
+
+    let actual = BuiltIns::r#match(&[
+        Some("ababbabbcc".into()),
+        regex("a(b+)", ""),
+        Some(1.into()),
+    ]);
+    let expected = json!({ "match": "ab", "index": 0, "groups": ["b"] });
+    assert_eq!(actual, Ok(Some(make_val(expected))));
+
+    // indexes count characters, and groups which did not match are null
+    let actual = BuiltIns::r#match(&[Some("😀 ab".into()), regex("(a)|(b)", ""), None]);
+    let expected = json!([
+        { "match": "a", "index": 2, "groups": ["a", null] },
+        { "match": "b", "index": 3, "groups": [null, "b"] },
+    ]);
+    assert_eq!(actual, Ok(Some(make_val(expected))));
+
+    let actual = BuiltIns::r#match(&[Some("abc".into()), regex("x", ""), None]);
+    assert_eq!(actual, Ok(None));
+
+    let actual = BuiltIns::r#match(&[Some("abc".into()), regex("a", ""), Some((-1).into())]);
+    assert_eq!(actual, Err(EvaluationError::MatchNegativeLimit));
+
+    // an empty match is only allowed as the last match
+    let actual = BuiltIns::r#match(&[Some("abc".into()), regex("x*$", ""), None]);
+    let expected = json!({ "match": "", "index": 3, "groups": [] });
+    assert_eq!(actual, Ok(Some(make_val(expected))));
+    let actual = BuiltIns::r#match(&[Some("abc".into()), regex("b?", ""), None]);
+    assert_eq!(actual, Err(EvaluationError::RegexMatchesEmptyString));
+}
+
+#[test]
+fn match_custom_matcher() {
+    // matches each digit, given the rest of the string after the previous match
+    let digits = JSONataValue::from_func(
+        |args| {
+            let rest = args[0]
+                .as_ref()
+                .and_then(|s| s.as_value()?.as_str())
+                .unwrap();
+            Ok(rest.chars().position(|c| c.is_ascii_digit()).map(|start| {
+                let matched = rest.chars().nth(start).unwrap().to_string();
+                make_val(json!({ "match": matched, "start": start, "end": start + 1 }))
+            }))
+        },
+        "digits",
+    );
+    let actual = BuiltIns::r#match(&[Some("a1b22".into()), Some(digits), None]);
+    let expected = json!([
+        { "match": "1", "index": 1, "groups": [] },
+        { "match": "2", "index": 3, "groups": [] },
+        { "match": "2", "index": 4, "groups": [] },
+    ]);
+    assert_eq!(actual, Ok(Some(make_val(expected))));
+
+    let broken = JSONataValue::from_func(|_| Ok(Some(make_val(json!("1")))), "broken");
+    assert_eq!(
+        BuiltIns::r#match(&[Some("a1".into()), Some(broken), None]),
+        Err(EvaluationError::InvalidMatcherResult("broken".into()))
+    );
+}
+
+#[test]
+fn replace() {
+    let cases = vec![
+        (
+            "John Smith and John Jones",
+            Some("John".into()),
+            "Mr",
+            None,
+            "Mr Smith and Mr Jones",
+        ),
+        (
+            "John Smith and John Jones",
+            Some("John".into()),
+            "Mr",
+            Some(1),
+            "Mr Smith and John Jones",
+        ),
+        ("John Smith", Some("John".into()), "$1", None, "$1 Smith"),
+        ("abracadabra", regex("a.*?a", ""), "*", None, "*c*bra"),
+        (
+            "John Smith",
+            regex(r"(\w+)\s(\w+)", ""),
+            "$2, $1",
+            None,
+            "Smith, John",
+        ),
+        ("265USD", regex("([0-9]+)USD", ""), "$$$1", None, "$265"),
+        ("abc", regex("b", ""), "[$0]", None, "a[b]c"),
+        ("abc", regex("(b)", ""), "$2$", None, "a$c"),
+        ("abc", regex("(b)", ""), "$12$x", None, "ab2$xc"),
+        (
+            "abcdefghijk",
+            regex("(a)(b)(c)(d)(e)(f)(g)(h)(i)(j)(k)", ""),
+            "$11$12",
+            None,
+            "ka2",
+        ),
+        (
+            "abcdefghijk",
+            regex("(a)(b)(c)(d)(e)(f)(g)(h)(i)(j)(k)", ""),
+            "$13",
+            None,
+            "a3",
+        ),
+        ("aaa", regex("a", ""), "b", Some(0), "aaa"),
+        ("aaa", regex("a", ""), "b", Some(2), "bba"),
+    ];
+    for (s, pattern, replacement, limit, expected) in cases {
+        let actual = BuiltIns::replace(&[
+            Some(s.into()),
+            pattern,
+            Some(replacement.into()),
+            limit.map(JSONataValue::from),
+        ]);
+        assert_eq!(actual, Ok(Some(expected.into())), "{} {}", s, replacement);
+    }
+}
+
+#[test]
+fn replace_with_function() {
+    let double = JSONataValue::from_func(
+        |args| {
+            let matched = args[0]
+                .as_ref()
+                .and_then(|m| m.as_value()?.get("groups")?.get(0));
+            let n = matched
+                .and_then(|n| n.as_str()?.parse::<u32>().ok())
+                .unwrap();
+            Ok(Some(format!("{}", n * 2).into()))
+        },
+        "double",
+    );
+    let actual = BuiltIns::replace(&[
+        Some("1 apple and 20 pears".into()),
+        regex("([0-9]+)", ""),
+        Some(double),
+        None,
+    ]);
+    assert_eq!(actual, Ok(Some("2 apple and 40 pears".into())));
+
+    let number = JSONataValue::from_func(|_| Ok(Some(5.into())), "number");
+    let actual = BuiltIns::replace(&[Some("abc".into()), regex("b", ""), Some(number), None]);
+    assert_eq!(actual, Err(EvaluationError::ReplacementNotString(json!(5))));
+}
+
+#[test]
+fn replace_errors() {
+    let replace = |pattern, limit: Option<i32>| {
+        BuiltIns::replace(&[
+            Some("abc".into()),
+            pattern,
+            Some("x".into()),
+            limit.map(JSONataValue::from),
+        ])
+    };
+    assert_eq!(
+        replace(Some("".into()), None),
+        Err(EvaluationError::ReplaceEmptyPattern)
+    );
+    assert_eq!(
+        replace(Some("b".into()), Some(-1)),
+        Err(EvaluationError::ReplaceNegativeLimit)
+    );
+    assert_eq!(
+        replace(regex("x*", ""), None),
+        Err(EvaluationError::RegexMatchesEmptyString)
+    );
+}
+
+#[test]
+fn eval() {
+    let context = EvaluationContext::new(&Environment::new());
+    let input = json!({ "a": 5 });
+    let eval = |args: &[Option<JSONataValue>]| BuiltIns::eval(&context, args, Some(&input));
+
+    assert_eq!(
+        eval(&[Some(make_val(json!("a + 1"))), None]),
+        Ok(Some(make_val(json!(6))))
+    );
+    assert_eq!(
+        eval(&[
+            Some(make_val(json!("a + 1"))),
+            Some(make_val(json!({ "a": 1 })))
+        ]),
+        Ok(Some(make_val(json!(2))))
+    );
+    assert_eq!(
+        BuiltIns::eval(&context, &[Some(make_val(json!("a"))), None], None),
+        Ok(None)
+    );
+    assert_eq!(eval(&[None, None]), Ok(None));
+}
+
+#[test]
+fn base64() {
+    let cases = vec![
+        ("myuser:mypass", "bXl1c2VyOm15cGFzcw=="),
+        ("", ""),
+        ("a", "YQ=="),
+        ("ab", "YWI="),
+        ("abc", "YWJj"),
+        ("\u{e9}\u{ff}\u{0}", "6f8A"),
+    ];
+    for (decoded, encoded) in cases {
+        assert_eq!(
+            BuiltIns::base64_encode(&[Some(make_val(json!(decoded)))]),
+            Ok(Some(make_val(json!(encoded))))
+        );
+        assert_eq!(
+            BuiltIns::base64_decode(&[Some(make_val(json!(encoded)))]),
+            Ok(Some(make_val(json!(decoded))))
+        );
+    }
+
+    // as with `atob`, whitespace and missing padding are allowed, and bytes
+    // are not decoded as UTF-8
+    let cases = vec![
+        ("bXl1 c2Vy\nOm15", "myuser:my"),
+        ("YQ", "a"),
+        ("w6k=", "\u{c3}\u{a9}"),
+    ];
+    for (encoded, decoded) in cases {
+        assert_eq!(
+            BuiltIns::base64_decode(&[Some(make_val(json!(encoded)))]),
+            Ok(Some(make_val(json!(decoded))))
+        );
+    }
+
+    assert_eq!(
+        BuiltIns::base64_encode(&[Some(make_val(json!("шеллы")))]),
+        Err(EvaluationError::InvalidBase64(
+            "base64encode".into(),
+            "шеллы".into()
+        ))
+    );
+    for encoded in ["YQ=", "Y", "YQ===", "Y-Q=", "=YQ="] {
+        assert_eq!(
+            BuiltIns::base64_decode(&[Some(make_val(json!(encoded)))]),
+            Err(EvaluationError::InvalidBase64(
+                "base64decode".into(),
+                encoded.into()
+            )),
+            "{}",
+            encoded
+        );
+    }
+
+    // a deliberate extension, jsonata-js has no code for these errors
+    let error = BuiltIns::base64_decode(&[Some(make_val(json!("Y-Q=")))]).unwrap_err();
+    assert_eq!(error.code(), "D0000");
+    assert_eq!(
+        error.to_string(),
+        r#"Invalid string passed to $base64decode(): "Y-Q=""#
+    );
+    assert_eq!(BuiltIns::base64_encode(&[None]), Ok(None));
+    assert_eq!(BuiltIns::base64_decode(&[None]), Ok(None));
+}
+
+#[test]
+fn encode_url() {
+    let cases = vec![
+        (
+            "https://mozilla.org/?x=шеллы",
+            "https%3A%2F%2Fmozilla.org%2F%3Fx%3D%D1%88%D0%B5%D0%BB%D0%BB%D1%8B",
+            "https://mozilla.org/?x=%D1%88%D0%B5%D0%BB%D0%BB%D1%8B",
+        ),
+        (
+            ";,/?:@&=+$#",
+            "%3B%2C%2F%3F%3A%40%26%3D%2B%24%23",
+            ";,/?:@&=+$#",
+        ),
+        ("-_.!~*'()", "-_.!~*'()", "-_.!~*'()"),
+        (
+            "a b%[]😀",
+            "a%20b%25%5B%5D%F0%9F%98%80",
+            "a%20b%25%5B%5D%F0%9F%98%80",
+        ),
+    ];
+    for (decoded, component, url) in cases {
+        let arg = [Some(make_val(json!(decoded)))];
+        assert_eq!(
+            BuiltIns::encode_url_component(&arg),
+            Ok(Some(make_val(json!(component))))
+        );
+        assert_eq!(BuiltIns::encode_url(&arg), Ok(Some(make_val(json!(url)))));
+        assert_eq!(
+            BuiltIns::decode_url_component(&[Some(make_val(json!(component)))]),
+            Ok(Some(make_val(json!(decoded))))
+        );
+        assert_eq!(
+            BuiltIns::decode_url(&[Some(make_val(json!(url)))]),
+            Ok(Some(make_val(json!(decoded))))
+        );
+    }
+    assert_eq!(BuiltIns::encode_url(&[None]), Ok(None));
+}
+
+#[test]
+fn decode_url() {
+    // `decodeURI` leaves the escape sequences of reserved characters
+    let arg = [Some(make_val(json!("%3fx%3D%41%c3%a9")))];
+    assert_eq!(
+        BuiltIns::decode_url_component(&arg),
+        Ok(Some(make_val(json!("?x=Aé"))))
+    );
+    assert_eq!(
+        BuiltIns::decode_url(&arg),
+        Ok(Some(make_val(json!("%3fx%3DAé"))))
+    );
+
+    let malformed = [
+        "%",
+        "%4",
+        "%G1",
+        "%+1",
+        "%C3",
+        "%C3%41",
+        "%80",
+        "%C0%AF",
+        "%ED%A0%80",
+        "%F8%80%80%80%80",
+    ];
+    for s in malformed {
+        let arg = [Some(make_val(json!(s)))];
+        assert_eq!(
+            BuiltIns::decode_url_component(&arg),
+            Err(EvaluationError::MalformedUrl(
+                "decodeUrlComponent".into(),
+                s.into()
+            )),
+            "{}",
+            s
+        );
+        assert_eq!(
+            BuiltIns::decode_url(&arg),
+            Err(EvaluationError::MalformedUrl("decodeUrl".into(), s.into())),
+            "{}",
+            s
+        );
+    }
+    assert_eq!(BuiltIns::decode_url(&[None]), Ok(None));
+}
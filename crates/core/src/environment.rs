@@ -0,0 +1,111 @@
+use std::{cell::RefCell, rc::Rc};
+
+use serde_json::Value;
+
+use crate::{
+    builtins::BuiltIns,
+    evaluate::{
+        Clock, EvaluationError, EvaluationResult, Evaluations, JSONataVariables, RandomSource,
+        Randomness, SystemClock,
+    },
+    value::{signature::Signature, JSONataValue},
+};
+
+/// Bindings shared by every evaluation of an expression
+///
+/// A new environment contains the JSONata built-in functions. Values bound by
+/// the host are available to expressions as variables, for example a value
+/// bound with the name `"rate"` is referenced as `$rate`.
+pub struct Environment {
+    /// Shared with the evaluations of `$eval`, which see the same bindings
+    pub(crate) variables: Rc<JSONataVariables>,
+    /// Built-ins which read the state of the evaluation in progress, looked up
+    /// after `variables` so that the host may still replace them
+    pub(crate) evaluation_builtins: Rc<JSONataVariables>,
+    pub(crate) evaluations: Rc<Evaluations>,
+    pub(crate) clock: Rc<dyn Clock>,
+    pub(crate) random: Randomness,
+}
+
+impl Environment {
+    pub fn new() -> Self {
+        let mut variables = JSONataVariables::new();
+        BuiltIns::populate_context(&mut variables);
+
+        let evaluations = Rc::new(Evaluations::default());
+        let mut evaluation_builtins = JSONataVariables::new();
+        BuiltIns::populate_evaluation_context(&mut evaluation_builtins, &evaluations);
+
+        Self {
+            variables: Rc::new(variables),
+            evaluation_builtins: Rc::new(evaluation_builtins),
+            evaluations,
+            clock: Rc::new(SystemClock),
+            random: Randomness::Entropy,
+        }
+    }
+
+    /// Replaces the clock which the date and time built-ins read the current
+    /// time from, the system time by default.
+    pub fn set_clock<C: 'static + Clock>(&mut self, clock: C) {
+        self.clock = Rc::new(clock);
+    }
+
+    /// Seeds the random numbers of `$random()` and `$shuffle()` with `seed` at
+    /// the start of every evaluation, so that evaluating an expression against
+    /// the same input always gives the same result, on any platform. By default
+    /// each evaluation is seeded by the operating system.
+    pub fn set_random_seed(&mut self, seed: u64) {
+        self.random = Randomness::Seed(seed);
+    }
+
+    /// Replaces the source which `$random()` and `$shuffle()` draw random numbers
+    /// from. Unlike a seed, the source is not reset between evaluations, so each
+    /// evaluation continues from where the previous one left off.
+    pub fn set_random_source<R: 'static + RandomSource>(&mut self, source: R) {
+        self.random = Randomness::Source(Rc::new(RefCell::new(source)));
+    }
+
+    /// Removes the `$eval` built-in, so that expressions from untrusted sources
+    /// cannot compile and evaluate strings from their input. Calling `$eval`
+    /// is then an error unless the host binds a function of that name itself.
+    pub fn disable_eval(&mut self) {
+        Rc::make_mut(&mut self.evaluation_builtins).remove("eval");
+    }
+
+    /// Binds `value` to the variable `name`, which is given without
+    /// the preceding `$` symbol.
+    pub fn bind<N: Into<String>>(&mut self, name: N, value: Value) {
+        Rc::make_mut(&mut self.variables).insert(name.into(), Some(value.into()));
+    }
+
+    /// Registers a host function which expressions can call as `$name(...)`
+    ///
+    /// The arguments of each call are validated against `signature`, for example
+    /// `"<n-s?:n>"`, before `func` is called, so `func` can rely on receiving
+    /// arguments of the declared types. `None` represents an undefined argument.
+    ///
+    /// Returns an error if the signature cannot be parsed.
+    pub fn register_function<N, F>(
+        &mut self,
+        name: N,
+        signature: &str,
+        func: F,
+    ) -> Result<(), EvaluationError>
+    where
+        N: Into<String>,
+        F: 'static + Fn(&[Option<JSONataValue>]) -> EvaluationResult,
+    {
+        let name = name.into();
+        let signature = Signature::parse(signature)?;
+        let func = JSONataValue::from_func_with_signature(func, name.clone(), signature);
+        Rc::make_mut(&mut self.variables).insert(name, Some(func));
+        Ok(())
+    }
+}
+
+impl Default for Environment {
+    fn default() -> Self {
+        Self::new()
+    }
+}
@@ -0,0 +1,189 @@
+use std::{
+    cell::{RefCell, RefMut},
+    rc::Rc,
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+use rand::{Rng, RngCore, SeedableRng};
+use rand_chacha::ChaCha8Rng;
+
+use crate::{environment::Environment, value::JSONataValue};
+
+use super::{EvaluationError, Frame, JSONataVariables};
+
+/// Source of the current time for the date and time built-ins
+///
+/// The clock is read once at the start of each evaluation, so all calls to
+/// `$now()` and `$millis()` within an evaluation return the same timestamp.
+/// Any closure returning milliseconds since the Unix Epoch is a clock, which
+/// can be used to pin the time in tests.
+///
+/// ```
+/// let mut env = Environment::new();
+/// env.set_clock(|| 1510067557121);
+/// ```
+pub trait Clock {
+    /// Milliseconds since the Unix Epoch (1 January, 1970 UTC)
+    fn millis(&self) -> i64;
+}
+
+impl<F> Clock for F
+where
+    F: Fn() -> i64,
+{
+    fn millis(&self) -> i64 {
+        self()
+    }
+}
+
+/// The default clock, which reads the system time
+#[derive(Debug, Clone, Copy, Default)]
+pub struct SystemClock;
+
+impl Clock for SystemClock {
+    fn millis(&self) -> i64 {
+        match SystemTime::now().duration_since(UNIX_EPOCH) {
+            Ok(duration) => duration.as_millis() as i64,
+            Err(error) => -(error.duration().as_millis() as i64),
+        }
+    }
+}
+
+/// Source of the random numbers for `$random()` and `$shuffle()`
+///
+/// Any random number generator of the `rand` crate is a random source, or
+/// the host can implement the trait to control the numbers drawn in tests.
+///
+/// ```
+/// let mut env = Environment::new();
+/// env.set_random_source(rand::rngs::OsRng);
+/// ```
+pub trait RandomSource {
+    /// A random number greater than or equal to zero and less than one
+    fn next_f64(&mut self) -> f64;
+}
+
+impl<R> RandomSource for R
+where
+    R: RngCore,
+{
+    fn next_f64(&mut self) -> f64 {
+        self.gen()
+    }
+}
+
+/// Where the random numbers of each evaluation are drawn from
+#[derive(Clone)]
+pub(crate) enum Randomness {
+    /// A generator seeded by the operating system for each evaluation
+    Entropy,
+    /// A generator seeded with the same seed for each evaluation
+    Seed(u64),
+    /// A source set by the host, shared by every evaluation
+    Source(Rc<RefCell<dyn RandomSource>>),
+}
+
+/// State captured once at the start of an evaluation
+///
+/// Expressions evaluated by `$eval` share the context of the evaluation that
+/// called it.
+pub(crate) struct EvaluationContext {
+    globals: Rc<JSONataVariables>,
+    builtins: Rc<JSONataVariables>,
+    timestamp: i64,
+    random: Rc<RefCell<dyn RandomSource>>,
+    /// Frames of the function calls in progress, innermost last
+    callers: RefCell<Vec<Rc<Frame>>>,
+}
+
+impl EvaluationContext {
+    pub(crate) fn new(env: &Environment) -> Self {
+        // a fixed algorithm, unlike `StdRng`, so that a seed draws the same
+        // numbers across versions of `rand` and platforms
+        let random: Rc<RefCell<dyn RandomSource>> = match &env.random {
+            Randomness::Entropy => Rc::new(RefCell::new(ChaCha8Rng::from_entropy())),
+            Randomness::Seed(seed) => Rc::new(RefCell::new(ChaCha8Rng::seed_from_u64(*seed))),
+            Randomness::Source(source) => source.clone(),
+        };
+
+        Self {
+            globals: env.variables.clone(),
+            builtins: env.evaluation_builtins.clone(),
+            timestamp: env.clock.millis(),
+            random,
+            callers: RefCell::default(),
+        }
+    }
+
+    /// The variables of the environment
+    pub(crate) fn globals(&self) -> &JSONataVariables {
+        &self.globals
+    }
+
+    /// Looks up one of the built-ins which depend on the state of an
+    /// evaluation, after the variables of the environment so that the host
+    /// may still replace them
+    pub(crate) fn variable(&self, name: &str) -> Option<&Option<JSONataValue>> {
+        self.builtins.get(name)
+    }
+
+    /// Milliseconds since the Unix Epoch at the start of the evaluation
+    pub(crate) fn timestamp(&self) -> i64 {
+        self.timestamp
+    }
+
+    pub(crate) fn random(&self) -> RefMut<'_, dyn RandomSource> {
+        self.random.borrow_mut()
+    }
+
+    /// Records `frame` as the scope of the function call about to be made,
+    /// until the matching [`EvaluationContext::exit_call`]
+    pub(crate) fn enter_call(&self, frame: Rc<Frame>) {
+        self.callers.borrow_mut().push(frame);
+    }
+
+    pub(crate) fn exit_call(&self) {
+        self.callers.borrow_mut().pop();
+    }
+
+    /// The frame of the innermost function call in progress
+    pub(crate) fn caller(&self) -> Option<Rc<Frame>> {
+        self.callers.borrow().last().cloned()
+    }
+}
+
+/// The evaluations in progress within an environment, innermost last
+///
+/// The built-ins which depend on the state of an evaluation are bound once in
+/// the environment and read that state from here when they are called. An
+/// evaluation is nested when a host function evaluates another expression.
+#[derive(Default)]
+pub(crate) struct Evaluations(RefCell<Vec<Rc<EvaluationContext>>>);
+
+impl Evaluations {
+    /// Makes `context` the current evaluation until the returned guard is
+    /// dropped
+    pub(crate) fn enter(&self, context: Rc<EvaluationContext>) -> EvaluationGuard<'_> {
+        self.0.borrow_mut().push(context);
+        EvaluationGuard(self)
+    }
+
+    /// The context of the innermost evaluation, or an error for the built-in
+    /// `name` when it is called outside of any evaluation, as the host may
+    /// keep the function and call it afterwards
+    pub(crate) fn current(&self, name: &str) -> Result<Rc<EvaluationContext>, EvaluationError> {
+        self.0
+            .borrow()
+            .last()
+            .cloned()
+            .ok_or_else(|| EvaluationError::NoEvaluationInProgress(name.into()))
+    }
+}
+
+pub(crate) struct EvaluationGuard<'a>(&'a Evaluations);
+
+impl Drop for EvaluationGuard<'_> {
+    fn drop(&mut self) {
+        self.0 .0.borrow_mut().pop();
+    }
+}
@@ -0,0 +1,866 @@
+use std::fmt;
+
+use serde_json::Value;
+use text_size::TextRange;
+
+/// Arithmetic operators, used to report which operator received
+/// a non-numeric operand
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ArithmeticOpType {
+    Add,
+    Sub,
+    Mul,
+    Div,
+    Rem,
+}
+
+impl fmt::Display for ArithmeticOpType {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(match self {
+            ArithmeticOpType::Add => "+",
+            ArithmeticOpType::Sub => "-",
+            ArithmeticOpType::Mul => "*",
+            ArithmeticOpType::Div => "/",
+            ArithmeticOpType::Rem => "%",
+        })
+    }
+}
+
+/// Side of a binary operator
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Side {
+    Left,
+    Right,
+}
+
+/// Error produced while evaluating an expression
+///
+/// Each variant corresponds to an error code of the reference implementation,
+/// available from [`EvaluationError::code`], and is displayed with the same
+/// message as jsonata-js. Errors which jsonata-js raises without a code, or does
+/// not raise at all, are extensions of this implementation and use the
+/// non-standard code `X0000` of their category, i.e. `S0000`, `T0000` or `D0000`.
+///
+/// Errors returned from an evaluation are wrapped in [`EvaluationError::WithRange`]
+/// to record the sub-expression that raised them.
+#[derive(Debug, Clone, PartialEq)]
+pub enum EvaluationError {
+    /// An error along with the range of the sub-expression that raised it
+    WithRange(Box<EvaluationError>, TextRange),
+
+    // Static errors, raised while compiling an expression
+    /// S0101
+    StringLiteralUnterminated,
+    /// S0102
+    NumberLiteralOutOfRange(String),
+    /// S0103
+    UnsupportedEscapeSequence(String),
+    /// S0104
+    InvalidUnicodeEscape,
+    /// S0105
+    QuotedPropertyUnterminated,
+    /// S0106
+    CommentUnterminated,
+    /// S0201
+    SyntaxError(String),
+    /// S0202: (expected, found)
+    ExpectedToken(String, String),
+    /// S0203
+    ExpectedTokenBeforeEnd(String),
+    /// S0204
+    UnknownOperator(String),
+    /// S0205
+    UnexpectedToken(String),
+    /// S0206
+    UnknownExpressionType(String),
+    /// S0207
+    UnexpectedEnd,
+    /// S0208
+    FunctionParameterNotVariable(String),
+    /// S0209
+    PredicateAfterGroupBy,
+    /// S0210
+    MultipleGroupBy,
+    /// S0211
+    InvalidUnaryOperator(String),
+    /// S0212
+    AssignToNonVariable,
+    /// S0213
+    LiteralPathStep(Value),
+    /// S0214
+    BindToNonVariable(String),
+    /// S0215
+    ContextBindingAfterPredicate,
+    /// S0216
+    ContextBindingAfterOrderBy,
+    /// S0217
+    ParentNotDerivable(String),
+    /// S0301
+    EmptyRegex,
+    /// S0302
+    RegexUnterminated,
+    /// S0401
+    TypeParameterNotAllowed,
+    /// S0402
+    ParameterizedChoiceGroup,
+    /// S0500
+    ExpressionHasSyntaxErrors,
+
+    // Type errors
+    /// T0410: (argument index, function name)
+    FunctionSignatureMismatch(usize, String),
+    /// T0411: (argument index, function name)
+    FunctionContextMismatch(usize, String),
+    /// T0412: (argument index, function name, expected member type)
+    FunctionArrayMemberMismatch(usize, String, String),
+    /// T1003
+    ObjectKeyNotString(Value),
+    /// T1005: holds the name of the path that was invoked
+    InvokeNonFunctionNamed(String),
+    /// T1006
+    InvokeNonFunction,
+    /// T1007: holds the name of the path that was partially applied
+    PartialApplyNonFunctionNamed(String),
+    /// T1008
+    PartialApplyNonFunction,
+    /// T1010: holds the name of the function given the matcher
+    InvalidMatcherResult(String),
+    /// T2001 or T2002, depending on the side of the operator
+    DyadicMustBeNumber(ArithmeticOpType, Side),
+    /// T2003 or T2004, depending on the side of the range operator
+    RangeMustBeInteger(Side),
+    /// T2006
+    ApplyToNonFunction,
+    /// T2007
+    OrderByTypeMismatch(Box<Value>, Box<Value>),
+    /// T2008
+    OrderByNotComparable,
+    /// T2009: (lhs, rhs, operator)
+    ComparisonTypeMismatch(Box<Value>, Box<Value>, String),
+    /// T2010: holds the operator
+    ComparisonNotComparable(String),
+    /// T2011
+    TransformUpdateNotObject(Value),
+    /// T2012
+    TransformDeleteNotStrings(Value),
+    /// T2013
+    TransformCloneOverridden,
+
+    // Dynamic errors
+    /// D1001
+    NumberOutOfRange(f64),
+    /// D1002
+    CannotNegateNonNumber(Value),
+    /// D1004
+    RegexMatchesEmptyString,
+    /// D1009
+    DuplicateObjectKey(String),
+    /// D2014
+    RangeTooLarge(f64),
+    /// D3001
+    StringifyNonFinite,
+    /// D3010
+    ReplaceEmptyPattern,
+    /// D3011
+    ReplaceNegativeLimit,
+    /// D3012
+    ReplacementNotString(Value),
+    /// D3020
+    SplitNegativeLimit,
+    /// D3030
+    CannotConvertToNumber(Value),
+    /// D3040
+    MatchNegativeLimit,
+    /// D3050
+    ReduceFunctionArity,
+    /// D3060
+    SqrtOfNegative(f64),
+    /// D3061: (base, exponent)
+    PowerOutOfRange(f64, f64),
+    /// D3070
+    SortNotComparable,
+    /// D3080
+    PictureTooManySubPictures,
+    /// D3081
+    PictureMultipleDecimalSeparators,
+    /// D3082
+    PictureMultiplePercents,
+    /// D3083
+    PictureMultiplePerMilles,
+    /// D3084
+    PicturePercentAndPerMille,
+    /// D3085
+    PictureMantissaWithoutDigits,
+    /// D3086
+    PicturePassiveBetweenActive,
+    /// D3087
+    PictureGroupingAdjacentToDecimal,
+    /// D3088
+    PictureGroupingAtEndOfInteger,
+    /// D3089
+    PictureAdjacentGroupings,
+    /// D3090
+    PictureOptionalAfterMandatoryInteger,
+    /// D3091
+    PictureMandatoryAfterOptionalFraction,
+    /// D3092
+    PicturePercentWithExponent,
+    /// D3093
+    PictureExponentNotDigits,
+    /// D3100
+    InvalidRadix(Value),
+    /// D3110
+    InvalidTimestamp(String),
+    /// D3120
+    EvalSyntaxError(String),
+    /// D3121
+    EvalDynamicError(String),
+    /// D3130
+    UnsupportedIntegerSequence(String),
+    /// D3131
+    MixedDecimalDigitGroups,
+    /// D3132
+    UnknownDateComponent(String),
+    /// D3133
+    NameModifierNotApplicable(String),
+    /// D3134
+    TimezoneTooManyDigits,
+    /// D3135
+    DatePictureUnterminated,
+    /// D3136
+    DatePictureMissingComponents,
+    /// D3137: raised by `$error`, with the range of the call once it is known
+    UserError {
+        message: String,
+        range: Option<TextRange>,
+    },
+    /// D3138
+    SingleMatchedMany,
+    /// D3139
+    SingleMatchedNone,
+    /// D3140: (function name, value)
+    MalformedUrl(String, String),
+    /// D3141: raised by `$assert`, with the range of the call once it is known
+    AssertionFailed {
+        message: String,
+        range: Option<TextRange>,
+    },
+
+    // Extensions, without a code in jsonata-js
+    /// T0000: holds the ident of a function found where a JSON value was required
+    FunctionCannotConvertToValue(String),
+    /// S0000: holds a regex which is invalid or has no equivalent in the regex engine
+    InvalidRegex(String),
+    /// D0000: (function name, value) of a string which is not valid for base 64
+    InvalidBase64(String, String),
+    /// D0000: holds a `$pad` width wider than the longest string that can be built
+    PadWidthTooLarge(f64),
+    /// D0000: holds the name of a function which depends on an evaluation in
+    /// progress but was called by the host after it finished
+    NoEvaluationInProgress(String),
+}
+
+impl EvaluationError {
+    /// Records the range of the sub-expression that raised the error, unless
+    /// a more specific range was already recorded.
+    pub(crate) fn with_range(self, range: TextRange) -> Self {
+        match self {
+            EvaluationError::WithRange(..) => self,
+            // errors raised by the expression itself also carry the range
+            // in their payload, for hosts which match on them
+            EvaluationError::UserError {
+                message,
+                range: None,
+            } => EvaluationError::UserError {
+                message,
+                range: Some(range),
+            }
+            .with_range(range),
+            EvaluationError::AssertionFailed {
+                message,
+                range: None,
+            } => EvaluationError::AssertionFailed {
+                message,
+                range: Some(range),
+            }
+            .with_range(range),
+            error => EvaluationError::WithRange(Box::new(error), range),
+        }
+    }
+
+    /// Range in the source of the sub-expression that raised the error
+    pub fn range(&self) -> Option<TextRange> {
+        match self {
+            EvaluationError::WithRange(_, range) => Some(*range),
+            _ => None,
+        }
+    }
+
+    /// The underlying error, without its range
+    pub fn kind(&self) -> &EvaluationError {
+        match self {
+            EvaluationError::WithRange(error, _) => error.kind(),
+            error => error,
+        }
+    }
+
+    /// The JSONata error code, e.g. `"T2001"`
+    pub fn code(&self) -> &'static str {
+        match self {
+            EvaluationError::WithRange(error, _) => error.code(),
+
+            EvaluationError::StringLiteralUnterminated => "S0101",
+            EvaluationError::NumberLiteralOutOfRange(_) => "S0102",
+            EvaluationError::UnsupportedEscapeSequence(_) => "S0103",
+            EvaluationError::InvalidUnicodeEscape => "S0104",
+            EvaluationError::QuotedPropertyUnterminated => "S0105",
+            EvaluationError::CommentUnterminated => "S0106",
+            EvaluationError::SyntaxError(_) => "S0201",
+            EvaluationError::ExpectedToken(..) => "S0202",
+            EvaluationError::ExpectedTokenBeforeEnd(_) => "S0203",
+            EvaluationError::UnknownOperator(_) => "S0204",
+            EvaluationError::UnexpectedToken(_) => "S0205",
+            EvaluationError::UnknownExpressionType(_) => "S0206",
+            EvaluationError::UnexpectedEnd => "S0207",
+            EvaluationError::FunctionParameterNotVariable(_) => "S0208",
+            EvaluationError::PredicateAfterGroupBy => "S0209",
+            EvaluationError::MultipleGroupBy => "S0210",
+            EvaluationError::InvalidUnaryOperator(_) => "S0211",
+            EvaluationError::AssignToNonVariable => "S0212",
+            EvaluationError::LiteralPathStep(_) => "S0213",
+            EvaluationError::BindToNonVariable(_) => "S0214",
+            EvaluationError::ContextBindingAfterPredicate => "S0215",
+            EvaluationError::ContextBindingAfterOrderBy => "S0216",
+            EvaluationError::ParentNotDerivable(_) => "S0217",
+            EvaluationError::EmptyRegex => "S0301",
+            EvaluationError::RegexUnterminated => "S0302",
+            EvaluationError::TypeParameterNotAllowed => "S0401",
+            EvaluationError::ParameterizedChoiceGroup => "S0402",
+            EvaluationError::ExpressionHasSyntaxErrors => "S0500",
+
+            EvaluationError::FunctionSignatureMismatch(..) => "T0410",
+            EvaluationError::FunctionContextMismatch(..) => "T0411",
+            EvaluationError::FunctionArrayMemberMismatch(..) => "T0412",
+            EvaluationError::ObjectKeyNotString(_) => "T1003",
+            EvaluationError::InvokeNonFunctionNamed(_) => "T1005",
+            EvaluationError::InvokeNonFunction => "T1006",
+            EvaluationError::PartialApplyNonFunctionNamed(_) => "T1007",
+            EvaluationError::PartialApplyNonFunction => "T1008",
+            EvaluationError::InvalidMatcherResult(_) => "T1010",
+            EvaluationError::DyadicMustBeNumber(_, Side::Left) => "T2001",
+            EvaluationError::DyadicMustBeNumber(_, Side::Right) => "T2002",
+            EvaluationError::RangeMustBeInteger(Side::Left) => "T2003",
+            EvaluationError::RangeMustBeInteger(Side::Right) => "T2004",
+            EvaluationError::ApplyToNonFunction => "T2006",
+            EvaluationError::OrderByTypeMismatch(..) => "T2007",
+            EvaluationError::OrderByNotComparable => "T2008",
+            EvaluationError::ComparisonTypeMismatch(..) => "T2009",
+            EvaluationError::ComparisonNotComparable(_) => "T2010",
+            EvaluationError::TransformUpdateNotObject(_) => "T2011",
+            EvaluationError::TransformDeleteNotStrings(_) => "T2012",
+            EvaluationError::TransformCloneOverridden => "T2013",
+
+            EvaluationError::NumberOutOfRange(_) => "D1001",
+            EvaluationError::CannotNegateNonNumber(_) => "D1002",
+            EvaluationError::RegexMatchesEmptyString => "D1004",
+            EvaluationError::DuplicateObjectKey(_) => "D1009",
+            EvaluationError::RangeTooLarge(_) => "D2014",
+            EvaluationError::StringifyNonFinite => "D3001",
+            EvaluationError::ReplaceEmptyPattern => "D3010",
+            EvaluationError::ReplaceNegativeLimit => "D3011",
+            EvaluationError::ReplacementNotString(_) => "D3012",
+            EvaluationError::SplitNegativeLimit => "D3020",
+            EvaluationError::CannotConvertToNumber(_) => "D3030",
+            EvaluationError::MatchNegativeLimit => "D3040",
+            EvaluationError::ReduceFunctionArity => "D3050",
+            EvaluationError::SqrtOfNegative(_) => "D3060",
+            EvaluationError::PowerOutOfRange(..) => "D3061",
+            EvaluationError::SortNotComparable => "D3070",
+            EvaluationError::PictureTooManySubPictures => "D3080",
+            EvaluationError::PictureMultipleDecimalSeparators => "D3081",
+            EvaluationError::PictureMultiplePercents => "D3082",
+            EvaluationError::PictureMultiplePerMilles => "D3083",
+            EvaluationError::PicturePercentAndPerMille => "D3084",
+            EvaluationError::PictureMantissaWithoutDigits => "D3085",
+            EvaluationError::PicturePassiveBetweenActive => "D3086",
+            EvaluationError::PictureGroupingAdjacentToDecimal => "D3087",
+            EvaluationError::PictureGroupingAtEndOfInteger => "D3088",
+            EvaluationError::PictureAdjacentGroupings => "D3089",
+            EvaluationError::PictureOptionalAfterMandatoryInteger => "D3090",
+            EvaluationError::PictureMandatoryAfterOptionalFraction => "D3091",
+            EvaluationError::PicturePercentWithExponent => "D3092",
+            EvaluationError::PictureExponentNotDigits => "D3093",
+            EvaluationError::InvalidRadix(_) => "D3100",
+            EvaluationError::InvalidTimestamp(_) => "D3110",
+            EvaluationError::EvalSyntaxError(_) => "D3120",
+            EvaluationError::EvalDynamicError(_) => "D3121",
+            EvaluationError::UnsupportedIntegerSequence(_) => "D3130",
+            EvaluationError::MixedDecimalDigitGroups => "D3131",
+            EvaluationError::UnknownDateComponent(_) => "D3132",
+            EvaluationError::NameModifierNotApplicable(_) => "D3133",
+            EvaluationError::TimezoneTooManyDigits => "D3134",
+            EvaluationError::DatePictureUnterminated => "D3135",
+            EvaluationError::DatePictureMissingComponents => "D3136",
+            EvaluationError::UserError { .. } => "D3137",
+            EvaluationError::SingleMatchedMany => "D3138",
+            EvaluationError::SingleMatchedNone => "D3139",
+            EvaluationError::MalformedUrl(..) => "D3140",
+            EvaluationError::AssertionFailed { .. } => "D3141",
+
+            EvaluationError::FunctionCannotConvertToValue(_) => "T0000",
+            EvaluationError::InvalidRegex(_) => "S0000",
+            EvaluationError::InvalidBase64(..) => "D0000",
+            EvaluationError::PadWidthTooLarge(_) => "D0000",
+            EvaluationError::NoEvaluationInProgress(_) => "D0000",
+        }
+    }
+}
+
+/// Formats a value the way jsonata-js substitutes `{{value}}` into a message
+/// template, i.e. with `JSON.stringify`
+fn stringify<T: Into<Value>>(value: T) -> String {
+    value.into().to_string()
+}
+
+/// `JSON.stringify` of a number, which has no representation for NaN or
+/// the infinities
+fn stringify_number(n: f64) -> String {
+    if n.is_finite() {
+        stringify(n)
+    } else {
+        "null".into()
+    }
+}
+
+impl fmt::Display for EvaluationError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            EvaluationError::WithRange(error, _) => write!(f, "{}", error),
+
+            EvaluationError::StringLiteralUnterminated => {
+                write!(f, "String literal must be terminated by a matching quote")
+            }
+            EvaluationError::NumberLiteralOutOfRange(token) => {
+                write!(f, "Number out of range: {}", stringify(token.as_str()))
+            }
+            EvaluationError::UnsupportedEscapeSequence(token) => {
+                write!(f, "Unsupported escape sequence: \\{}", stringify(token.as_str()))
+            }
+            EvaluationError::InvalidUnicodeEscape => {
+                write!(f, "The escape sequence \\u must be followed by 4 hex digits")
+            }
+            EvaluationError::QuotedPropertyUnterminated => write!(
+                f,
+                "Quoted property name must be terminated with a backquote (`)"
+            ),
+            EvaluationError::CommentUnterminated => write!(f, "Comment has no closing tag"),
+            EvaluationError::SyntaxError(token) => {
+                write!(f, "Syntax error: {}", stringify(token.as_str()))
+            }
+            EvaluationError::ExpectedToken(expected, found) => write!(
+                f,
+                "Expected {}, got {}",
+                stringify(expected.as_str()),
+                stringify(found.as_str())
+            ),
+            EvaluationError::ExpectedTokenBeforeEnd(expected) => write!(
+                f,
+                "Expected {} before end of expression",
+                stringify(expected.as_str())
+            ),
+            EvaluationError::UnknownOperator(token) => {
+                write!(f, "Unknown operator: {}", stringify(token.as_str()))
+            }
+            EvaluationError::UnexpectedToken(token) => {
+                write!(f, "Unexpected token: {}", stringify(token.as_str()))
+            }
+            EvaluationError::UnknownExpressionType(token) => {
+                write!(f, "Unknown expression type: {}", stringify(token.as_str()))
+            }
+            EvaluationError::UnexpectedEnd => write!(f, "Unexpected end of expression"),
+            EvaluationError::FunctionParameterNotVariable(value) => write!(
+                f,
+                "Parameter {} of function definition must be a variable name (start with $)",
+                stringify(value.as_str())
+            ),
+            EvaluationError::PredicateAfterGroupBy => write!(
+                f,
+                "A predicate cannot follow a grouping expression in a step"
+            ),
+            EvaluationError::MultipleGroupBy => {
+                write!(f, "Each step can only have one grouping expression")
+            }
+            EvaluationError::InvalidUnaryOperator(token) => write!(
+                f,
+                "The symbol {} cannot be used as a unary operator",
+                stringify(token.as_str())
+            ),
+            EvaluationError::AssignToNonVariable => write!(
+                f,
+                "The left side of := must be a variable name (start with $)"
+            ),
+            EvaluationError::LiteralPathStep(value) => write!(
+                f,
+                "The literal value {} cannot be used as a step within a path expression",
+                stringify(value.clone())
+            ),
+            EvaluationError::BindToNonVariable(token) => write!(
+                f,
+                "The right side of {} must be a variable name (start with $)",
+                stringify(token.as_str())
+            ),
+            EvaluationError::ContextBindingAfterPredicate => write!(
+                f,
+                "A context variable binding must precede any predicates on a step"
+            ),
+            EvaluationError::ContextBindingAfterOrderBy => write!(
+                f,
+                "A context variable binding must precede the 'order-by' clause on a step"
+            ),
+            EvaluationError::ParentNotDerivable(token) => write!(
+                f,
+                "The object representing the 'parent' cannot be derived from this expression: {}",
+                stringify(token.as_str())
+            ),
+            EvaluationError::EmptyRegex => {
+                write!(f, "Empty regular expressions are not allowed")
+            }
+            EvaluationError::RegexUnterminated => {
+                write!(f, "No terminating / in regular expression")
+            }
+            EvaluationError::TypeParameterNotAllowed => write!(
+                f,
+                "Type parameters can only be applied to functions and arrays"
+            ),
+            EvaluationError::ParameterizedChoiceGroup => write!(
+                f,
+                "Choice groups containing parameterized types are not supported"
+            ),
+            EvaluationError::ExpressionHasSyntaxErrors => write!(
+                f,
+                "Attempted to evaluate an expression containing syntax error(s)"
+            ),
+
+            EvaluationError::FunctionSignatureMismatch(index, name) => write!(
+                f,
+                "Argument {} of function {} does not match function signature",
+                index,
+                stringify(name.as_str())
+            ),
+            EvaluationError::FunctionContextMismatch(index, name) => write!(
+                f,
+                "Context value is not a compatible type with argument {} of function {}",
+                index,
+                stringify(name.as_str())
+            ),
+            EvaluationError::FunctionArrayMemberMismatch(index, name, member_type) => write!(
+                f,
+                "Argument {} of function {} must be an array of {}",
+                index,
+                stringify(name.as_str()),
+                stringify(member_type.as_str())
+            ),
+            EvaluationError::ObjectKeyNotString(value) => write!(
+                f,
+                "Key in object structure must evaluate to a string; got: {}",
+                stringify(value.clone())
+            ),
+            EvaluationError::InvokeNonFunctionNamed(token) => write!(
+                f,
+                "Attempted to invoke a non-function. Did you mean ${}?",
+                token
+            ),
+            EvaluationError::InvokeNonFunction => {
+                write!(f, "Attempted to invoke a non-function")
+            }
+            EvaluationError::PartialApplyNonFunctionNamed(token) => write!(
+                f,
+                "Attempted to partially apply a non-function. Did you mean ${}?",
+                token
+            ),
+            EvaluationError::PartialApplyNonFunction => {
+                write!(f, "Attempted to partially apply a non-function")
+            }
+            EvaluationError::InvalidMatcherResult(token) => write!(
+                f,
+                "The matcher function argument passed to function {} does not return the correct object structure",
+                stringify(token.as_str())
+            ),
+            EvaluationError::DyadicMustBeNumber(op, side) => write!(
+                f,
+                "The {} side of the {} operator must evaluate to a number",
+                match side {
+                    Side::Left => "left",
+                    Side::Right => "right",
+                },
+                stringify(op.to_string())
+            ),
+            EvaluationError::RangeMustBeInteger(side) => write!(
+                f,
+                "The {} side of the range operator (..) must evaluate to an integer",
+                match side {
+                    Side::Left => "left",
+                    Side::Right => "right",
+                }
+            ),
+            EvaluationError::ApplyToNonFunction => write!(
+                f,
+                "The right side of the function application operator ~> must be a function"
+            ),
+            EvaluationError::OrderByTypeMismatch(value, value2) => write!(
+                f,
+                "Type mismatch when comparing values {} and {} in order-by clause",
+                stringify(value.as_ref().clone()),
+                stringify(value2.as_ref().clone())
+            ),
+            EvaluationError::OrderByNotComparable => write!(
+                f,
+                "The expressions within an order-by clause must evaluate to numeric or string values"
+            ),
+            EvaluationError::ComparisonTypeMismatch(value, value2, token) => write!(
+                f,
+                "The values {} and {} either side of operator {} must be of the same data type",
+                stringify(value.as_ref().clone()),
+                stringify(value2.as_ref().clone()),
+                stringify(token.as_str())
+            ),
+            EvaluationError::ComparisonNotComparable(token) => write!(
+                f,
+                "The expressions either side of operator {} must evaluate to numeric or string values",
+                stringify(token.as_str())
+            ),
+            EvaluationError::TransformUpdateNotObject(value) => write!(
+                f,
+                "The insert/update clause of the transform expression must evaluate to an object: {}",
+                stringify(value.clone())
+            ),
+            EvaluationError::TransformDeleteNotStrings(value) => write!(
+                f,
+                "The delete clause of the transform expression must evaluate to a string or array of strings: {}",
+                stringify(value.clone())
+            ),
+            EvaluationError::TransformCloneOverridden => write!(
+                f,
+                "The transform expression clones the input object using the $clone() function.  This has been overridden in the current scope by a non-function."
+            ),
+
+            EvaluationError::NumberOutOfRange(value) => {
+                write!(f, "Number out of range: {}", stringify_number(*value))
+            }
+            EvaluationError::CannotNegateNonNumber(value) => write!(
+                f,
+                "Cannot negate a non-numeric value: {}",
+                stringify(value.clone())
+            ),
+            EvaluationError::RegexMatchesEmptyString => {
+                write!(f, "Regular expression matches zero length string")
+            }
+            EvaluationError::DuplicateObjectKey(value) => write!(
+                f,
+                "Multiple key definitions evaluate to same key: {}",
+                stringify(value.as_str())
+            ),
+            EvaluationError::RangeTooLarge(value) => write!(
+                f,
+                "The size of the sequence allocated by the range operator (..) must not exceed 1e6.  Attempted to allocate {}.",
+                stringify_number(*value)
+            ),
+            EvaluationError::StringifyNonFinite => write!(
+                f,
+                "Attempting to invoke string function on Infinity or NaN"
+            ),
+            EvaluationError::ReplaceEmptyPattern => write!(
+                f,
+                "Second argument of replace function cannot be an empty string"
+            ),
+            EvaluationError::ReplaceNegativeLimit => write!(
+                f,
+                "Fourth argument of replace function must evaluate to a positive number"
+            ),
+            EvaluationError::ReplacementNotString(_) => write!(
+                f,
+                "Attempted to replace a matched string with a non-string value"
+            ),
+            EvaluationError::SplitNegativeLimit => write!(
+                f,
+                "Third argument of split function must evaluate to a positive number"
+            ),
+            EvaluationError::CannotConvertToNumber(value) => write!(
+                f,
+                "Unable to cast value to a number: {}",
+                stringify(value.clone())
+            ),
+            EvaluationError::MatchNegativeLimit => write!(
+                f,
+                "Third argument of match function must evaluate to a positive number"
+            ),
+            EvaluationError::ReduceFunctionArity => write!(
+                f,
+                "The second argument of reduce function must be a function with at least two arguments"
+            ),
+            EvaluationError::SqrtOfNegative(value) => write!(
+                f,
+                "The sqrt function cannot be applied to a negative number: {}",
+                stringify_number(*value)
+            ),
+            EvaluationError::PowerOutOfRange(base, exponent) => write!(
+                f,
+                "The power function has resulted in a value that cannot be represented as a JSON number: base={}, exponent={}",
+                stringify_number(*base),
+                stringify_number(*exponent)
+            ),
+            EvaluationError::SortNotComparable => write!(
+                f,
+                "The single argument form of the sort function can only be applied to an array of strings or an array of numbers.  Use the second argument to specify a comparison function"
+            ),
+            EvaluationError::PictureTooManySubPictures => write!(
+                f,
+                "The picture string must only contain a maximum of two sub-pictures"
+            ),
+            EvaluationError::PictureMultipleDecimalSeparators => write!(
+                f,
+                "The sub-picture must not contain more than one instance of the 'decimal-separator' character"
+            ),
+            EvaluationError::PictureMultiplePercents => write!(
+                f,
+                "The sub-picture must not contain more than one instance of the 'percent' character"
+            ),
+            EvaluationError::PictureMultiplePerMilles => write!(
+                f,
+                "The sub-picture must not contain more than one instance of the 'per-mille' character"
+            ),
+            EvaluationError::PicturePercentAndPerMille => write!(
+                f,
+                "The sub-picture must not contain both a 'percent' and a 'per-mille' character"
+            ),
+            EvaluationError::PictureMantissaWithoutDigits => write!(
+                f,
+                "The mantissa part of a sub-picture must contain at least one character that is either an 'optional digit character' or a member of the 'decimal digit family'"
+            ),
+            EvaluationError::PicturePassiveBetweenActive => write!(
+                f,
+                "The sub-picture must not contain a passive character that is preceded by an active character and that is followed by another active character"
+            ),
+            EvaluationError::PictureGroupingAdjacentToDecimal => write!(
+                f,
+                "The sub-picture must not contain a 'grouping-separator' character that appears adjacent to a 'decimal-separator' character"
+            ),
+            EvaluationError::PictureGroupingAtEndOfInteger => write!(
+                f,
+                "The sub-picture must not contain a 'grouping-separator' at the end of the integer part"
+            ),
+            EvaluationError::PictureAdjacentGroupings => write!(
+                f,
+                "The sub-picture must not contain two adjacent instances of the 'grouping-separator' character"
+            ),
+            EvaluationError::PictureOptionalAfterMandatoryInteger => write!(
+                f,
+                "The integer part of the sub-picture must not contain a member of the 'decimal digit family' that is followed by an instance of the 'optional digit character'"
+            ),
+            EvaluationError::PictureMandatoryAfterOptionalFraction => write!(
+                f,
+                "The fractional part of the sub-picture must not contain an instance of the 'optional digit character' that is followed by a member of the 'decimal digit family'"
+            ),
+            EvaluationError::PicturePercentWithExponent => write!(
+                f,
+                "A sub-picture that contains a 'percent' or 'per-mille' character must not contain a character treated as an 'exponent-separator'"
+            ),
+            EvaluationError::PictureExponentNotDigits => write!(
+                f,
+                "The exponent part of the sub-picture must comprise only of one or more characters that are members of the 'decimal digit family'"
+            ),
+            EvaluationError::InvalidRadix(value) => write!(
+                f,
+                "The radix of the formatBase function must be between 2 and 36.  It was given {}",
+                stringify(value.clone())
+            ),
+            EvaluationError::InvalidTimestamp(value) => write!(
+                f,
+                "The argument of the toMillis function must be an ISO 8601 formatted timestamp. Given {}",
+                stringify(value.as_str())
+            ),
+            EvaluationError::EvalSyntaxError(value) => write!(
+                f,
+                "Syntax error in expression passed to function eval: {}",
+                stringify(value.as_str())
+            ),
+            EvaluationError::EvalDynamicError(value) => write!(
+                f,
+                "Dynamic error evaluating the expression passed to function eval: {}",
+                stringify(value.as_str())
+            ),
+            EvaluationError::UnsupportedIntegerSequence(value) => write!(
+                f,
+                "Formatting or parsing an integer as a sequence starting with {} is not supported by this implementation",
+                stringify(value.as_str())
+            ),
+            EvaluationError::MixedDecimalDigitGroups => write!(
+                f,
+                "In a decimal digit pattern, all digits must be from the same decimal group"
+            ),
+            EvaluationError::UnknownDateComponent(value) => write!(
+                f,
+                "Unknown component specifier {} in date/time picture string",
+                stringify(value.as_str())
+            ),
+            EvaluationError::NameModifierNotApplicable(value) => write!(
+                f,
+                "The 'name' modifier can only be applied to months and days in the date/time picture string, not {}",
+                stringify(value.as_str())
+            ),
+            EvaluationError::TimezoneTooManyDigits => write!(
+                f,
+                "The timezone integer format specifier cannot have more than four digits"
+            ),
+            EvaluationError::DatePictureUnterminated => write!(
+                f,
+                "No matching closing bracket ']' in date/time picture string"
+            ),
+            EvaluationError::DatePictureMissingComponents => write!(
+                f,
+                "The date/time picture string is missing specifiers required to parse the timestamp"
+            ),
+            EvaluationError::UserError { message, .. } => write!(f, "{}", message),
+            EvaluationError::SingleMatchedMany => write!(
+                f,
+                "The $single() function expected exactly 1 matching result.  Instead it matched more."
+            ),
+            EvaluationError::SingleMatchedNone => write!(
+                f,
+                "The $single() function expected exactly 1 matching result.  Instead it matched 0."
+            ),
+            EvaluationError::MalformedUrl(name, value) => write!(
+                f,
+                "Malformed URL passed to ${}(): {}",
+                name,
+                stringify(value.as_str())
+            ),
+            EvaluationError::AssertionFailed { message, .. } => write!(f, "{}", message),
+
+            EvaluationError::FunctionCannotConvertToValue(ident) => {
+                write!(f, "Function ${} cannot be converted to a JSON value", ident)
+            }
+            EvaluationError::InvalidRegex(regex) => {
+                write!(f, "Invalid regular expression: {}", regex)
+            }
+            EvaluationError::InvalidBase64(name, value) => write!(
+                f,
+                "Invalid string passed to ${}(): {}",
+                name,
+                stringify(value.as_str())
+            ),
+            EvaluationError::PadWidthTooLarge(width) => write!(
+                f,
+                "Invalid string length: $pad() cannot pad a string to a width of {}",
+                stringify_number(*width)
+            ),
+            EvaluationError::NoEvaluationInProgress(name) => write!(
+                f,
+                "Function ${}() can only be called while an expression is evaluated",
+                name
+            ),
+        }
+    }
+}
+
+impl std::error::Error for EvaluationError {}
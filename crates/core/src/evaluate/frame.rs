@@ -0,0 +1,39 @@
+use std::{cell::RefCell, rc::Rc};
+
+use crate::value::JSONataValue;
+
+use super::JSONataVariables;
+
+/// Scope of the variables defined while evaluating an expression
+///
+/// Variables which are not defined in a frame are looked up in its parent,
+/// so an expression evaluated by `$eval` sees the variables of its caller
+/// without its own definitions leaking back out.
+#[derive(Default)]
+pub(crate) struct Frame {
+    variables: RefCell<JSONataVariables>,
+    parent: Option<Rc<Frame>>,
+}
+
+impl Frame {
+    /// A new, empty frame within `parent`
+    pub(crate) fn with_parent(parent: Rc<Frame>) -> Self {
+        Self {
+            variables: RefCell::default(),
+            parent: Some(parent),
+        }
+    }
+
+    /// Looks up `name` in this frame and then in each of its ancestors, the
+    /// outer `None` meaning that it is not defined in any of them
+    pub(crate) fn lookup(&self, name: &str) -> Option<Option<JSONataValue>> {
+        match self.variables.borrow().get(name) {
+            Some(value) => Some(value.clone()),
+            None => self.parent.as_ref()?.lookup(name),
+        }
+    }
+
+    pub(crate) fn bind(&self, name: &str, value: Option<JSONataValue>) {
+        self.variables.borrow_mut().insert(name.into(), value);
+    }
+}
@@ -0,0 +1,384 @@
+mod context;
+mod error;
+mod frame;
+#[cfg(test)]
+mod tests;
+
+use std::collections::HashMap;
+use std::convert::TryFrom;
+use std::ops::Neg;
+use std::rc::Rc;
+
+use hir::{BinaryOp, Database, Expr, ExprIdx, UnaryOp};
+use serde_json::Value;
+
+use crate::value::{number::JSONataNumber, sequence::Sequence, JSONataValue, TryNumericOps};
+
+pub use context::{Clock, RandomSource, SystemClock};
+pub(crate) use context::{EvaluationContext, Evaluations, Randomness};
+pub use error::{ArithmeticOpType, EvaluationError, Side};
+pub(crate) use frame::Frame;
+
+/// Result of evaluating an expression, `None` represents an
+/// undefined result (e.g. a path that matched nothing).
+pub type EvaluationResult = Result<Option<JSONataValue>, EvaluationError>;
+
+/// Variables in scope during an evaluation, keyed by their
+/// name without the preceding `$` symbol
+pub(crate) type JSONataVariables = HashMap<String, Option<JSONataValue>>;
+
+/// Walks the lowered HIR of an expression against an input value
+///
+/// Variables defined by the expression itself are kept in its own `frame` so
+/// that the environment can be shared across evaluations without being mutated.
+pub(crate) struct Evaluator<'a> {
+    db: &'a Database,
+    globals: &'a JSONataVariables,
+    context: &'a EvaluationContext,
+    frame: Rc<Frame>,
+}
+
+impl<'a> Evaluator<'a> {
+    /// An evaluator whose variables are defined in a new frame within `parent`,
+    /// if given, e.g. the frame of the caller of `$eval`
+    pub(crate) fn new(
+        db: &'a Database,
+        context: &'a EvaluationContext,
+        parent: Option<Rc<Frame>>,
+    ) -> Self {
+        let frame = match parent {
+            Some(parent) => Frame::with_parent(parent),
+            None => Frame::default(),
+        };
+
+        Self {
+            db,
+            globals: context.globals(),
+            context,
+            frame: Rc::new(frame),
+        }
+    }
+
+    pub(crate) fn eval(&mut self, expr: &Expr, input: &Value) -> EvaluationResult {
+        match expr {
+            Expr::Missing => Ok(None),
+            Expr::Binary { op, lhs, rhs } => self.eval_binary(op, *lhs, *rhs, input),
+            Expr::Call { func, args } => self.eval_call(func, args, input),
+            Expr::Literal { n } => Ok(Some((*n).into())),
+            Expr::Object { pairs } => self.eval_object(pairs, input),
+            Expr::Paren { exprs } => self.eval_block(exprs, input),
+            Expr::PathIdent { name, keep_array } => {
+                let mut results = Vec::new();
+                for item in Evaluator::path_inputs(input, false) {
+                    results.extend(Evaluator::lookup(&item, name)?);
+                }
+
+                let mut sequence = Sequence::from_step(results, true);
+                if *keep_array {
+                    sequence.keep_array();
+                }
+                sequence.collapse()
+            }
+            Expr::Unary { op, expr } => self.eval_unary(op, *expr, input),
+            Expr::VariableRef { var } => Ok(self.variable(var)),
+            Expr::VariableDef { name, value } => {
+                let value = self.eval_idx(*value, input)?;
+                self.frame
+                    .bind(Evaluator::variable_name(name), value.clone());
+                Ok(value)
+            }
+        }
+    }
+
+    fn eval_idx(&mut self, idx: ExprIdx, input: &Value) -> EvaluationResult {
+        let db = self.db;
+        self.eval(db.expr(idx), input)
+            .map_err(|error| self.with_range(error, idx))
+    }
+
+    /// Attaches the source range of the expression at `idx` to `error`
+    fn with_range(&self, error: EvaluationError, idx: ExprIdx) -> EvaluationError {
+        match self.db.range(idx) {
+            Some(range) => error.with_range(range),
+            None => error,
+        }
+    }
+
+    /// Evaluates each expression of a block in turn, returning the result of
+    /// the last one. Variables defined in the block are scoped to it.
+    fn eval_block(&mut self, exprs: &[ExprIdx], input: &Value) -> EvaluationResult {
+        let frame = Rc::new(Frame::with_parent(self.frame.clone()));
+        let outer = std::mem::replace(&mut self.frame, frame);
+
+        let mut result = Ok(None);
+        for expr in exprs {
+            result = self.eval_idx(*expr, input);
+            if result.is_err() {
+                break;
+            }
+        }
+
+        self.frame = outer;
+        result
+    }
+
+    fn eval_binary(
+        &mut self,
+        op: &BinaryOp,
+        lhs: ExprIdx,
+        rhs: ExprIdx,
+        input: &Value,
+    ) -> EvaluationResult {
+        let op_type = match op {
+            BinaryOp::Add => ArithmeticOpType::Add,
+            BinaryOp::Sub => ArithmeticOpType::Sub,
+            BinaryOp::Mul => ArithmeticOpType::Mul,
+            BinaryOp::Div => ArithmeticOpType::Div,
+            BinaryOp::Map => {
+                let mut steps = self.path_steps(lhs);
+                steps.push(rhs);
+                return self.eval_path(&steps, input);
+            }
+        };
+
+        let (lhs_idx, rhs_idx) = (lhs, rhs);
+        let lhs = self.eval_idx(lhs_idx, input)?;
+        let rhs = self.eval_idx(rhs_idx, input)?;
+
+        // Either side being undefined makes the result undefined, but a
+        // defined operand must still be a number.
+        let (lhs, rhs) = match (lhs, rhs) {
+            (Some(lhs), Some(rhs)) => (lhs, rhs),
+            (Some(JSONataValue::Value(Value::Number(_))), None)
+            | (None, Some(JSONataValue::Value(Value::Number(_))))
+            | (None, None) => return Ok(None),
+            (Some(_), None) => {
+                let error = EvaluationError::DyadicMustBeNumber(op_type, Side::Left);
+                return Err(self.with_range(error, lhs_idx));
+            }
+            (None, Some(_)) => {
+                let error = EvaluationError::DyadicMustBeNumber(op_type, Side::Right);
+                return Err(self.with_range(error, rhs_idx));
+            }
+        };
+
+        let result = match op_type {
+            ArithmeticOpType::Add => lhs.try_add(rhs),
+            ArithmeticOpType::Sub => lhs.try_sub(rhs),
+            ArithmeticOpType::Mul => lhs.try_mul(rhs),
+            ArithmeticOpType::Div => lhs.try_div(rhs),
+            ArithmeticOpType::Rem => lhs.try_rem(rhs),
+        };
+
+        // Point at the operand which is not a number
+        match result {
+            Ok(result) => Ok(Some(result.into())),
+            Err(error @ EvaluationError::DyadicMustBeNumber(_, Side::Left)) => {
+                Err(self.with_range(error, lhs_idx))
+            }
+            Err(error @ EvaluationError::DyadicMustBeNumber(_, Side::Right)) => {
+                Err(self.with_range(error, rhs_idx))
+            }
+            Err(error) => Err(error),
+        }
+    }
+
+    /// Flattens a chain of map operators into the steps of a path
+    fn path_steps(&self, idx: ExprIdx) -> Vec<ExprIdx> {
+        match self.db.expr(idx) {
+            Expr::Binary {
+                op: BinaryOp::Map,
+                lhs,
+                rhs,
+            } => {
+                let mut steps = self.path_steps(*lhs);
+                steps.push(*rhs);
+                steps
+            }
+            _ => vec![idx],
+        }
+    }
+
+    /// Evaluates each step of a path once for each item produced by the
+    /// previous step, using that item as the context.
+    fn eval_path(&mut self, steps: &[ExprIdx], input: &Value) -> EvaluationResult {
+        let starts_with_variable = matches!(self.db.expr(steps[0]), Expr::VariableRef { .. });
+        let mut inputs = Evaluator::path_inputs(input, starts_with_variable);
+        let mut sequence = Sequence::new();
+
+        for (i, step) in steps.iter().enumerate() {
+            let last_step = i == steps.len() - 1;
+
+            let mut results = Vec::new();
+            for item in &inputs {
+                results.extend(self.eval_step(*step, item)?);
+            }
+
+            sequence = Sequence::from_step(results, last_step);
+            if sequence.is_empty() {
+                break;
+            }
+            if !last_step {
+                inputs = sequence.to_values()?;
+            }
+        }
+
+        // `[]` on any step keeps the result of the whole path as an array
+        let keep_array = steps.iter().any(|step| {
+            matches!(
+                self.db.expr(*step),
+                Expr::PathIdent {
+                    keep_array: true,
+                    ..
+                }
+            )
+        });
+        if keep_array {
+            sequence.keep_array();
+        }
+
+        sequence.collapse()
+    }
+
+    /// A name within a path is looked up directly in the context item,
+    /// any other step is evaluated as an expression.
+    fn eval_step(&mut self, step: ExprIdx, item: &Value) -> EvaluationResult {
+        match self.db.expr(step) {
+            Expr::PathIdent { name, .. } => Evaluator::lookup(item, name),
+            _ => self.eval_idx(step, item),
+        }
+    }
+
+    /// The first step of a path is evaluated against each item of an array
+    /// context, unless it is a variable.
+    fn path_inputs(input: &Value, starts_with_variable: bool) -> Vec<Value> {
+        match input {
+            Value::Array(items) if !starts_with_variable => items.clone(),
+            input => vec![input.clone()],
+        }
+    }
+
+    /// Calls the function bound to the variable `func`, evaluating the
+    /// arguments against the context value
+    fn eval_call(&mut self, func: &str, args: &[ExprIdx], input: &Value) -> EvaluationResult {
+        let func = match self.variable(func) {
+            Some(JSONataValue::Function(func)) => func,
+            _ => return Err(EvaluationError::InvokeNonFunction),
+        };
+
+        let args = args
+            .iter()
+            .map(|arg| self.eval_idx(*arg, input))
+            .collect::<Result<Vec<_>, _>>()?;
+
+        // the frame of the call is the scope of any expression that it evaluates
+        self.context.enter_call(self.frame.clone());
+        let result = func.call(&args, Some(input));
+        self.context.exit_call();
+        result
+    }
+
+    /// Builds an object from each pair of key and value expressions
+    ///
+    /// The items of an array context are grouped by the key they evaluate to,
+    /// and the value is then evaluated once per group with the grouped items
+    /// as its context. Keys are inserted in the order they are first seen.
+    fn eval_object(&mut self, pairs: &[(ExprIdx, ExprIdx)], input: &Value) -> EvaluationResult {
+        let items: Vec<&Value> = match input {
+            Value::Array(items) => items.iter().collect(),
+            input => vec![input],
+        };
+
+        // (key, index of the pair, items grouped under the key), along with
+        // the position of each key in `groups`
+        let mut groups: Vec<(String, usize, Vec<&Value>)> = Vec::new();
+        let mut positions: HashMap<String, usize> = HashMap::new();
+        for item in items {
+            for (idx, (key, _)) in pairs.iter().enumerate() {
+                let name = match self.eval_idx(*key, item)? {
+                    Some(JSONataValue::Value(Value::String(name))) => name,
+                    Some(key_value) => {
+                        let error =
+                            EvaluationError::ObjectKeyNotString(Value::try_from(key_value)?);
+                        return Err(self.with_range(error, *key));
+                    }
+                    None => continue,
+                };
+
+                match positions.get(&name) {
+                    Some(&position) => {
+                        let (name, group_idx, group) = &mut groups[position];
+                        if *group_idx != idx {
+                            let error = EvaluationError::DuplicateObjectKey(name.clone());
+                            return Err(self.with_range(error, *key));
+                        }
+                        group.push(item);
+                    }
+                    None => {
+                        positions.insert(name.clone(), groups.len());
+                        groups.push((name, idx, vec![item]));
+                    }
+                }
+            }
+        }
+
+        let mut object = serde_json::Map::new();
+        for (name, idx, items) in groups {
+            let context = match items.as_slice() {
+                [item] => (*item).clone(),
+                items => Value::Array(items.iter().map(|item| (*item).clone()).collect()),
+            };
+
+            if let Some(value) = self.eval_idx(pairs[idx].1, &context)? {
+                object.insert(name, Value::try_from(value)?);
+            }
+        }
+
+        Ok(Some(Value::Object(object).into()))
+    }
+
+    fn eval_unary(&mut self, op: &UnaryOp, expr: ExprIdx, input: &Value) -> EvaluationResult {
+        match op {
+            UnaryOp::Neg => match self.eval_idx(expr, input)? {
+                Some(JSONataValue::Value(Value::Number(n))) => {
+                    Ok(Some(JSONataNumber::from(n).neg().into()))
+                }
+                Some(value) => Err(EvaluationError::CannotNegateNonNumber(Value::try_from(
+                    value,
+                )?)),
+                None => Ok(None),
+            },
+        }
+    }
+
+    /// Looks up the field `name` in the context value. Arrays are
+    /// searched item by item and the matches are flattened together.
+    fn lookup(input: &Value, name: &str) -> EvaluationResult {
+        match input {
+            Value::Object(map) => Ok(map.get(name).map(JSONataValue::from)),
+            Value::Array(items) => {
+                let mut sequence = Sequence::new();
+                for item in items {
+                    if let Some(value) = Evaluator::lookup(item, name)? {
+                        sequence.push_flattened(value);
+                    }
+                }
+                sequence.collapse()
+            }
+            _ => Ok(None),
+        }
+    }
+
+    fn variable(&self, var: &str) -> Option<JSONataValue> {
+        let name = Evaluator::variable_name(var);
+        self.frame
+            .lookup(name)
+            .or_else(|| self.globals.get(name).cloned())
+            .or_else(|| self.context.variable(name).cloned())
+            .flatten()
+    }
+
+    fn variable_name(var: &str) -> &str {
+        var.strip_prefix('$').unwrap_or(var)
+    }
+}
@@ -0,0 +1,632 @@
+use std::{
+    cell::{Cell, RefCell},
+    rc::Rc,
+};
+
+use serde_json::{json, Value};
+use text_size::TextRange;
+
+use crate::{
+    ArithmeticOpType, Environment, EvaluationError, Expression, JSONataValue, RandomSource, Side,
+};
+
+fn eval(input: &str, data: Value) -> Result<Option<Value>, EvaluationError> {
+    let expr = Expression::compile(input).unwrap();
+    expr.evaluate(&data, &Environment::new())
+}
+
+fn check(input: &str, data: Value, expected: Option<Value>) {
+    assert_eq!(eval(input, data), Ok(expected));
+}
+
+#[test]
+fn arithmetic() {
+    check("1 + 2 * 3", json!(null), Some(json!(7)));
+    check("(1 + 2) * 3", json!(null), Some(json!(9)));
+    check("-5 + 2", json!(null), Some(json!(-3)));
+    check("7 / 2", json!(null), Some(json!(3.5)));
+
+    // integral results are integers, as JavaScript does not tell them apart
+    check("10 / 2", json!(null), Some(json!(5)));
+    check(
+        "$average(Values)",
+        json!({ "Values": [3, 5] }),
+        Some(json!(4)),
+    );
+    check(
+        "4294967296 * 4294967296",
+        json!(null),
+        Some(json!(18446744073709551616.0)),
+    );
+}
+
+#[test]
+fn arithmetic_with_undefined_operand() {
+    check("missing + 1", json!({}), None);
+    check("1 * missing", json!({}), None);
+}
+
+fn check_error(input: &str, data: Value, code: &str, message: &str, range: (u32, u32)) {
+    let error = eval(input, data).unwrap_err();
+    assert_eq!(error.code(), code);
+    assert_eq!(error.to_string(), message);
+    assert_eq!(
+        error.range(),
+        Some(TextRange::new(range.0.into(), range.1.into()))
+    );
+}
+
+#[test]
+fn arithmetic_with_non_numeric_operand() {
+    let data = json!({ "name": "ACME" });
+    assert_eq!(
+        eval("name + 1", data.clone()).unwrap_err().kind(),
+        &EvaluationError::DyadicMustBeNumber(ArithmeticOpType::Add, Side::Left)
+    );
+
+    check_error(
+        "name + 1",
+        data.clone(),
+        "T2001",
+        r#"The left side of the "+" operator must evaluate to a number"#,
+        (0, 4),
+    );
+    check_error(
+        "2 * (1 + name)",
+        data.clone(),
+        "T2002",
+        r#"The right side of the "+" operator must evaluate to a number"#,
+        (9, 13),
+    );
+    check_error(
+        "-name",
+        data,
+        "D1002",
+        r#"Cannot negate a non-numeric value: "ACME""#,
+        (0, 5),
+    );
+}
+
+#[test]
+fn arithmetic_out_of_range() {
+    check_error(
+        "1 + 1 / 0",
+        json!(null),
+        "D1001",
+        "Number out of range: null",
+        (4, 9),
+    );
+}
+
+#[test]
+fn paths() {
+    let data = json!({
+        "Account": {
+            "Name": "Firefly",
+            "Order": [
+                { "Product": { "Price": 34.45 } },
+                { "Product": { "Price": 21.67 } }
+            ]
+        }
+    });
+
+    check("Account", data.clone(), Some(data["Account"].clone()));
+    check("Account.Name", data.clone(), Some(json!("Firefly")));
+    check(
+        "Account.Order.Product.Price",
+        data.clone(),
+        Some(json!([34.45, 21.67])),
+    );
+    check("Account.Missing", data, None);
+}
+
+#[test]
+fn path_sequences() {
+    // a single value is not wrapped in an array
+    check("a.b", json!({ "a": [{ "b": 1 }] }), Some(json!(1)));
+
+    // the results of each step are flattened by one level
+    check(
+        "a.b",
+        json!({ "a": [{ "b": [1, 2] }, { "b": [3] }] }),
+        Some(json!([1, 2, 3])),
+    );
+    check(
+        "a.b",
+        json!({ "a": [{ "b": [[1, 2]] }, { "b": [[3]] }] }),
+        Some(json!([[1, 2], [3]])),
+    );
+
+    // a single array matched by the last step is kept as-is
+    check("a.b", json!({ "a": { "b": [5] } }), Some(json!([5])));
+    check("a.b", json!({ "a": { "b": [] } }), Some(json!([])));
+    check(
+        "a.b",
+        json!({ "a": { "b": [[1, 2], [3]] } }),
+        Some(json!([[1, 2], [3]])),
+    );
+
+    // an array context is mapped over by the first step
+    check("a", json!([{ "a": 1 }, { "a": 2 }]), Some(json!([1, 2])));
+    check(
+        "a.b",
+        json!([{ "a": { "b": 1 } }, { "a": {} }]),
+        Some(json!(1)),
+    );
+}
+
+#[test]
+fn keep_array() {
+    let data = json!({ "Account": { "Order": [{ "Product": "Hat" }] } });
+
+    check("Account.Order.Product", data.clone(), Some(json!("Hat")));
+    check(
+        "Account.Order.Product[]",
+        data.clone(),
+        Some(json!(["Hat"])),
+    );
+    check(
+        "Account.Order[].Product",
+        data.clone(),
+        Some(json!(["Hat"])),
+    );
+    check("Account[]", json!({ "Account": 1 }), Some(json!([1])));
+    check("Account.Missing[]", data, None);
+}
+
+#[test]
+fn map_operator() {
+    let data = json!({
+        "Order": [
+            { "Product": "Hat", "Price": 20, "Qty": 2 },
+            { "Product": "Scarf", "Price": 15, "Qty": 1 }
+        ]
+    });
+
+    check("Order.(Price * Qty)", data.clone(), Some(json!([40, 15])));
+    check(
+        "Order.Price * 2",
+        json!({ "Order": { "Price": 3 } }),
+        Some(json!(6)),
+    );
+    check(
+        "2 * Order.Price",
+        json!({ "Order": { "Price": 3 } }),
+        Some(json!(6)),
+    );
+    check(
+        "Order.{Product: Price}",
+        data.clone(),
+        Some(json!([{ "Hat": 20 }, { "Scarf": 15 }])),
+    );
+    check("$count(Order)", data.clone(), Some(json!(2)));
+    check("Order.$count(Product)", data, Some(json!([1, 1])));
+}
+
+#[test]
+fn object_constructor_groups_array_context() {
+    let data = json!([
+        { "Product": "Hat", "Price": 20 },
+        { "Product": "Hat", "Price": 25 },
+        { "Product": "Scarf", "Price": 15 }
+    ]);
+
+    check(
+        "{Product: Price}",
+        data,
+        Some(json!({ "Hat": [20, 25], "Scarf": 15 })),
+    );
+    check_error(
+        "{Price: Product}",
+        json!({ "Product": "Hat", "Price": 20 }),
+        "T1003",
+        "Key in object structure must evaluate to a string; got: 20",
+        (1, 6),
+    );
+}
+
+#[test]
+fn object_constructor_keeps_first_seen_key_order() {
+    let data = json!([
+        { "Product": "Scarf", "Price": 1 },
+        { "Product": "Hat", "Price": 2 },
+        { "Product": "Scarf", "Price": 3 }
+    ]);
+
+    // objects compare equal regardless of key order, so compare the JSON
+    let object = eval("{Product: Price}", data).unwrap().unwrap();
+    assert_eq!(object.to_string(), r#"{"Scarf":[1,3],"Hat":2}"#);
+}
+
+#[test]
+fn invoke_non_function() {
+    let mut env = Environment::new();
+    env.bind("x", json!(1));
+    let expr = Expression::compile("$x()").unwrap();
+    let error = expr.evaluate(&json!(null), &env).unwrap_err();
+
+    assert_eq!(error.kind(), &EvaluationError::InvokeNonFunction);
+    assert_eq!(error.code(), "T1006");
+}
+
+#[test]
+fn registered_functions() {
+    let mut env = Environment::new();
+    env.register_function("double", "<n-:n>", |args| {
+        let n = args[0].as_ref().and_then(|n| n.as_value()?.as_f64());
+        Ok(n.map(|n| (n * 2.0).into()))
+    })
+    .unwrap();
+
+    let expr = Expression::compile("$double(Price)").unwrap();
+    let data = json!({ "Price": 2.5 });
+    assert_eq!(expr.evaluate(&data, &env), Ok(Some(json!(5))));
+
+    // the context value is used when the argument is missing
+    let expr = Expression::compile("Price.$double()").unwrap();
+    assert_eq!(expr.evaluate(&data, &env), Ok(Some(json!(5))));
+
+    let expr = Expression::compile("$double(Name)").unwrap();
+    let error = expr.evaluate(&json!({ "Name": "Hat" }), &env).unwrap_err();
+    assert_eq!(error.code(), "T0410");
+    assert_eq!(
+        error.to_string(),
+        r#"Argument 1 of function "double" does not match function signature"#
+    );
+
+    assert!(env
+        .register_function("broken", "<n<s>:n>", |_| Ok(None))
+        .is_err());
+}
+
+#[test]
+fn builtin_signatures() {
+    let data = json!({ "Price": 5, "Name": "Hat" });
+
+    // a single value is wrapped in an array
+    check("$count(Price)", data.clone(), Some(json!(1)));
+    check("$count(Missing)", data.clone(), Some(json!(0)));
+    check("$exists(Missing)", data.clone(), Some(json!(false)));
+
+    let expr = Expression::compile("$exists()").unwrap();
+    let error = expr.evaluate(&data, &Environment::new()).unwrap_err();
+    assert_eq!(error.code(), "T0410");
+    assert_eq!(
+        error.to_string(),
+        r#"Argument 1 of function "exists" does not match function signature"#
+    );
+}
+
+#[test]
+fn standard_builtins() {
+    let data = json!({ "Name": " Hat ", "Price": 12.5, "Tags": ["a", "b"] });
+
+    check("$trim(Name)", data.clone(), Some(json!("Hat")));
+    check("Price.$round()", data.clone(), Some(json!(12)));
+    check("$join(Tags)", data.clone(), Some(json!("ab")));
+    check("$boolean(Tags)", data.clone(), Some(json!(true)));
+    check("$type(Tags)", data, Some(json!("array")));
+}
+
+#[test]
+fn variables() {
+    check("$foo := 5", json!(null), Some(json!(5)));
+
+    let mut env = Environment::new();
+    env.bind("rate", json!(3));
+    let expr = Expression::compile("$rate * 2").unwrap();
+    assert_eq!(expr.evaluate(&json!(null), &env), Ok(Some(json!(6))));
+    check("$undefined", json!(null), None);
+}
+
+#[test]
+fn blocks() {
+    check("($x := 5; $x * 2)", json!(null), Some(json!(10)));
+
+    // variables defined in a block are scoped to it
+    check("($x := 1; ($x := 2; $x); $x)", json!(null), Some(json!(1)));
+    check("($x := 1; ($y := 2); $y)", json!(null), Some(json!(2)));
+    check("($x := 1; ($y := 2; $y); $y)", json!(null), None);
+}
+
+#[test]
+fn evaluate_many_inputs() {
+    let expr = Expression::compile("price * 2").unwrap();
+    let env = Environment::new();
+
+    for (price, expected) in [(1, 2), (10, 20), (21, 42)] {
+        assert_eq!(
+            expr.evaluate(&json!({ "price": price }), &env),
+            Ok(Some(json!(expected)))
+        );
+    }
+}
+
+#[test]
+fn compile_errors() {
+    let diagnostics = Expression::compile("(2").unwrap_err();
+    assert_eq!(diagnostics.errors().len(), 1);
+    assert_eq!(
+        diagnostics.to_string(),
+        "error at 1..2: expected ‘+’, ‘-’, ‘*’, ‘/’, ‘.’, ‘;’, or ‘)’"
+    );
+}
+
+#[test]
+fn supported_syntax() {
+    let data = json!({
+        "Order": [
+            { "Product": "Hat" },
+            { "Product": "Scarf" }
+        ]
+    });
+
+    check(
+        "Order.Product.$uppercase()",
+        data.clone(),
+        Some(json!(["HAT", "SCARF"])),
+    );
+    check(
+        "Order.Product.$pad(6)",
+        data,
+        Some(json!(["Hat   ", "Scarf "])),
+    );
+
+    // commas and string literals are not tokens yet
+    for input in ["$pad(Product, 6)", "{ a: 1, b: 2 }", "{ 'id': Id }"] {
+        assert!(Expression::compile(input).is_err(), "{}", input);
+    }
+}
+
+#[test]
+fn empty_expression() {
+    check("", json!({ "a": 1 }), None);
+}
+
+#[test]
+fn clock_is_read_once_per_evaluation() {
+    let ticks = Rc::new(Cell::new(0));
+    let mut env = Environment::new();
+    env.set_clock({
+        let ticks = ticks.clone();
+        move || {
+            ticks.set(ticks.get() + 1);
+            1510067557121 + ticks.get() * 1000
+        }
+    });
+
+    let expr = Expression::compile("$millis() - $millis()").unwrap();
+    assert_eq!(expr.evaluate(&json!(null), &env), Ok(Some(json!(0))));
+
+    let expr = Expression::compile("$millis()").unwrap();
+    assert_eq!(
+        expr.evaluate(&json!(null), &env),
+        Ok(Some(json!(1510067559121_i64)))
+    );
+    assert_eq!(ticks.get(), 2);
+
+    env.set_clock(|| 1510067557121);
+    let expr = Expression::compile("$now()").unwrap();
+    assert_eq!(
+        expr.evaluate(&json!(null), &env),
+        Ok(Some(json!("2017-11-07T15:12:37.121Z")))
+    );
+
+    // the host may still replace the evaluation-scoped built-ins
+    env.bind("millis", json!(5));
+    let expr = Expression::compile("$millis").unwrap();
+    assert_eq!(expr.evaluate(&json!(null), &env), Ok(Some(json!(5))));
+}
+
+#[test]
+fn seeded_random_numbers() {
+    let mut env = Environment::new();
+    env.set_random_seed(42);
+
+    let expr = Expression::compile("$random() - $random()").unwrap();
+    let first = expr.evaluate(&json!(null), &env).unwrap();
+
+    // every evaluation is seeded the same way
+    assert_eq!(expr.evaluate(&json!(null), &env), Ok(first));
+
+    let expr = Expression::compile("$shuffle(Tags)").unwrap();
+    let data = json!({ "Tags": ["a", "b", "c", "d", "e", "f"] });
+    let shuffled = expr.evaluate(&data, &env).unwrap();
+    assert_eq!(expr.evaluate(&data, &env), Ok(shuffled));
+}
+
+#[test]
+fn seeded_random_numbers_are_pinned() {
+    // results for a seed must not change between releases or platforms
+    let mut env = Environment::new();
+    env.set_random_seed(42);
+
+    let expr = Expression::compile("$random()").unwrap();
+    assert_eq!(
+        expr.evaluate(&json!(null), &env),
+        Ok(Some(json!(0.6818961923066714)))
+    );
+
+    let expr = Expression::compile("$shuffle(Tags)").unwrap();
+    let data = json!({ "Tags": ["a", "b", "c", "d", "e", "f"] });
+    assert_eq!(
+        expr.evaluate(&data, &env),
+        Ok(Some(json!(["c", "a", "d", "b", "f", "e"])))
+    );
+}
+
+#[test]
+fn host_random_source() {
+    /// Draws 0, 0.25, 0.5, 0.75, 0, ...
+    struct Quarters(u8);
+
+    impl RandomSource for Quarters {
+        fn next_f64(&mut self) -> f64 {
+            let n = self.0;
+            self.0 = (n + 1) % 4;
+            f64::from(n) / 4.0
+        }
+    }
+
+    let mut env = Environment::new();
+    env.set_random_source(Quarters(1));
+
+    // the source carries on from one evaluation to the next
+    let expr = Expression::compile("$random()").unwrap();
+    assert_eq!(expr.evaluate(&json!(null), &env), Ok(Some(json!(0.25))));
+    assert_eq!(expr.evaluate(&json!(null), &env), Ok(Some(json!(0.5))));
+}
+
+#[test]
+fn regex_matchers() {
+    let mut env = Environment::new();
+    env.register_function("regex", "<ss?:f>", |args| {
+        let pattern = args[0].as_ref().and_then(|p| p.as_value()?.as_str());
+        let flags = args[1].as_ref().and_then(|f| f.as_value()?.as_str());
+        JSONataValue::from_regex(pattern.unwrap(), flags.unwrap_or("")).map(Some)
+    })
+    .unwrap();
+    env.bind("mobile", json!("^077"));
+    env.bind("space", json!(r"\s"));
+    env.bind("lookahead", json!("(?=7)"));
+    let data = json!({ "Phone": "077 7700 1234" });
+
+    let expr = Expression::compile("Phone.$contains($regex($mobile))").unwrap();
+    assert_eq!(expr.evaluate(&data, &env), Ok(Some(json!(true))));
+
+    let expr = Expression::compile("Phone.$split($regex($space))").unwrap();
+    assert_eq!(
+        expr.evaluate(&data, &env),
+        Ok(Some(json!(["077", "7700", "1234"])))
+    );
+
+    let expr = Expression::compile("$regex($lookahead)").unwrap();
+    let error = expr.evaluate(&data, &env).unwrap_err();
+    assert_eq!(error.code(), "S0000");
+    assert_eq!(error.to_string(), "Invalid regular expression: /(?=7)/");
+}
+
+#[test]
+fn eval_expressions() {
+    let mut env = Environment::new();
+    env.set_clock(|| 1510067557121);
+    env.bind("rate", json!(2));
+    env.register_function("double", "<n:n>", |args| {
+        let n = args[0].as_ref().and_then(|n| n.as_value()?.as_f64());
+        Ok(n.map(|n| (n * 2.0).into()))
+    })
+    .unwrap();
+    let data = json!({
+        "Total": 5,
+        "Name": "order",
+        "Sources": {
+            "scaled": "Total * $rate",
+            "doubled": "$double(Total)",
+            "timestamp": "$millis()",
+            "broken": "Total +",
+            "dynamic": "Total + Name",
+            "local": "$x",
+            "define": "$y := 1",
+        },
+    });
+
+    let cases = vec![
+        ("$eval(Sources.scaled)", Some(json!(10))),
+        ("$eval(Sources.doubled)", Some(json!(10))),
+        ("$eval(Sources.timestamp)", Some(json!(1510067557121_i64))),
+        ("$eval(Sources.missing)", None),
+        // the variables of the caller are in scope, but not the other way round
+        ("($x := 5; $eval(Sources.local))", Some(json!(5))),
+        ("($eval(Sources.define); $y)", None),
+    ];
+    for (input, expected) in cases {
+        let expr = Expression::compile(input).unwrap();
+        assert_eq!(expr.evaluate(&data, &env), Ok(expected), "{}", input);
+    }
+
+    let expr = Expression::compile("$eval(Sources.broken)").unwrap();
+    assert_eq!(expr.evaluate(&data, &env).unwrap_err().code(), "D3120");
+    let expr = Expression::compile("$eval(Sources.dynamic)").unwrap();
+    assert_eq!(expr.evaluate(&data, &env).unwrap_err().code(), "D3121");
+
+    env.disable_eval();
+    let expr = Expression::compile("$eval(Sources.scaled)").unwrap();
+    assert_eq!(expr.evaluate(&data, &env).unwrap_err().code(), "T1006");
+}
+
+#[test]
+fn eval_after_its_evaluation() {
+    // a host function can keep `$eval` and call it once the evaluation is over
+    let kept = Rc::new(RefCell::new(None));
+    let mut env = Environment::new();
+    let keep = kept.clone();
+    env.register_function("keep", "<f:l>", move |args| {
+        *keep.borrow_mut() = args[0].clone();
+        Ok(None)
+    })
+    .unwrap();
+
+    let expr = Expression::compile("$keep($eval)").unwrap();
+    assert_eq!(expr.evaluate(&json!(null), &env), Ok(None));
+
+    let eval = match kept.borrow_mut().take() {
+        Some(JSONataValue::Function(eval)) => eval,
+        _ => panic!("$eval was not kept"),
+    };
+    let error = eval.call(&[Some(json!("1").into())], None).unwrap_err();
+    assert_eq!(
+        error,
+        EvaluationError::NoEvaluationInProgress("eval".into())
+    );
+    assert_eq!(error.code(), "D0000");
+}
+
+#[test]
+fn user_errors() {
+    let data = json!({ "Items": [], "Message": "Order has no items" });
+    let error = eval("1 + $error(Message)", data).unwrap_err();
+    assert_eq!(
+        error.kind(),
+        &EvaluationError::UserError {
+            message: "Order has no items".into(),
+            range: Some(TextRange::new(4.into(), 19.into())),
+        }
+    );
+    assert_eq!(error.code(), "D3137");
+    assert_eq!(error.range(), Some(TextRange::new(4.into(), 19.into())));
+
+    let error = eval("$assert(Items.Valid)", json!({ "Items": [] })).unwrap_err();
+    assert_eq!(
+        error.kind(),
+        &EvaluationError::AssertionFailed {
+            message: "$assert() statement failed".into(),
+            range: Some(TextRange::new(0.into(), 20.into())),
+        }
+    );
+    assert_eq!(error.code(), "D3141");
+}
+
+#[test]
+fn aggregation_signatures() {
+    let data = json!({ "Prices": [5, 1.5, 3], "Price": 4, "Names": ["a"] });
+    check("$sum(Prices)", data.clone(), Some(json!(9.5)));
+    check("$average(Price)", data.clone(), Some(json!(4)));
+    check_error(
+        "$max(Names)",
+        data,
+        "T0412",
+        r#"Argument 1 of function "max" must be an array of "numbers""#,
+        (0, 11),
+    );
+}
+
+#[test]
+fn sort_mixed_types() {
+    check_error(
+        "$sort(Values)",
+        json!({ "Values": [1, "a"] }),
+        "D3070",
+        "The single argument form of the sort function can only be applied to an array of strings or an array of numbers.  Use the second argument to specify a comparison function",
+        (0, 13),
+    );
+}
@@ -0,0 +1,115 @@
+use std::convert::TryFrom;
+use std::fmt;
+use std::rc::Rc;
+
+use hir::{Database, Expr};
+use parser::ParseError;
+use serde_json::Value;
+use text_size::TextRange;
+
+use crate::{
+    environment::Environment,
+    evaluate::{EvaluationContext, EvaluationError, EvaluationResult, Evaluator, Frame},
+};
+
+/// A compiled JSONata expression
+///
+/// Compiling parses and lowers the source once, after which the expression
+/// can be evaluated against any number of inputs.
+///
+/// ```
+/// let expr = Expression::compile("Account.Order.Price")?;
+/// let env = Environment::new();
+///
+/// for input in inputs {
+///     let result = expr.evaluate(&input, &env)?;
+/// }
+/// ```
+#[derive(Debug)]
+pub struct Expression {
+    db: Database,
+    root: Option<Expr>,
+    range: Option<TextRange>,
+}
+
+impl Expression {
+    /// Parses and lowers the `input` source, returning all of the syntax
+    /// errors if it is not a valid expression.
+    pub fn compile(input: &str) -> Result<Self, Diagnostics> {
+        let parse = parser::parse(input);
+        if !parse.errors().is_empty() {
+            return Err(Diagnostics(parse.errors().to_vec()));
+        }
+
+        let root = ast::Root::cast(parse.syntax()).unwrap();
+        let range = root.expr().map(|expr| expr.range());
+        let (db, root) = hir::lower(root);
+
+        Ok(Self { db, root, range })
+    }
+
+    /// Evaluates the expression with `input` as the context value
+    ///
+    /// Returns `Ok(None)` when the result is undefined, e.g. when a path
+    /// does not match anything in `input`. Errors carry the range of the
+    /// sub-expression that raised them.
+    ///
+    /// The clock of `env` is read once per call, so `$now()` and `$millis()`
+    /// return the same timestamp throughout an evaluation.
+    pub fn evaluate(
+        &self,
+        input: &Value,
+        env: &Environment,
+    ) -> Result<Option<Value>, EvaluationError> {
+        let context = Rc::new(EvaluationContext::new(env));
+        let _evaluation = env.evaluations.enter(context.clone());
+        let result = self
+            .evaluate_in(input, &context, None)
+            .and_then(|result| result.map(Value::try_from).transpose());
+
+        result.map_err(|error| match self.range {
+            Some(range) => error.with_range(range),
+            None => error,
+        })
+    }
+
+    /// Evaluates the expression with `input` as the context value within an
+    /// evaluation that is already in progress, as `$eval` does, where the
+    /// variables of `parent` are in scope
+    pub(crate) fn evaluate_in(
+        &self,
+        input: &Value,
+        context: &EvaluationContext,
+        parent: Option<Rc<Frame>>,
+    ) -> EvaluationResult {
+        match &self.root {
+            Some(root) => Evaluator::new(&self.db, context, parent).eval(root, input),
+            None => Ok(None),
+        }
+    }
+}
+
+/// Syntax errors found while compiling an expression
+#[derive(Debug, Clone, PartialEq)]
+pub struct Diagnostics(Vec<ParseError>);
+
+impl Diagnostics {
+    pub fn errors(&self) -> &[ParseError] {
+        &self.0
+    }
+}
+
+impl fmt::Display for Diagnostics {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        for (idx, error) in self.0.iter().enumerate() {
+            if idx > 0 {
+                writeln!(f)?;
+            }
+            write!(f, "{}", error)?;
+        }
+
+        Ok(())
+    }
+}
+
+impl std::error::Error for Diagnostics {}
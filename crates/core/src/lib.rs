@@ -1,5 +1,20 @@
+mod builtins;
+mod environment;
+mod evaluate;
+mod expression;
+#[cfg(test)]
+mod tests;
+mod value;
+
 use parser::Parse;
 
+pub use environment::Environment;
+pub use evaluate::{
+    ArithmeticOpType, Clock, EvaluationError, EvaluationResult, RandomSource, Side, SystemClock,
+};
+pub use expression::{Diagnostics, Expression};
+pub use value::{number::JSONataNumber, JSONataValue};
+
 pub fn parse(input: &str) -> Parse {
     parser::parse(input)
 }
@@ -0,0 +1,14 @@
+use serde_json::Value;
+
+use crate::{builtins::BuiltIns, evaluate::JSONataVariables, value::JSONataValue};
+
+pub(crate) fn make_val(val: Value) -> JSONataValue {
+    JSONataValue::Value(val)
+}
+
+/// The built-in function registered as `$ident`
+pub(crate) fn builtin(ident: &str) -> Option<JSONataValue> {
+    let mut variables = JSONataVariables::new();
+    BuiltIns::populate_context(&mut variables);
+    variables.remove(ident).flatten()
+}
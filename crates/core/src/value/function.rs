@@ -1,86 +1,96 @@
 use std::{fmt, rc::Rc};
 
+use regex::Regex;
+use serde_json::Value;
+
 use crate::evaluate::EvaluationResult;
 
-use super::JSONataValue;
+use super::{signature::Signature, JSONataValue};
+
+/// Rust function backing a [`JSONataFunction`], which is passed the arguments
+/// of a call and the context value of the call if there is one
+pub(crate) type NativeFn = dyn Fn(&[Option<JSONataValue>], Option<&Value>) -> EvaluationResult;
 
+#[derive(Clone)]
 pub struct JSONataFunction {
-    /// Function which takes a slice of JSONataValue's as arguments
-    /// and returns a EvaluationResult
-    pub(super) func: Rc<dyn Fn(&[Option<JSONataValue>]) -> EvaluationResult>,
+    /// Function which takes a slice of JSONataValue's as arguments, and the
+    /// context value of the call if there is one, and returns a EvaluationResult
+    pub(super) func: Rc<NativeFn>,
 
     /// Identifier for the function without the preceding `$` symbol. For example,
     /// the built-in function $max has an ident of "max". Two functions may not have
     /// the same ident.
     pub(super) ident: String,
 
-    /// A function signature is a string of the form <params:return>. params is a sequence of type symbols, each one representing an input argument's type. return is a single type symbol representing the return value type.
-    ///
-    /// Type symbols work as follows:
-    ///
-    /// Simple types:
-    ///
-    /// * b - Boolean
-    /// * n - number
-    /// * s - string
-    /// * l - null
-    ///
-    /// Complex types:
-    ///
-    /// * a - array
-    /// * o - object
-    /// * f - function
-    ///
-    /// Union types:
-    ///
-    /// * (sao) - string, array or object
-    /// * (o) - same as o
-    /// * u - equivalent to (bnsl) i.e. Boolean, number, string or null
-    /// * j - any JSON type. Equivalent to (bnsloa) i.e. Boolean, number, string, null, object or array, but not function
-    /// * x - any type. Equivalent to (bnsloaf)
-    ///
-    /// Parameterized types:
-    ///
-    /// * a<s> - array of strings
-    /// * a<x> - array of values of any type
-    ///
-    /// Some examples of signatures of built-in JSONata functions:
-    ///
-    /// * $count has signature <a:n>; it accepts an array and returns a number.
-    /// * $append has signature <aa:a>; it accepts two arrays and returns an array.
-    /// * $sum has signature <a<n>:n>; it accepts an array of numbers and returns a number.
-    /// * $reduce has signature <fa<j>:j>; it accepts a reducer function f and an a<j> (array of JSON objects) and returns a JSON object.
-    ///
-    /// Each type symbol may also have options applied.
-    ///
-    /// * + : one or more arguments of this type
-    ///         E.g. $zip has signature <a+>; it accepts one array, or two arrays, or three arrays, or...
-    /// * ? : optional argument
-    ///         E.g. $join has signature <a<s>s?:s>; it accepts an array of strings and an optional joiner string which defaults to the empty string. It returns a string.
-    /// * - : if this argument is missing, use the context value ("focus").
-    ///         E.g. $length has signature <s-:n>; it can be called as $length(OrderID) (one argument) but equivalently as OrderID.$length().
-    pub(super) signature: String,
+    /// Signature which the arguments are validated against before the function
+    /// is called, see [`Signature`] for the syntax. Functions created with
+    /// [`JSONataValue::from_func`] have no signature.
+    pub(super) signature: Option<Signature>,
+
+    /// The compiled regular expression of a regex matcher function, see
+    /// [`JSONataValue::from_regex`]
+    pub(super) regex: Option<Regex>,
 }
 
 impl JSONataFunction {
-    fn test(&self) -> EvaluationResult {
-        (self.func)(&[])
-    }
-
     pub fn ident(&self) -> &str {
         &self.ident
     }
+
+    /// The regular expression, if this is the matcher function of one
+    pub(crate) fn regex(&self) -> Option<&Regex> {
+        self.regex.as_ref()
+    }
+
+    /// Number of leading required parameters declared by the signature of the
+    /// function, or `None` if it has no signature and accepts any number of
+    /// arguments
+    pub(crate) fn arity(&self) -> Option<usize> {
+        self.signature.as_ref().map(Signature::arity)
+    }
+
+    /// Calls the function with the given arguments, `None` represents an
+    /// undefined argument
+    ///
+    /// The arguments are first validated against the signature of the function,
+    /// which may substitute the `context` value for a missing argument. The
+    /// `context` is also passed on to the function itself.
+    pub(crate) fn call(
+        &self,
+        args: &[Option<JSONataValue>],
+        context: Option<&Value>,
+    ) -> EvaluationResult {
+        match &self.signature {
+            Some(signature) => {
+                let args = signature.validate(&self.ident, args, context)?;
+                (self.func)(&args, context)
+            }
+            None => (self.func)(args, context),
+        }
+    }
 }
 
 impl fmt::Debug for JSONataFunction {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
-        write!(f, "todo: format function")
+        f.debug_struct("JSONataFunction")
+            .field("ident", &self.ident)
+            .field(
+                "signature",
+                &self.signature.as_ref().map(Signature::to_string),
+            )
+            .finish()
     }
 }
 
+/// Formats the function as its name followed by its signature,
+/// e.g. `$pad<s-ns?:s>`
 impl fmt::Display for JSONataFunction {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
-        write!(f, "todo: format function")
+        write!(f, "${}", self.ident)?;
+        match &self.signature {
+            Some(signature) => write!(f, "{}", signature),
+            None => Ok(()),
+        }
     }
 }
 
@@ -1,23 +1,30 @@
 mod function;
 pub(crate) mod number;
+pub(crate) mod regex;
+pub(crate) mod sequence;
+pub(crate) mod signature;
+pub(crate) mod stringify;
 #[cfg(test)]
 mod tests;
 mod traits;
 
+use std::collections::hash_map::DefaultHasher;
 use std::convert::TryFrom;
 use std::fmt;
+use std::hash::{Hash, Hasher};
 use std::rc::Rc;
 
 use serde_json::Value;
 
-use crate::ast::dyadic::arithmetic::ArithmeticOpType;
-use crate::ast::literal::LiteralValue;
+use crate::evaluate::ArithmeticOpType;
 use crate::evaluate::EvaluationError;
 use crate::evaluate::EvaluationResult;
+use crate::evaluate::Side;
 
-use self::function::JSONataFunction;
+pub(crate) use self::function::{JSONataFunction, NativeFn};
 use self::number::JSONataNumber;
-use self::traits::TryNumericOps;
+use self::signature::Signature;
+pub(crate) use self::traits::TryNumericOps;
 
 /// Primary data type of JSONata
 ///
@@ -25,7 +32,7 @@ use self::traits::TryNumericOps;
 /// Composed of an enum for either:
 /// * `Value`
 /// * `JSONataFunction`
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 pub enum JSONataValue {
     Value(Value),
     Function(JSONataFunction),
@@ -47,10 +54,41 @@ impl JSONataValue {
         F: 'static + Fn(&[Option<JSONataValue>]) -> EvaluationResult,
         I: Into<String>,
     {
+        let func: Rc<NativeFn> = Rc::new(move |args, _| func(args));
         JSONataFunction {
-            func: Rc::new(func),
+            func,
             ident: ident.into(),
-            signature: "".into(),
+            signature: None,
+            regex: None,
+        }
+        .into()
+    }
+
+    /// Generates a JSONataValue that is a function whose arguments are validated
+    /// against `signature` before it is called.
+    pub(crate) fn from_func_with_signature<F, I>(func: F, ident: I, signature: Signature) -> Self
+    where
+        F: 'static + Fn(&[Option<JSONataValue>]) -> EvaluationResult,
+        I: Into<String>,
+    {
+        JSONataValue::from_func_with_context(move |args, _| func(args), ident, signature)
+    }
+
+    /// Generates a JSONataValue that is a function like
+    /// [`JSONataValue::from_func_with_signature`], which is also passed the
+    /// context value of each call, or `None` when it is called by another
+    /// function rather than from an expression.
+    pub(crate) fn from_func_with_context<F, I>(func: F, ident: I, signature: Signature) -> Self
+    where
+        F: 'static + Fn(&[Option<JSONataValue>], Option<&Value>) -> EvaluationResult,
+        I: Into<String>,
+    {
+        let func: Rc<NativeFn> = Rc::new(func);
+        JSONataFunction {
+            func,
+            ident: ident.into(),
+            signature: Some(signature),
+            regex: None,
         }
         .into()
     }
@@ -167,14 +205,16 @@ impl From<i32> for JSONataValue {
 }
 
 impl From<f64> for JSONataValue {
+    /// An integral `f` is converted to an integer and NaN or the infinities
+    /// to `null`, see [`JSONataNumber::from_f64`]
     fn from(f: f64) -> Self {
-        JSONataValue::Value(f.into())
+        JSONataValue::Value(JSONataNumber::from_f64(f).map_or(Value::Null, JSONataNumber::to_value))
     }
 }
 
-impl From<LiteralValue> for JSONataValue {
-    fn from(val: LiteralValue) -> Self {
-        JSONataValue::Value(val.into())
+impl From<u64> for JSONataValue {
+    fn from(u: u64) -> Self {
+        JSONataValue::Value(u.into())
     }
 }
 
@@ -239,89 +279,106 @@ impl PartialEq for JSONataValue {
     }
 }
 
-impl TryNumericOps for JSONataValue {
-    fn try_add(self, rhs: Self) -> Result<Value, EvaluationError> {
-        if let (Some(self_val), Some(rhs_val)) = (self.as_value(), rhs.as_value()) {
-            match (self_val, rhs_val) {
-                (Value::Number(left), Value::Number(right)) => {
-                    Ok((JSONataNumber::from(left) + JSONataNumber::from(right)).to_value())
-                }
-                (_, _) => Err(EvaluationError::DyadicMustBeNumber(
-                    ArithmeticOpType::Add.into(),
-                )),
+/// Hashes `value` so that values which are equal as JSONataValues have the
+/// same hash, e.g. `1` and `1.0`, or objects with their keys in another order
+///
+/// Numbers are hashed by their value as a float, which is how integers and
+/// floats are compared, and the pairs of an object are combined in a way that
+/// does not depend on their order. Values with the same hash may still differ.
+pub(crate) fn canonical_hash(value: &Value) -> u64 {
+    let mut hasher = DefaultHasher::new();
+    hash_value(value, &mut hasher);
+    hasher.finish()
+}
+
+fn hash_value<H: Hasher>(value: &Value, state: &mut H) {
+    match value {
+        Value::Null => state.write_u8(0),
+        Value::Bool(b) => {
+            state.write_u8(1);
+            b.hash(state);
+        }
+        Value::Number(n) => {
+            state.write_u8(2);
+            // -0.0 is equal to 0
+            let f = JSONataNumber::from(n).as_f64() + 0.0;
+            f.to_bits().hash(state);
+        }
+        Value::String(s) => {
+            state.write_u8(3);
+            s.hash(state);
+        }
+        Value::Array(items) => {
+            state.write_u8(4);
+            state.write_usize(items.len());
+            for item in items {
+                hash_value(item, state);
             }
-        } else {
-            Err(EvaluationError::DyadicMustBeNumber(
-                ArithmeticOpType::Add.into(),
-            ))
+        }
+        Value::Object(object) => {
+            state.write_u8(5);
+            state.write_usize(object.len());
+            let pairs = object.iter().fold(0u64, |pairs, (key, value)| {
+                let mut pair = DefaultHasher::new();
+                key.hash(&mut pair);
+                hash_value(value, &mut pair);
+                pairs.wrapping_add(pair.finish())
+            });
+            state.write_u64(pairs);
         }
     }
+}
 
-    fn try_sub(self, rhs: Self) -> Result<Value, EvaluationError> {
-        if let (Some(self_val), Some(rhs_val)) = (self.as_value(), rhs.as_value()) {
-            match (self_val, rhs_val) {
-                (Value::Number(left), Value::Number(right)) => {
-                    Ok((JSONataNumber::from(left) - JSONataNumber::from(right)).to_value())
-                }
-                (_, _) => Err(EvaluationError::DyadicMustBeNumber(
-                    ArithmeticOpType::Sub.into(),
-                )),
-            }
-        } else {
-            Err(EvaluationError::DyadicMustBeNumber(
-                ArithmeticOpType::Sub.into(),
-            ))
+impl JSONataValue {
+    /// Applies an arithmetic operator to two values, checking that both are numbers
+    /// and that the result can be represented as a JSON number
+    fn try_arithmetic(self, rhs: Self, op: ArithmeticOpType) -> Result<Value, EvaluationError> {
+        let left = JSONataNumber::try_from(self)
+            .map_err(|_| EvaluationError::DyadicMustBeNumber(op, Side::Left))?;
+        let right = JSONataNumber::try_from(rhs)
+            .map_err(|_| EvaluationError::DyadicMustBeNumber(op, Side::Right))?;
+
+        let (l, r) = (left.as_f64(), right.as_f64());
+        let approx = match op {
+            ArithmeticOpType::Add => l + r,
+            ArithmeticOpType::Sub => l - r,
+            ArithmeticOpType::Mul => l * r,
+            ArithmeticOpType::Div => l / r,
+            ArithmeticOpType::Rem => l % r,
+        };
+        if !approx.is_finite() {
+            return Err(EvaluationError::NumberOutOfRange(approx));
         }
+
+        let result = match op {
+            ArithmeticOpType::Add => left + right,
+            ArithmeticOpType::Sub => left - right,
+            ArithmeticOpType::Mul => left * right,
+            ArithmeticOpType::Div => left / right,
+            ArithmeticOpType::Rem => left % right,
+        };
+        Ok(result.to_value())
+    }
+}
+
+impl TryNumericOps for JSONataValue {
+    fn try_add(self, rhs: Self) -> Result<Value, EvaluationError> {
+        self.try_arithmetic(rhs, ArithmeticOpType::Add)
+    }
+
+    fn try_sub(self, rhs: Self) -> Result<Value, EvaluationError> {
+        self.try_arithmetic(rhs, ArithmeticOpType::Sub)
     }
 
     fn try_mul(self, rhs: Self) -> Result<Value, EvaluationError> {
-        if let (Some(self_val), Some(rhs_val)) = (self.as_value(), rhs.as_value()) {
-            match (self_val, rhs_val) {
-                (Value::Number(left), Value::Number(right)) => {
-                    Ok((JSONataNumber::from(left) * JSONataNumber::from(right)).to_value())
-                }
-                (_, _) => Err(EvaluationError::DyadicMustBeNumber(
-                    ArithmeticOpType::Mul.into(),
-                )),
-            }
-        } else {
-            Err(EvaluationError::DyadicMustBeNumber(
-                ArithmeticOpType::Mul.into(),
-            ))
-        }
+        self.try_arithmetic(rhs, ArithmeticOpType::Mul)
     }
 
     fn try_div(self, rhs: Self) -> Result<Value, EvaluationError> {
-        if let (Some(self_val), Some(rhs_val)) = (self.as_value(), rhs.as_value()) {
-            match (self_val, rhs_val) {
-                (Value::Number(left), Value::Number(right)) => {
-                    Ok((JSONataNumber::from(left) / JSONataNumber::from(right)).to_value())
-                }
-                (_, _) => Err(EvaluationError::DyadicMustBeNumber(
-                    ArithmeticOpType::Div.into(),
-                )),
-            }
-        } else {
-            Err(EvaluationError::DyadicMustBeNumber(
-                ArithmeticOpType::Div.into(),
-            ))
-        }
+        self.try_arithmetic(rhs, ArithmeticOpType::Div)
     }
 
     fn try_rem(self, rhs: Self) -> Result<Value, EvaluationError> {
-        if let (Some(self_val), Some(rhs_val)) = (self.as_value(), rhs.as_value()) {
-            match (self_val, rhs_val) {
-                (Value::Number(left), Value::Number(right)) => {
-                    Ok((JSONataNumber::from(left) % JSONataNumber::from(right)).to_value())
-                }
-                (_, _) => Err(EvaluationError::DyadicMustBeNumber(
-                    ArithmeticOpType::Div.into(),
-                )),
-            }
-        } else {
-            Err(EvaluationError::DyadicMustBeNumber(
-                ArithmeticOpType::Div.into(),
-            ))
-        }
+        self.try_arithmetic(rhs, ArithmeticOpType::Rem)
     }
 }
@@ -8,10 +8,12 @@ use serde_json::{Number, Value};
 
 use crate::evaluate::EvaluationError;
 
-use super::JSONataValue;
+use super::{stringify::number_to_string, JSONataValue};
 
+/// A JSON number with the arithmetic of JSONata, where integers and floating
+/// point numbers with the same value are equal
 #[derive(Debug, Clone)]
-pub(crate) struct JSONataNumber(Number);
+pub struct JSONataNumber(Number);
 
 #[derive(Debug, Clone, Copy)]
 enum NType {
@@ -20,13 +22,63 @@ enum NType {
     Float(f64),
 }
 
+/// Largest integer up to which every integer is exactly representable as an f64
+const MAX_SAFE_INTEGER: f64 = 9_007_199_254_740_992.0;
+
 impl JSONataNumber {
+    pub fn as_f64(&self) -> f64 {
+        self.0.as_f64().unwrap() // always representable without arbitrary_precision
+    }
+
     pub fn to_value(self) -> Value {
         Value::Number(self.0)
     }
 }
 
 impl JSONataNumber {
+    /// Converts `f` to a number, or `None` for NaN and the infinities which JSON
+    /// cannot represent. An integral value is stored as an integer, since
+    /// JavaScript does not tell the two apart, so `10 / 2` gives `5` rather
+    /// than `5.0`. Beyond 2^53 a float may be an approximation, so it is kept.
+    pub(crate) fn from_f64(f: f64) -> Option<Self> {
+        if f.fract() == 0.0 && f.abs() <= MAX_SAFE_INTEGER {
+            Some((f as i64).into())
+        } else {
+            Number::from_f64(f).map(JSONataNumber)
+        }
+    }
+
+    /// The product of integers, which is approximated as a float if it
+    /// does not fit in an i128
+    fn mul_i128(a: i128, b: i128) -> Self {
+        match a.checked_mul(b) {
+            Some(n) => JSONataNumber::from_i128(n),
+            None => (a as f64 * b as f64).into(),
+        }
+    }
+
+    /// The result of integer arithmetic, which stays exact if it fits in an
+    /// i64 or u64 and is otherwise approximated as a float
+    fn from_i128(n: i128) -> Self {
+        if let Ok(i) = i64::try_from(n) {
+            i.into()
+        } else if let Ok(u) = u64::try_from(n) {
+            u.into()
+        } else {
+            (n as f64).into()
+        }
+    }
+
+    /// Adds `rhs` like the `+` operator, raising D1001 if the sum is not finite
+    pub fn checked_add(self, rhs: Self) -> Result<Self, EvaluationError> {
+        let approx = self.as_f64() + rhs.as_f64();
+        if approx.is_finite() {
+            Ok(self + rhs)
+        } else {
+            Err(EvaluationError::NumberOutOfRange(approx))
+        }
+    }
+
     pub fn abs(&self) -> Self {
         let num = &self.0;
         if num.is_f64() {
@@ -53,6 +105,118 @@ impl JSONataNumber {
         }
     }
 
+    pub fn ceil(&self) -> Self {
+        let num = &self.0;
+        if num.is_f64() {
+            num.as_f64().unwrap().ceil().into()
+        } else {
+            num.clone().into()
+        }
+    }
+
+    /// Rounds to `precision` decimal places, or to the column `-precision` places
+    /// to the left of the decimal point if `precision` is negative. Numbers exactly
+    /// halfway between two candidates are rounded to the even one.
+    pub fn round(&self, precision: i64) -> Self {
+        if !self.0.is_f64() && precision >= 0 {
+            return self.clone();
+        }
+
+        let shifted = JSONataNumber::shift(self.as_f64(), precision);
+        if !shifted.is_finite() {
+            return self.clone();
+        }
+        let rounded = JSONataNumber::shift(shifted.round_ties_even(), -precision);
+
+        // avoid negative zero
+        if rounded == 0.0 {
+            0.0.into()
+        } else {
+            rounded.into()
+        }
+    }
+
+    /// Formats the number as an integer in the given `radix`, which must be between
+    /// 2 and 36, as `$formatBase` does. Non-integers are first rounded to the nearest
+    /// integer, or to the even one if exactly halfway, and digits above 9 are written
+    /// as lowercase letters, e.g. `-255` in radix 16 is `"-ff"`.
+    pub fn to_string_radix(&self, radix: u32) -> Result<String, EvaluationError> {
+        if !(2..=36).contains(&radix) {
+            return Err(EvaluationError::InvalidRadix(radix.into()));
+        }
+
+        let (negative, mut limbs) = match NType::from(&self.round(0).0) {
+            NType::PosInt(u) => (false, JSONataNumber::limbs(u, 0)),
+            NType::NegInt(i) => (i < 0, JSONataNumber::limbs(i.unsigned_abs(), 0)),
+            // as in JavaScript, large numbers in radix 10 use exponential notation
+            NType::Float(f) if radix == 10 => return number_to_string(f),
+            NType::Float(f) => {
+                // f is an integer, so is exactly mantissa * 2^exponent
+                let bits = f.abs().to_bits();
+                let exponent = ((bits >> 52) & 0x7ff) as i64;
+                let mut mantissa = bits & ((1 << 52) - 1);
+                if exponent != 0 {
+                    mantissa |= 1 << 52;
+                }
+                let shift = exponent.max(1) - 1075;
+                if shift < 0 {
+                    let mantissa = mantissa.checked_shr(-shift as u32).unwrap_or(0);
+                    (f < 0.0, JSONataNumber::limbs(mantissa, 0))
+                } else {
+                    (f < 0.0, JSONataNumber::limbs(mantissa, shift as u32))
+                }
+            }
+        };
+
+        // the remainders of repeated division are the digits from the right
+        let mut digits = Vec::new();
+        while !limbs.is_empty() {
+            let mut remainder = 0_u64;
+            for limb in limbs.iter_mut().rev() {
+                let current = (remainder << 32) | *limb as u64;
+                *limb = (current / radix as u64) as u32;
+                remainder = current % radix as u64;
+            }
+            while limbs.last() == Some(&0) {
+                limbs.pop();
+            }
+            digits.push(std::char::from_digit(remainder as u32, radix).unwrap());
+            // remainder < radix
+        }
+        if digits.is_empty() {
+            digits.push('0');
+        }
+        if negative {
+            digits.push('-');
+        }
+
+        Ok(digits.into_iter().rev().collect())
+    }
+
+    /// The 32 bit limbs, least significant first, of `value * 2^shift`
+    fn limbs(value: u64, shift: u32) -> Vec<u32> {
+        let mut limbs = vec![0; (shift / 32) as usize];
+        let shifted = (value as u128) << (shift % 32);
+        limbs.extend((0..3).map(|i| (shifted >> (32 * i)) as u32));
+        while limbs.last() == Some(&0) {
+            limbs.pop();
+        }
+        limbs
+    }
+
+    /// Multiplies `f` by 10 to the power of `exponent` by adjusting its decimal
+    /// representation, which avoids the error of a floating point multiplication
+    /// (e.g. 1.005 * 100 is 100.49999999999999)
+    fn shift(f: f64, exponent: i64) -> f64 {
+        if exponent == 0 {
+            return f;
+        }
+        let repr = format!("{:e}", f);
+        let (mantissa, exp) = repr.split_once('e').unwrap(); // always present in {:e}
+        let exp: i64 = exp.parse().unwrap();
+        format!("{}e{}", mantissa, exp + exponent).parse().unwrap()
+    }
+
     fn compare_f64_and_u64(f: f64, u: u64) -> Option<Ordering> {
         if u < (f64::MAX as u64) {
             f.partial_cmp(&(u as f64))
@@ -67,7 +231,7 @@ impl JSONataNumber {
 
     fn compare_f64_and_i64(f: f64, i: i64) -> Option<Ordering> {
         if i < 0 {
-            match JSONataNumber::compare_f64_and_u64(-f, (-i) as u64) {
+            match JSONataNumber::compare_f64_and_u64(-f, i.unsigned_abs()) {
                 Some(ordering) => Some(ordering.reverse()),
                 None => None,
             }
@@ -143,7 +307,7 @@ impl From<u64> for JSONataNumber {
 
 impl From<f64> for JSONataNumber {
     fn from(f: f64) -> Self {
-        JSONataNumber(Number::from_f64(f).unwrap())
+        JSONataNumber::from_f64(f).unwrap()
     }
 }
 
@@ -152,10 +316,12 @@ impl TryFrom<JSONataValue> for JSONataNumber {
 
     fn try_from(value: JSONataValue) -> Result<Self, Self::Error> {
         match value {
-            JSONataValue::Function(_) => Err(EvaluationError::CannotConvertToNumber),
+            JSONataValue::Function(func) => Err(EvaluationError::CannotConvertToNumber(
+                Value::String(func.ident().to_string()),
+            )),
             JSONataValue::Value(value) => match value {
                 Value::Number(num) => Ok(num.into()),
-                _ => Err(EvaluationError::CannotConvertToNumber),
+                value => Err(EvaluationError::CannotConvertToNumber(value)),
             },
         }
     }
@@ -173,7 +339,7 @@ impl TryFrom<&Value> for JSONataNumber {
     fn try_from(val: &Value) -> Result<Self, Self::Error> {
         match val {
             Value::Number(num) => Ok(num.into()),
-            _ => Err(EvaluationError::CannotConvertToNumber),
+            val => Err(EvaluationError::CannotConvertToNumber(val.clone())),
         }
     }
 }
@@ -265,8 +431,8 @@ impl Neg for JSONataNumber {
     fn neg(self) -> Self::Output {
         let self_type: NType = (&self.0).into();
         match self_type {
-            NType::NegInt(i) => JSONataNumber::from(-i),
-            NType::PosInt(u) => JSONataNumber::from(-(u as i64)),
+            NType::NegInt(i) => JSONataNumber::from_i128(-(i as i128)),
+            NType::PosInt(u) => JSONataNumber::from_i128(-(u as i128)),
             NType::Float(f) => JSONataNumber::from(-f),
         }
     }
@@ -279,12 +445,12 @@ impl Add for JSONataNumber {
         let self_type: NType = (&self.0).into();
         let rhs_type: NType = (&rhs.0).into();
         match (self_type, rhs_type) {
-            (NType::NegInt(a), NType::NegInt(b)) => (a + b).into(),
-            (NType::PosInt(a), NType::PosInt(b)) => (a + b).into(),
+            (NType::NegInt(a), NType::NegInt(b)) => JSONataNumber::from_i128(a as i128 + b as i128),
+            (NType::PosInt(a), NType::PosInt(b)) => JSONataNumber::from_i128(a as i128 + b as i128),
             (NType::Float(a), NType::Float(b)) => (a + b).into(),
 
-            (NType::NegInt(i), NType::PosInt(u)) => (i + u as i64).into(),
-            (NType::PosInt(u), NType::NegInt(i)) => (i + u as i64).into(),
+            (NType::NegInt(i), NType::PosInt(u)) => JSONataNumber::from_i128(i as i128 + u as i128),
+            (NType::PosInt(u), NType::NegInt(i)) => JSONataNumber::from_i128(i as i128 + u as i128),
 
             (NType::NegInt(i), NType::Float(f)) => (f + i as f64).into(),
             (NType::PosInt(u), NType::Float(f)) => (f + u as f64).into(),
@@ -309,12 +475,12 @@ impl Mul for JSONataNumber {
         let self_type: NType = (&self.0).into();
         let rhs_type: NType = (&rhs.0).into();
         match (self_type, rhs_type) {
-            (NType::NegInt(a), NType::NegInt(b)) => (a * b).into(),
-            (NType::PosInt(a), NType::PosInt(b)) => (a * b).into(),
+            (NType::NegInt(a), NType::NegInt(b)) => JSONataNumber::mul_i128(a as i128, b as i128),
+            (NType::PosInt(a), NType::PosInt(b)) => JSONataNumber::mul_i128(a as i128, b as i128),
             (NType::Float(a), NType::Float(b)) => (a * b).into(),
 
-            (NType::NegInt(i), NType::PosInt(u)) => (i * u as i64).into(),
-            (NType::PosInt(u), NType::NegInt(i)) => (i * u as i64).into(),
+            (NType::NegInt(i), NType::PosInt(u)) => JSONataNumber::mul_i128(i as i128, u as i128),
+            (NType::PosInt(u), NType::NegInt(i)) => JSONataNumber::mul_i128(i as i128, u as i128),
 
             (NType::NegInt(i), NType::Float(f)) => (f * i as f64).into(),
             (NType::PosInt(u), NType::Float(f)) => (f * u as f64).into(),
@@ -332,12 +498,12 @@ impl Div for JSONataNumber {
         let rhs_type: NType = (&rhs.0).into();
 
         match (self_type, rhs_type) {
-            (NType::NegInt(a), NType::NegInt(b)) => ((a / b) as f64).into(),
-            (NType::PosInt(a), NType::PosInt(b)) => ((a / b) as f64).into(),
-            (NType::Float(a), NType::Float(b)) => ((a / b) as f64).into(),
+            (NType::NegInt(a), NType::NegInt(b)) => (a as f64 / b as f64).into(),
+            (NType::PosInt(a), NType::PosInt(b)) => (a as f64 / b as f64).into(),
+            (NType::Float(a), NType::Float(b)) => (a / b).into(),
 
-            (NType::NegInt(a), NType::PosInt(b)) => ((a / b as i64) as f64).into(),
-            (NType::PosInt(a), NType::NegInt(b)) => ((a as i64 / b) as f64).into(),
+            (NType::NegInt(a), NType::PosInt(b)) => (a as f64 / b as f64).into(),
+            (NType::PosInt(a), NType::NegInt(b)) => (a as f64 / b as f64).into(),
 
             (NType::NegInt(a), NType::Float(b)) => (a as f64 / b).into(),
             (NType::PosInt(a), NType::Float(b)) => (a as f64 / b).into(),
@@ -424,4 +590,49 @@ mod tests {
             assert_eq!(lhs % rhs, expected)
         }
     }
+
+    #[test]
+    fn round() {
+        let cases: Vec<(JSONataNumber, i64, JSONataNumber)> = vec![
+            (123.456.into(), 0, 123_u64.into()),
+            (123.456.into(), 2, 123.46.into()),
+            (123.456.into(), -1, 120_u64.into()),
+            (123.456.into(), -2, 100_u64.into()),
+            (11.5.into(), 0, 12_u64.into()),
+            (12.5.into(), 0, 12_u64.into()),
+            ((-12.5).into(), 0, (-12_i64).into()),
+            (125_u64.into(), -1, 120_u64.into()),
+            (1.005.into(), 2, 1.0.into()),
+            (0.125.into(), 2, 0.12.into()),
+        ];
+        for (number, precision, expected) in cases {
+            assert_eq!(number.round(precision), expected);
+        }
+    }
+
+    #[test]
+    fn to_string_radix() {
+        let cases: Vec<(JSONataNumber, u32, &str)> = vec![
+            (100_u64.into(), 2, "1100100"),
+            (2555_u64.into(), 16, "9fb"),
+            ((-255_i64).into(), 16, "-ff"),
+            (0_u64.into(), 36, "0"),
+            (35_u64.into(), 36, "z"),
+            (12.5.into(), 2, "1100"),
+            (13.5.into(), 8, "16"),
+            ((-0.4).into(), 2, "0"),
+            (u64::MAX.into(), 16, "ffffffffffffffff"),
+            (1e21.into(), 16, "3635c9adc5dea00000"),
+            (1e21.into(), 10, "1e+21"),
+            (2f64.powi(100).into(), 32, "100000000000000000000"),
+        ];
+        for (number, radix, expected) in cases {
+            assert_eq!(number.to_string_radix(radix), Ok(expected.to_string()));
+        }
+
+        assert_eq!(
+            JSONataNumber::from(1_u64).to_string_radix(37),
+            Err(EvaluationError::InvalidRadix(37.into()))
+        );
+    }
 }
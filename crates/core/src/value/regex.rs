@@ -0,0 +1,352 @@
+//! Regular expressions, written in JSONata as in JavaScript, e.g. `/ab+c/i`
+//!
+//! A regular expression evaluates to a matcher function, which takes a string
+//! and returns an object describing the first match, or undefined if there is
+//! none. Patterns use the subset of ECMAScript syntax documented by JSONata and
+//! are translated to the syntax of the `regex` crate, which has no equivalent
+//! for backreferences or lookaround.
+
+use std::rc::Rc;
+
+use regex::{Captures, Regex};
+use serde_json::{Map, Value};
+
+use crate::evaluate::EvaluationError;
+
+use super::{signature::Signature, JSONataFunction, JSONataValue};
+
+/// Characters matched by `\w` and separated by `\b` in ECMAScript
+const WORD: &str = "0-9A-Za-z_";
+
+/// Characters which are not matched by `.` in ECMAScript
+const LINE_TERMINATORS: &str = r"\n\r\x{2028}\x{2029}";
+
+/// A match of a regular expression, positions are byte offsets into the
+/// string that was searched
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) struct RegexMatch {
+    pub(crate) start: usize,
+    pub(crate) end: usize,
+    /// The substrings captured by each group, `None` for a group which did
+    /// not participate in the match
+    pub(crate) groups: Vec<Option<String>>,
+}
+
+impl RegexMatch {
+    pub(crate) fn from_captures(captures: &Captures) -> Self {
+        let whole = captures.get(0).expect("group 0 is always the whole match");
+        Self {
+            start: whole.start(),
+            end: whole.end(),
+            groups: captures
+                .iter()
+                .skip(1)
+                .map(|group| group.map(|group| group.as_str().to_string()))
+                .collect(),
+        }
+    }
+
+    /// The object returned by `$match` for this match of `s`, with the
+    /// `index` counted in characters
+    pub(crate) fn to_value(&self, s: &str) -> Value {
+        let mut object = Map::new();
+        object.insert("match".into(), s[self.start..self.end].into());
+        object.insert("index".into(), s[..self.start].chars().count().into());
+        object.insert("groups".into(), self.groups_value());
+        Value::Object(object)
+    }
+
+    /// The object returned by a matcher function for this match of `s`
+    fn to_matcher_value(&self, s: &str) -> Value {
+        let start = s[..self.start].chars().count();
+        let mut object = Map::new();
+        object.insert("match".into(), s[self.start..self.end].into());
+        object.insert("start".into(), start.into());
+        object.insert(
+            "end".into(),
+            (start + s[self.start..self.end].chars().count()).into(),
+        );
+        object.insert("groups".into(), self.groups_value());
+        Value::Object(object)
+    }
+
+    fn groups_value(&self) -> Value {
+        self.groups
+            .iter()
+            .map(|group| group.as_deref().map_or(Value::Null, Value::from))
+            .collect()
+    }
+}
+
+impl JSONataValue {
+    /// Generates a JSONataValue that is the matcher function of a regular
+    /// expression, as a regex literal `/pattern/flags` evaluates to
+    ///
+    /// The flags may be `i` for a case-insensitive match and `m` for `^` and
+    /// `$` to match at the start and end of each line. Returns an error if
+    /// the pattern is empty, is not valid or is not supported.
+    pub fn from_regex(pattern: &str, flags: &str) -> Result<Self, EvaluationError> {
+        let regex = compile(pattern, flags)?;
+        let matcher = regex.clone();
+        let func = move |args: &[Option<JSONataValue>], _: Option<&Value>| {
+            let s = match args
+                .first()
+                .and_then(|arg| arg.as_ref()?.as_value()?.as_str())
+            {
+                Some(s) => s,
+                None => return Ok(None),
+            };
+            Ok(matcher.captures(s).map(|captures| {
+                RegexMatch::from_captures(&captures)
+                    .to_matcher_value(s)
+                    .into()
+            }))
+        };
+
+        Ok(JSONataFunction {
+            func: Rc::new(func),
+            ident: format!("/{}/{}", pattern, flags),
+            signature: Some(Signature::parse("<s-:o>")?),
+            regex: Some(regex),
+        }
+        .into())
+    }
+}
+
+fn compile(pattern: &str, flags: &str) -> Result<Regex, EvaluationError> {
+    if pattern.is_empty() {
+        return Err(EvaluationError::EmptyRegex);
+    }
+    let invalid = || EvaluationError::InvalidRegex(format!("/{}/{}", pattern, flags));
+
+    let mut translated = String::new();
+    for flag in flags.chars() {
+        match flag {
+            'i' if !translated.contains('i') => translated.push('i'),
+            'm' if !translated.contains('m') => translated.push('m'),
+            _ => return Err(invalid()),
+        }
+    }
+    if !translated.is_empty() {
+        translated = format!("(?{})", translated);
+    }
+
+    translate(pattern, &mut translated).ok_or_else(invalid)?;
+    Regex::new(&translated).map_err(|_| invalid())
+}
+
+/// Translates an ECMAScript `pattern` to the syntax of the `regex` crate,
+/// returning `None` for syntax which has no equivalent
+fn translate(pattern: &str, out: &mut String) -> Option<()> {
+    let chars: Vec<char> = pattern.chars().collect();
+    let mut in_class = false;
+    let mut i = 0;
+    while i < chars.len() {
+        let c = chars[i];
+        i += 1;
+        match c {
+            '\\' => {
+                let escaped = *chars.get(i)?;
+                i += 1;
+                match escaped {
+                    'd' | 'D' | 'w' | 'W' => {
+                        let negated = if escaped.is_ascii_uppercase() {
+                            "^"
+                        } else {
+                            ""
+                        };
+                        let set = if escaped.eq_ignore_ascii_case(&'d') {
+                            "0-9"
+                        } else {
+                            WORD
+                        };
+                        out.push_str(&format!("[{}{}]", negated, set));
+                    }
+                    'b' if in_class => out.push_str(r"\x08"),
+                    'b' | 'B' => out.push_str(&format!(r"(?-u:\{})", escaped)),
+                    's' | 'S' | 't' | 'n' | 'v' | 'f' | 'r' => {
+                        out.push('\\');
+                        out.push(escaped);
+                    }
+                    '0' if !chars.get(i).is_some_and(char::is_ascii_digit) => out.push_str(r"\x00"),
+                    'x' => {
+                        let hex = hex_digits(&chars, i, 2)?;
+                        out.push_str(&format!(r"\x{{{}}}", hex));
+                        i += 2;
+                    }
+                    'u' => {
+                        let hex = hex_digits(&chars, i, 4)?;
+                        out.push_str(&format!(r"\x{{{}}}", hex));
+                        i += 4;
+                    }
+                    'c' => {
+                        let letter = chars.get(i).filter(|c| c.is_ascii_alphabetic())?;
+                        out.push_str(&format!(r"\x{{{:02X}}}", *letter as u32 % 32));
+                        i += 1;
+                    }
+                    // backreferences and named backreferences
+                    '1'..='9' | 'k' => return None,
+                    escaped if escaped.is_ascii_alphanumeric() => return None,
+                    escaped => out.push_str(&regex::escape(&escaped.to_string())),
+                }
+            }
+            '[' if in_class => out.push_str(r"\["),
+            '[' => {
+                // `[]` matches nothing and `[^]` matches anything
+                match (chars.get(i), chars.get(i + 1)) {
+                    (Some(']'), _) => {
+                        out.push_str(r"[^\s\S]");
+                        i += 1;
+                        continue;
+                    }
+                    (Some('^'), Some(']')) => {
+                        out.push_str(r"[\s\S]");
+                        i += 2;
+                        continue;
+                    }
+                    _ => {}
+                }
+                in_class = true;
+                out.push('[');
+                if chars.get(i) == Some(&'^') {
+                    out.push('^');
+                    i += 1;
+                }
+            }
+            ']' if in_class => {
+                in_class = false;
+                out.push(']');
+            }
+            // set operations of the `regex` crate
+            '&' | '~' if in_class => {
+                out.push('\\');
+                out.push(c);
+            }
+            '.' if !in_class => out.push_str(&format!("[^{}]", LINE_TERMINATORS)),
+            '(' if !in_class && chars.get(i) == Some(&'?') => match chars.get(i + 1) {
+                Some(':') => {
+                    out.push_str("(?:");
+                    i += 2;
+                }
+                Some('<') if !matches!(chars.get(i + 2), Some('=') | Some('!')) => {
+                    out.push_str("(?<");
+                    i += 2;
+                }
+                // lookahead and lookbehind
+                _ => return None,
+            },
+            '{' if !in_class => {
+                let end = quantifier_end(&chars, i);
+                match end {
+                    Some(end) => {
+                        out.extend(&chars[i - 1..end]);
+                        i = end;
+                    }
+                    // not a quantifier, so a literal brace
+                    None => out.push_str(r"\{"),
+                }
+            }
+            '}' if !in_class => out.push_str(r"\}"),
+            c => out.push(c),
+        }
+    }
+
+    if in_class {
+        None
+    } else {
+        Some(())
+    }
+}
+
+fn hex_digits(chars: &[char], start: usize, count: usize) -> Option<String> {
+    let digits = chars.get(start..start + count)?;
+    if digits.iter().all(char::is_ascii_hexdigit) {
+        Some(digits.iter().collect())
+    } else {
+        None
+    }
+}
+
+/// The end of a quantifier `{n}`, `{n,}` or `{n,m}` whose digits start at
+/// `start`, which is just after the opening brace
+fn quantifier_end(chars: &[char], start: usize) -> Option<usize> {
+    let close = start + chars[start..].iter().position(|c| *c == '}')?;
+    let mut bounds = chars[start..close].split(|c| *c == ',');
+    let min = bounds.next()?;
+    let max = bounds.next();
+    let valid = !min.is_empty()
+        && min.iter().all(char::is_ascii_digit)
+        && max.is_none_or(|max| max.iter().all(char::is_ascii_digit))
+        && bounds.next().is_none();
+    valid.then_some(close + 1)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn translated(pattern: &str) -> Option<String> {
+        let mut out = String::new();
+        translate(pattern, &mut out).map(|_| out)
+    }
+
+    #[test]
+    fn translate_ecmascript_syntax() {
+        let cases = vec![
+            (r"a\d+", Some(r"a[0-9]+")),
+            (r"[\w-]", Some(r"[[0-9A-Za-z_]-]")),
+            (r"\bcat\b", Some(r"(?-u:\b)cat(?-u:\b)")),
+            (r"\/\.\u00e9\x41", Some(r"/\.\x{00e9}\x{41}")),
+            (r"a.c", Some(r"a[^\n\r\x{2028}\x{2029}]c")),
+            (r"[^][]", Some(r"[\s\S][^\s\S]")),
+            (r"[a[&&]", Some(r"[a\[\&\&]")),
+            (r"a{2,3}b{,1}{x}", Some(r"a{2,3}b\{,1\}\{x\}")),
+            (r"(?:a)(?<year>\d{4})", Some(r"(?:a)(?<year>[0-9]{4})")),
+            (r"(a)\1", None),
+            (r"a(?=b)", None),
+            (r"(?<!a)b", None),
+            (r"[abc", None),
+            (r"\", None),
+        ];
+        for (pattern, expected) in cases {
+            assert_eq!(translated(pattern).as_deref(), expected, "{}", pattern);
+            if let Some(expected) = expected {
+                assert!(Regex::new(expected).is_ok(), "{}", expected);
+            }
+        }
+    }
+
+    #[test]
+    fn matcher_function() {
+        let matcher = match JSONataValue::from_regex("(a)(x)?b+", "").unwrap() {
+            JSONataValue::Function(matcher) => matcher,
+            JSONataValue::Value(_) => unreachable!(),
+        };
+        assert_eq!(
+            matcher.call(&[Some("😀 abb".into())], None),
+            Ok(Some(
+                serde_json::json!({ "match": "abb", "start": 2, "end": 5, "groups": ["a", null] })
+                    .into()
+            ))
+        );
+        assert_eq!(matcher.call(&[Some("c".into())], None), Ok(None));
+        assert_eq!(matcher.ident(), "/(a)(x)?b+/");
+        assert!(matcher.regex().is_some());
+    }
+
+    #[test]
+    fn compile_flags() {
+        assert!(compile("Hello", "i").unwrap().is_match("hELLO"));
+        assert!(compile("^b", "m").unwrap().is_match("a\nb"));
+        assert!(!compile("^b", "").unwrap().is_match("a\nb"));
+        assert_eq!(compile("", "").err(), Some(EvaluationError::EmptyRegex));
+        assert_eq!(
+            compile("a", "g").err(),
+            Some(EvaluationError::InvalidRegex("/a/g".into()))
+        );
+        assert_eq!(
+            compile("a)", "").err(),
+            Some(EvaluationError::InvalidRegex("/a)/".into()))
+        );
+    }
+}
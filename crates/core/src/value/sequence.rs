@@ -0,0 +1,96 @@
+use std::convert::TryFrom;
+
+use serde_json::Value;
+
+use crate::evaluate::EvaluationError;
+
+use super::JSONataValue;
+
+/// Ordered results of evaluating a path
+///
+/// A sequence collapses when it becomes the result of an expression: an
+/// empty sequence is undefined and a sequence of one item is that item,
+/// unless `keep_array` is set in which case the items always form an array.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub(crate) struct Sequence {
+    items: Vec<JSONataValue>,
+    keep_array: bool,
+
+    /// The items are those of a single array from the input, which is
+    /// returned as-is rather than collapsed, even when empty
+    is_input_array: bool,
+}
+
+impl Sequence {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Collects the results of evaluating a path step against each item of
+    /// its input sequence.
+    ///
+    /// The results are flattened by one level, except when the last step of
+    /// a path produces a single array which is kept as-is.
+    pub fn from_step(mut results: Vec<JSONataValue>, last_step: bool) -> Self {
+        if last_step && results.len() == 1 {
+            if let JSONataValue::Value(Value::Array(items)) = &mut results[0] {
+                let items = std::mem::take(items);
+                return Self {
+                    items: items.into_iter().map(JSONataValue::Value).collect(),
+                    keep_array: false,
+                    is_input_array: true,
+                };
+            }
+        }
+
+        let mut sequence = Self::new();
+        for result in results {
+            sequence.push_flattened(result);
+        }
+        sequence
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.items.is_empty()
+    }
+
+    pub fn push(&mut self, value: JSONataValue) {
+        self.items.push(value);
+    }
+
+    /// Pushes the items of an array, or the value itself if it is not an array
+    pub fn push_flattened(&mut self, value: JSONataValue) {
+        match value {
+            JSONataValue::Value(Value::Array(items)) => self
+                .items
+                .extend(items.into_iter().map(JSONataValue::Value)),
+            value => self.items.push(value),
+        }
+    }
+
+    /// Marks the sequence to remain an array even if it holds a single item
+    pub fn keep_array(&mut self) {
+        self.keep_array = true;
+    }
+
+    /// Converts the items to JSON values, e.g. to be the inputs of the next
+    /// step of a path
+    pub fn to_values(&self) -> Result<Vec<Value>, EvaluationError> {
+        self.items.iter().cloned().map(Value::try_from).collect()
+    }
+
+    /// Converts the sequence into the result of an expression
+    pub fn collapse(mut self) -> Result<Option<JSONataValue>, EvaluationError> {
+        if !self.is_input_array {
+            if self.items.is_empty() {
+                return Ok(None);
+            }
+            if self.items.len() == 1 && !self.keep_array {
+                return Ok(self.items.pop());
+            }
+        }
+
+        let values = self.to_values()?;
+        Ok(Some(values.into()))
+    }
+}
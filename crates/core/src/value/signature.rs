@@ -0,0 +1,560 @@
+use std::fmt;
+
+use regex::Regex;
+use serde_json::Value;
+
+use crate::evaluate::EvaluationError;
+
+use super::JSONataValue;
+
+/// Parsed signature of a function, used to validate its arguments
+/// before it is called
+///
+/// A function signature is a string of the form `<params:return>`. `params` is a sequence
+/// of type symbols, each one representing an input argument's type. `return` is a single
+/// type symbol representing the return value type.
+///
+/// Type symbols work as follows:
+///
+/// Simple types:
+///
+/// * `b` - Boolean
+/// * `n` - number
+/// * `s` - string
+/// * `l` - null
+///
+/// Complex types:
+///
+/// * `a` - array, a value of another JSON type is wrapped in an array. Unlike jsonata-js,
+///   a function is not accepted because an array only holds JSON values.
+/// * `o` - object
+/// * `f` - function
+///
+/// Union types:
+///
+/// * `(sao)` - string, array or object
+/// * `(o)` - same as `o`
+/// * `u` - equivalent to `(bnsl)` i.e. Boolean, number, string or null
+/// * `j` - any JSON type. Equivalent to `(bnsloa)` i.e. Boolean, number, string, null,
+///   object or array, but not function
+/// * `x` - any type. Equivalent to `(bnsloaf)`
+///
+/// Parameterized types:
+///
+/// * `a<s>` - array of strings
+/// * `a<x>` - array of values of any type
+///
+/// Some examples of signatures of built-in JSONata functions:
+///
+/// * `$count` has signature `<a:n>`; it accepts an array and returns a number.
+/// * `$append` has signature `<aa:a>`; it accepts two arrays and returns an array.
+/// * `$sum` has signature `<a<n>:n>`; it accepts an array of numbers and returns a number.
+/// * `$reduce` has signature `<fa<j>:j>`; it accepts a reducer function `f` and an `a<j>`
+///   (array of JSON objects) and returns a JSON object.
+///
+/// Each type symbol may also have options applied.
+///
+/// * `+` - one or more arguments of this type, e.g. `$zip` has signature `<a+>`; it accepts
+///   one array, or two arrays, or three arrays, or...
+/// * `?` - optional argument, e.g. `$join` has signature `<a<s>s?:s>`; it accepts an array
+///   of strings and an optional joiner string which defaults to the empty string.
+/// * `-` - if this argument is missing, use the context value ("focus"), e.g. `$length` has
+///   signature `<s-:n>`; it can be called as `$length(OrderID)` (one argument) but
+///   equivalently as `OrderID.$length()`.
+///
+/// The return type is not checked.
+#[derive(Debug, Clone)]
+pub(crate) struct Signature {
+    /// The signature as it was written, e.g. `<s-n?:s>`
+    source: String,
+    params: Vec<Param>,
+    regex: Regex,
+}
+
+/// A parameter of a signature
+#[derive(Debug, Clone, PartialEq)]
+struct Param {
+    /// Type symbol, or the symbols of a union type
+    symbols: String,
+
+    /// Type symbol of the items of a parameterized type, e.g. `s` for `a<s>`
+    subtype: Option<String>,
+
+    option: ParamOption,
+
+    /// Whether the context value is used when the argument is missing
+    context: bool,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum ParamOption {
+    One,
+    Optional,
+    OneOrMore,
+}
+
+impl Param {
+    fn new(symbols: &str) -> Self {
+        Self {
+            symbols: symbols.into(),
+            subtype: None,
+            option: ParamOption::One,
+            context: false,
+        }
+    }
+
+    fn is_array(&self) -> bool {
+        self.symbols == "a"
+    }
+
+    /// Pattern matching the symbol of a single argument of this type, where `m`
+    /// represents a missing (undefined) argument
+    fn class(&self) -> String {
+        match self.symbols.as_str() {
+            "f" => "f".into(),
+            "a" => "[asnblom]".into(),
+            "x" => "[asnblfom]".into(),
+            "j" => "[asnblom]".into(),
+            "u" => "[snblm]".into(),
+            symbols => format!("[{}m]", symbols),
+        }
+    }
+
+    fn pattern(&self) -> String {
+        let class = self.class();
+        match (self.option, self.context) {
+            (ParamOption::OneOrMore, _) => format!("{}+", class),
+            (ParamOption::Optional, _) | (_, true) => format!("{}?", class),
+            (ParamOption::One, false) => class,
+        }
+    }
+
+    /// Whether a value with the type symbol `symbol` is accepted as an
+    /// argument of this type
+    fn accepts(&self, symbol: char) -> bool {
+        self.class().contains(symbol)
+    }
+}
+
+impl Signature {
+    /// Parses a signature such as `<s-n?:s>`
+    pub fn parse(signature: &str) -> Result<Self, EvaluationError> {
+        let invalid = || EvaluationError::SyntaxError(signature.into());
+        if !signature.is_ascii() {
+            return Err(invalid());
+        }
+
+        let inner = signature
+            .strip_prefix('<')
+            .and_then(|inner| inner.strip_suffix('>'))
+            .ok_or_else(invalid)?;
+
+        let mut params: Vec<Param> = Vec::new();
+        let mut chars = inner.char_indices();
+        while let Some((position, symbol)) = chars.next() {
+            match symbol {
+                // the return type is not checked
+                ':' => break,
+                'b' | 'n' | 's' | 'l' | 'o' | 'a' | 'f' | 'j' | 'x' | 'u' => {
+                    params.push(Param::new(&symbol.to_string()))
+                }
+                '(' => {
+                    let end = Signature::closing_bracket(inner, position, '(', ')')
+                        .ok_or_else(invalid)?;
+                    let choice = &inner[position + 1..end];
+                    if choice.contains('<') {
+                        return Err(EvaluationError::ParameterizedChoiceGroup);
+                    }
+                    if choice.is_empty() || !choice.chars().all(|c| "bnslaofjxu".contains(c)) {
+                        return Err(invalid());
+                    }
+                    params.push(Param::new(choice));
+                    chars.nth(end - position - 1);
+                }
+                '<' => {
+                    let param = params
+                        .last_mut()
+                        .filter(|param| param.symbols == "a" || param.symbols == "f")
+                        .ok_or(EvaluationError::TypeParameterNotAllowed)?;
+                    let end = Signature::closing_bracket(inner, position, '<', '>')
+                        .ok_or_else(invalid)?;
+                    param.subtype = Some(inner[position + 1..end].into());
+                    chars.nth(end - position - 1);
+                }
+                '?' | '+' | '-' => {
+                    let param = params.last_mut().ok_or_else(invalid)?;
+                    match symbol {
+                        '?' => param.option = ParamOption::Optional,
+                        '+' => param.option = ParamOption::OneOrMore,
+                        _ => param.context = true,
+                    }
+                }
+                _ => return Err(invalid()),
+            }
+        }
+
+        let pattern: String = params
+            .iter()
+            .map(|param| format!("({})", param.pattern()))
+            .collect();
+        let regex = Regex::new(&format!("^{}$", pattern)).map_err(|_| invalid())?;
+
+        Ok(Self {
+            source: signature.into(),
+            params,
+            regex,
+        })
+    }
+
+    /// Number of leading parameters which are not optional, like the `length`
+    /// of a JavaScript function, which stops at the first parameter with a
+    /// default value
+    pub fn arity(&self) -> usize {
+        self.params
+            .iter()
+            .take_while(|param| param.option != ParamOption::Optional)
+            .count()
+    }
+
+    /// Finds the position of the bracket closing the one at `start`
+    fn closing_bracket(s: &str, start: usize, open: char, close: char) -> Option<usize> {
+        let mut depth = 0;
+        for (position, c) in s
+            .char_indices()
+            .skip_while(|(position, _)| *position < start)
+        {
+            if c == open {
+                depth += 1;
+            } else if c == close {
+                depth -= 1;
+                if depth == 0 {
+                    return Some(position);
+                }
+            }
+        }
+        None
+    }
+
+    /// Validates the arguments of a call to the function `name`, returning
+    /// the arguments that the function should be called with.
+    ///
+    /// A missing argument is replaced by the context value if the parameter
+    /// allows it, and a value passed for an array parameter is wrapped in an
+    /// array if it is not one already.
+    pub fn validate(
+        &self,
+        name: &str,
+        args: &[Option<JSONataValue>],
+        context: Option<&Value>,
+    ) -> Result<Vec<Option<JSONataValue>>, EvaluationError> {
+        let supplied: String = args.iter().map(Signature::symbol).collect();
+        let captures = match self.regex.captures(&supplied) {
+            Some(captures) => captures,
+            None => return Err(self.mismatch(name, &supplied)),
+        };
+
+        let mut validated = Vec::with_capacity(args.len());
+        let mut index = 0;
+        for (param_index, param) in self.params.iter().enumerate() {
+            let matched = captures
+                .get(param_index + 1)
+                .map_or("", |matched| matched.as_str());
+
+            if matched.is_empty() {
+                if param.context {
+                    let context = context.cloned().map(JSONataValue::from);
+                    if !param.accepts(Signature::symbol(&context)) {
+                        return Err(EvaluationError::FunctionContextMismatch(
+                            index + 1,
+                            name.into(),
+                        ));
+                    }
+                    validated.push(context);
+                } else {
+                    validated.push(None);
+                }
+                continue;
+            }
+
+            for symbol in matched.chars() {
+                let arg = args[index].clone();
+                if param.is_array() {
+                    validated.push(Signature::validate_array(param, name, index, symbol, arg)?);
+                } else {
+                    validated.push(arg);
+                }
+                index += 1;
+            }
+        }
+
+        Ok(validated)
+    }
+
+    /// Checks the items of an array argument against the subtype of the
+    /// parameter, wrapping a single value in an array
+    fn validate_array(
+        param: &Param,
+        name: &str,
+        index: usize,
+        symbol: char,
+        arg: Option<JSONataValue>,
+    ) -> Result<Option<JSONataValue>, EvaluationError> {
+        let arg = match arg {
+            Some(arg) => arg,
+            None => return Ok(None),
+        };
+
+        if let Some(subtype) = &param.subtype {
+            let item_param = Param::new(subtype);
+            let items_ok = match &arg {
+                JSONataValue::Value(Value::Array(items)) => items
+                    .iter()
+                    .all(|item| item_param.accepts(Signature::value_symbol(item))),
+                _ => item_param.accepts(symbol),
+            };
+
+            if !items_ok {
+                return Err(EvaluationError::FunctionArrayMemberMismatch(
+                    index + 1,
+                    name.into(),
+                    Signature::plural(subtype).into(),
+                ));
+            }
+        }
+
+        match arg {
+            JSONataValue::Value(Value::Array(_)) => Ok(Some(arg)),
+            JSONataValue::Value(value) => Ok(Some(Value::Array(vec![value]).into())),
+            JSONataValue::Function(_) => unreachable!("functions do not match an array parameter"),
+        }
+    }
+
+    /// Finds the first argument which does not match the signature
+    fn mismatch(&self, name: &str, supplied: &str) -> EvaluationError {
+        let mut pattern = String::from("^");
+        let mut good_to = 0;
+        for param in &self.params {
+            pattern.push_str(&param.pattern());
+            match Regex::new(&pattern)
+                .ok()
+                .and_then(|regex| regex.find(supplied))
+            {
+                Some(matched) => good_to = matched.end(),
+                None => break,
+            }
+        }
+
+        EvaluationError::FunctionSignatureMismatch(good_to + 1, name.into())
+    }
+
+    fn symbol(arg: &Option<JSONataValue>) -> char {
+        match arg {
+            None => 'm',
+            Some(JSONataValue::Function(_)) => 'f',
+            Some(JSONataValue::Value(value)) => Signature::value_symbol(value),
+        }
+    }
+
+    fn value_symbol(value: &Value) -> char {
+        match value {
+            Value::Null => 'l',
+            Value::Bool(_) => 'b',
+            Value::Number(_) => 'n',
+            Value::String(_) => 's',
+            Value::Array(_) => 'a',
+            Value::Object(_) => 'o',
+        }
+    }
+
+    fn plural(subtype: &str) -> &str {
+        match subtype {
+            "a" => "arrays",
+            "b" => "booleans",
+            "f" => "functions",
+            "n" => "numbers",
+            "o" => "objects",
+            "s" => "strings",
+            subtype => subtype,
+        }
+    }
+}
+
+impl fmt::Display for Signature {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(&self.source)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::*;
+
+    fn args(values: Vec<Option<Value>>) -> Vec<Option<JSONataValue>> {
+        values
+            .into_iter()
+            .map(|value| value.map(JSONataValue::from))
+            .collect()
+    }
+
+    fn validate(
+        signature: &str,
+        values: Vec<Option<Value>>,
+        context: Option<Value>,
+    ) -> Result<Vec<Option<JSONataValue>>, EvaluationError> {
+        Signature::parse(signature)
+            .unwrap()
+            .validate("f", &args(values), context.as_ref())
+    }
+
+    #[test]
+    fn parse_params() {
+        let signature = Signature::parse("<a<s>s?(sao)-n+:s>").unwrap();
+        assert_eq!(
+            signature.params,
+            vec![
+                Param {
+                    subtype: Some("s".into()),
+                    ..Param::new("a")
+                },
+                Param {
+                    option: ParamOption::Optional,
+                    ..Param::new("s")
+                },
+                Param {
+                    context: true,
+                    ..Param::new("sao")
+                },
+                Param {
+                    option: ParamOption::OneOrMore,
+                    ..Param::new("n")
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn parse_invalid_signatures() {
+        assert_eq!(
+            Signature::parse("<n<s>:n>").unwrap_err(),
+            EvaluationError::TypeParameterNotAllowed
+        );
+        assert_eq!(
+            Signature::parse("<(sa<n>):n>").unwrap_err(),
+            EvaluationError::ParameterizedChoiceGroup
+        );
+        assert_eq!(
+            Signature::parse("<z:n>").unwrap_err(),
+            EvaluationError::SyntaxError("<z:n>".into())
+        );
+        assert_eq!(
+            Signature::parse("n:n").unwrap_err(),
+            EvaluationError::SyntaxError("n:n".into())
+        );
+    }
+
+    #[test]
+    fn arity_counts_leading_required_params() {
+        let arity = |signature| Signature::parse(signature).unwrap().arity();
+        assert_eq!(arity("<nn:n>"), 2);
+        assert_eq!(arity("<x-b?:s>"), 1);
+        assert_eq!(arity("<s?n:s>"), 0);
+        assert_eq!(arity("<a+>"), 1);
+    }
+
+    #[test]
+    fn validate_simple_types() {
+        assert_eq!(
+            validate("<ns?:n>", vec![Some(json!(1))], None),
+            Ok(args(vec![Some(json!(1)), None]))
+        );
+        assert_eq!(
+            validate("<ns?:n>", vec![Some(json!(1)), Some(json!("a"))], None),
+            Ok(args(vec![Some(json!(1)), Some(json!("a"))]))
+        );
+        assert_eq!(
+            validate("<ns?:n>", vec![Some(json!("a"))], None),
+            Err(EvaluationError::FunctionSignatureMismatch(1, "f".into()))
+        );
+        assert_eq!(
+            validate("<ns?:n>", vec![Some(json!(1)), Some(json!(2))], None),
+            Err(EvaluationError::FunctionSignatureMismatch(2, "f".into()))
+        );
+        assert_eq!(
+            validate("<n:n>", vec![Some(json!(1)), Some(json!(2))], None),
+            Err(EvaluationError::FunctionSignatureMismatch(2, "f".into()))
+        );
+    }
+
+    #[test]
+    fn validate_undefined_argument() {
+        assert_eq!(validate("<n:n>", vec![None], None), Ok(vec![None]));
+    }
+
+    #[test]
+    fn validate_context_argument() {
+        assert_eq!(
+            validate("<s-:n>", vec![], Some(json!("hello"))),
+            Ok(args(vec![Some(json!("hello"))]))
+        );
+        assert_eq!(
+            validate("<s-:n>", vec![Some(json!("a"))], Some(json!("hello"))),
+            Ok(args(vec![Some(json!("a"))]))
+        );
+        assert_eq!(
+            validate("<s-:n>", vec![], Some(json!(5))),
+            Err(EvaluationError::FunctionContextMismatch(1, "f".into()))
+        );
+    }
+
+    #[test]
+    fn validate_arrays() {
+        // a single value is wrapped in an array
+        assert_eq!(
+            validate("<a:n>", vec![Some(json!(1))], None),
+            Ok(args(vec![Some(json!([1]))]))
+        );
+        assert_eq!(
+            validate("<a<n>:n>", vec![Some(json!([1, 2.5]))], None),
+            Ok(args(vec![Some(json!([1, 2.5]))]))
+        );
+        assert_eq!(
+            validate("<a<n>:n>", vec![Some(json!([1, "2"]))], None),
+            Err(EvaluationError::FunctionArrayMemberMismatch(
+                1,
+                "f".into(),
+                "numbers".into()
+            ))
+        );
+        assert_eq!(
+            validate("<a<n>:n>", vec![Some(json!("2"))], None),
+            Err(EvaluationError::FunctionArrayMemberMismatch(
+                1,
+                "f".into(),
+                "numbers".into()
+            ))
+        );
+
+        // an array cannot hold a function, so it is not wrapped
+        let func = JSONataValue::from_func(|_| Ok(None), "g");
+        assert_eq!(
+            Signature::parse("<a:n>")
+                .unwrap()
+                .validate("f", &[Some(func)], None),
+            Err(EvaluationError::FunctionSignatureMismatch(1, "f".into()))
+        );
+    }
+
+    #[test]
+    fn validate_one_or_more() {
+        assert_eq!(
+            validate("<a+>", vec![Some(json!([1])), Some(json!(2))], None),
+            Ok(args(vec![Some(json!([1])), Some(json!([2]))]))
+        );
+        assert_eq!(
+            validate("<a+>", vec![], None),
+            Err(EvaluationError::FunctionSignatureMismatch(1, "f".into()))
+        );
+    }
+}
@@ -0,0 +1,227 @@
+use serde_json::Value;
+
+use crate::evaluate::EvaluationError;
+
+/// Serializes a value to JSON the way `JSON.stringify` does in JavaScript,
+/// which JSONata uses to cast values to strings
+///
+/// Numbers are rounded to 15 significant digits so that the error of floating
+/// point arithmetic is hidden, e.g. `0.1 + 0.2` is written as `0.3`, and are
+/// then written following the ECMAScript rules for converting a number to a
+/// string. As in JavaScript this includes integers, so `12345678901234567` is
+/// written as `12345678901234600`.
+///
+/// If `prettify` is true, each array item and object field is written on its
+/// own line and indented by two spaces per level of nesting.
+pub(crate) fn stringify(value: &Value, prettify: bool) -> Result<String, EvaluationError> {
+    let mut out = String::new();
+    write_value(&mut out, value, prettify, 0)?;
+    Ok(out)
+}
+
+fn write_value(
+    out: &mut String,
+    value: &Value,
+    prettify: bool,
+    depth: usize,
+) -> Result<(), EvaluationError> {
+    match value {
+        Value::Null | Value::Bool(_) | Value::String(_) => out.push_str(&value.to_string()),
+        Value::Number(n) => match n.as_f64() {
+            Some(f) => out.push_str(&number_to_string(round_to_precision(f))?),
+            None => out.push_str(&n.to_string()),
+        },
+        Value::Array(items) => {
+            if items.is_empty() {
+                out.push_str("[]");
+                return Ok(());
+            }
+            out.push('[');
+            for (index, item) in items.iter().enumerate() {
+                if index > 0 {
+                    out.push(',');
+                }
+                write_indent(out, prettify, depth + 1);
+                write_value(out, item, prettify, depth + 1)?;
+            }
+            write_indent(out, prettify, depth);
+            out.push(']');
+        }
+        Value::Object(fields) => {
+            if fields.is_empty() {
+                out.push_str("{}");
+                return Ok(());
+            }
+            out.push('{');
+            for (index, (key, field)) in fields.iter().enumerate() {
+                if index > 0 {
+                    out.push(',');
+                }
+                write_indent(out, prettify, depth + 1);
+                out.push_str(&Value::String(key.clone()).to_string());
+                out.push_str(if prettify { ": " } else { ":" });
+                write_value(out, field, prettify, depth + 1)?;
+            }
+            write_indent(out, prettify, depth);
+            out.push('}');
+        }
+    }
+    Ok(())
+}
+
+fn write_indent(out: &mut String, prettify: bool, depth: usize) {
+    if prettify {
+        out.push('\n');
+        out.push_str(&"  ".repeat(depth));
+    }
+}
+
+/// Rounds to 15 significant digits, as `Number(n.toPrecision(15))` does
+///
+/// A magnitude exactly halfway between two candidates is rounded up, which
+/// needs the exact decimal digits of `f` rather than the round-half-to-even of
+/// formatting with a precision.
+fn round_to_precision(f: f64) -> f64 {
+    if !f.is_finite() {
+        return f;
+    }
+
+    // a double has at most 767 significant decimal digits
+    let exact = format!("{:.766e}", f.abs());
+    let (mantissa, exponent) = exact.split_once('e').unwrap(); // always present in {:e}
+    let exponent = exponent.parse::<i32>().unwrap();
+    let digits = mantissa.replace('.', "");
+
+    let mut kept = digits[..15].parse::<u64>().unwrap();
+    if digits.as_bytes()[15] >= b'5' {
+        kept += 1;
+    }
+    let rounded = format!("{}e{}", kept, exponent - 14).parse().unwrap_or(f);
+    f.signum() * rounded
+}
+
+/// Converts a number to a string following the ECMAScript `Number::toString`
+/// rules, e.g. `1e21` is written as `"1e+21"` and `0.000001` as `"0.000001"`
+///
+/// NaN and infinity cannot be represented in JSON, so they are an error.
+pub(crate) fn number_to_string(f: f64) -> Result<String, EvaluationError> {
+    if !f.is_finite() {
+        return Err(EvaluationError::StringifyNonFinite);
+    }
+    if f == 0.0 {
+        // includes negative zero
+        return Ok("0".into());
+    }
+
+    // the shortest digits which round-trip, with f = 0.digits * 10^n
+    let repr = format!("{:e}", f.abs());
+    let (mantissa, exponent) = repr.split_once('e').unwrap(); // always present in {:e}
+    let digits = mantissa.replace('.', "");
+    let k = digits.len() as i32;
+    let n = exponent.parse::<i32>().unwrap() + 1;
+
+    let body = if k <= n && n <= 21 {
+        format!("{}{}", digits, "0".repeat((n - k) as usize))
+    } else if 0 < n && n <= 21 {
+        let (integer, fraction) = digits.split_at(n as usize);
+        format!("{}.{}", integer, fraction)
+    } else if -6 < n && n <= 0 {
+        format!("0.{}{}", "0".repeat(-n as usize), digits)
+    } else {
+        let sign = if n > 0 { '+' } else { '-' };
+        let (first, rest) = digits.split_at(1);
+        let point = if rest.is_empty() { "" } else { "." };
+        format!("{}{}{}e{}{}", first, point, rest, sign, (n - 1).abs())
+    };
+
+    Ok(if f < 0.0 { format!("-{}", body) } else { body })
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::*;
+
+    #[test]
+    fn numbers() {
+        let cases = vec![
+            (0.0, "0"),
+            (-0.0, "0"),
+            (1.0, "1"),
+            (-1.5, "-1.5"),
+            (123.456, "123.456"),
+            (1e20, "100000000000000000000"),
+            (1e21, "1e+21"),
+            (2.5e25, "2.5e+25"),
+            (0.000001, "0.000001"),
+            (1e-7, "1e-7"),
+            (-1.5e-10, "-1.5e-10"),
+        ];
+        for (number, expected) in cases {
+            assert_eq!(number_to_string(number), Ok(expected.to_string()));
+        }
+
+        assert_eq!(
+            number_to_string(f64::NAN),
+            Err(EvaluationError::StringifyNonFinite)
+        );
+        assert_eq!(
+            number_to_string(f64::INFINITY),
+            Err(EvaluationError::StringifyNonFinite)
+        );
+    }
+
+    #[test]
+    fn floats_are_rounded_to_15_digits() {
+        assert_eq!(stringify(&json!(0.1 + 0.2), false), Ok("0.3".into()));
+        assert_eq!(
+            stringify(&json!(1.0 / 3.0), false),
+            Ok("0.333333333333333".into())
+        );
+        // 2^-22, exactly halfway between two candidates, is rounded up
+        assert_eq!(
+            stringify(&json!(2.384185791015625e-7), false),
+            Ok("2.38418579101563e-7".into())
+        );
+    }
+
+    #[test]
+    fn integers_are_rounded_to_15_digits() {
+        assert_eq!(stringify(&json!(123), false), Ok("123".into()));
+        assert_eq!(
+            stringify(&json!(12345678901234567_i64), false),
+            Ok("12345678901234600".into())
+        );
+        assert_eq!(
+            stringify(&json!(1000000000000005_i64), false),
+            Ok("1000000000000010".into())
+        );
+        assert_eq!(
+            stringify(&json!(-1000000000000005_i64), false),
+            Ok("-1000000000000010".into())
+        );
+        assert_eq!(
+            stringify(&json!(9007199254740993_u64), false),
+            Ok("9007199254740990".into())
+        );
+    }
+
+    #[test]
+    fn compact() {
+        let value = json!({ "a": [1, "two", null, true], "b": {}, "c": [] });
+        assert_eq!(
+            stringify(&value, false),
+            Ok(r#"{"a":[1,"two",null,true],"b":{},"c":[]}"#.into())
+        );
+        assert_eq!(stringify(&json!("a\"b\n"), false), Ok(r#""a\"b\n""#.into()));
+    }
+
+    #[test]
+    fn prettified() {
+        let value = json!({ "a": [1, { "b": 2.5 }], "c": {} });
+        let expected =
+            "{\n  \"a\": [\n    1,\n    {\n      \"b\": 2.5\n    }\n  ],\n  \"c\": {}\n}";
+        assert_eq!(stringify(&value, true), Ok(expected.into()));
+    }
+}
@@ -10,6 +10,7 @@ fn eq() {
         (json!("yes"), json!("yes")),
         (json!(1000), json!(1000)),
         (json!(10.0), json!(10)),
+        (json!(-0.0), json!(0)),
         (json!([1, 2]), json!([1.0, 2.0])),
         (json!([1, [2.0]]), json!([1.0, [2]])),
         (
@@ -18,6 +19,7 @@ fn eq() {
         ),
     ];
     for (a, b) in cases {
+        assert_eq!(canonical_hash(&a), canonical_hash(&b), "{} {}", a, b);
         assert!(JSONataValue::Value(a) == JSONataValue::Value(b));
     }
 }
@@ -27,8 +29,8 @@ fn add() {
     // (lhs, rhs, expected)
     let ok_cases: Vec<(Value, Value, Value)> = vec![
         (json!(1), json!(2), json!(3)),
-        (json!(1.5), json!(2.5), json!(4.0)),
-        (json!(-1.5), json!(2.5), json!(1.0)),
+        (json!(1.5), json!(2.5), json!(4)),
+        (json!(-1.5), json!(2.5), json!(1)),
         (json!(1), json!(2.5), json!(3.5)),
         (json!(100), json!(-250), json!(-150)),
     ];
@@ -39,20 +41,34 @@ fn add() {
         )
     }
 
-    // (lhs, rhs)
-    let err_cases: Vec<(Value, Value)> = vec![
-        (json!("hello"), json!("world")),
-        (json!(1), json!("1")),
-        (json!("1"), json!(1)),
+    // (lhs, rhs, side which is not a number)
+    let err_cases: Vec<(Value, Value, Side)> = vec![
+        (json!("hello"), json!("world"), Side::Left),
+        (json!(1), json!("1"), Side::Right),
+        (json!("1"), json!(1), Side::Left),
     ];
-    for (lhs, rhs) in err_cases {
+    for (lhs, rhs, side) in err_cases {
         assert_eq!(
             JSONataValue::Value(lhs).try_add(JSONataValue::Value(rhs)),
             Err(EvaluationError::DyadicMustBeNumber(
-                ArithmeticOpType::Add.into()
+                ArithmeticOpType::Add,
+                side
             ))
         )
     }
 }
 
 // TODO: tests for sub, mul, div, rem
+
+#[test]
+fn format_functions() {
+    let func = crate::tests::builtin("pad").unwrap();
+    assert_eq!(func.to_string(), "$pad<s-ns?:s>");
+    assert_eq!(
+        format!("{:?}", func),
+        r#"Function(JSONataFunction { ident: "pad", signature: Some("<s-ns?:s>") })"#
+    );
+
+    let func = JSONataValue::from_func(|_| Ok(None), "host");
+    assert_eq!(func.to_string(), "$host");
+}
@@ -2,7 +2,7 @@ use serde_json::Value;
 
 use crate::evaluate::EvaluationError;
 
-pub(super) trait TryNumericOps<Rhs = Self> {
+pub(crate) trait TryNumericOps<Rhs = Self> {
     /// Attempt addition between two values
     ///
     /// Ok if the operands are numeric, Err otherwise
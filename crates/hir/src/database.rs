@@ -1,21 +1,50 @@
-use la_arena::Arena;
+use la_arena::{Arena, ArenaMap};
 use syntax::SyntaxKind;
+use text_size::TextRange;
 
-use crate::{BinaryOp, Expr, UnaryOp};
+use crate::{BinaryOp, Expr, ExprIdx, UnaryOp};
 
-#[derive(Debug, Default, PartialEq)]
+#[derive(Debug, Default)]
 pub struct Database {
     exprs: Arena<Expr>,
+    ranges: ArenaMap<ExprIdx, TextRange>,
 }
 
 impl Database {
+    /// Returns the lowered expression stored at `idx`
+    pub fn expr(&self, idx: ExprIdx) -> &Expr {
+        &self.exprs[idx]
+    }
+
+    /// Returns the range in the source of the expression stored at `idx`,
+    /// if it was lowered from the syntax tree
+    pub fn range(&self, idx: ExprIdx) -> Option<TextRange> {
+        self.ranges.get(idx).copied()
+    }
+
+    /// Lowers `ast` and stores it, recording its source range
+    fn alloc_expr(&mut self, ast: Option<ast::Expr>) -> ExprIdx {
+        let range = ast.as_ref().map(ast::Expr::range);
+        let expr = self.lower_expr(ast);
+        let idx = self.exprs.alloc(expr);
+        if let Some(range) = range {
+            self.ranges.insert(idx, range);
+        }
+        idx
+    }
+
     pub(crate) fn lower_expr(&mut self, ast: Option<ast::Expr>) -> Expr {
         if let Some(ast) = ast {
             match ast {
                 ast::Expr::Binary(ast) => self.lower_binary(ast),
+                ast::Expr::Call(ast) => self.lower_call(ast),
                 ast::Expr::Literal(ast) => Expr::Literal { n: ast.parse() },
-                ast::Expr::Paren(ast) => self.lower_expr(ast.expr()),
-                ast::Expr::PathIdent(ast) => Expr::PathIdent { name: ast.name() },
+                ast::Expr::Object(ast) => self.lower_object(ast),
+                ast::Expr::Paren(ast) => self.lower_paren(ast),
+                ast::Expr::PathIdent(ast) => Expr::PathIdent {
+                    name: ast.name(),
+                    keep_array: ast.keep_array(),
+                },
                 ast::Expr::Unary(ast) => self.lower_unary(ast),
                 ast::Expr::VariableRef(ast) => Expr::VariableRef { var: ast.name() },
                 ast::Expr::VariableDef(ast) => self.lower_variable_def(ast),
@@ -35,13 +64,42 @@ impl Database {
             _ => unreachable!(),
         };
 
-        let lhs = self.lower_expr(ast.lhs());
-        let rhs = self.lower_expr(ast.rhs());
-
         Expr::Binary {
             op,
-            lhs: self.exprs.alloc(lhs),
-            rhs: self.exprs.alloc(rhs),
+            lhs: self.alloc_expr(ast.lhs()),
+            rhs: self.alloc_expr(ast.rhs()),
+        }
+    }
+
+    fn lower_call(&mut self, ast: ast::FunctionCall) -> Expr {
+        Expr::Call {
+            func: ast.name(),
+            args: ast.args().map(|arg| self.alloc_expr(Some(arg))).collect(),
+        }
+    }
+
+    fn lower_object(&mut self, ast: ast::ObjectExpr) -> Expr {
+        Expr::Object {
+            pairs: ast
+                .pairs()
+                .map(|pair| (self.alloc_expr(pair.key()), self.alloc_expr(pair.value())))
+                .collect(),
+        }
+    }
+
+    /// A single expression in parentheses only affects precedence, so it is
+    /// lowered to the expression itself, while a block of several is kept
+    fn lower_paren(&mut self, ast: ast::ParenExpr) -> Expr {
+        let mut exprs: Vec<ast::Expr> = ast.exprs().collect();
+        if exprs.len() <= 1 {
+            return self.lower_expr(exprs.pop());
+        }
+
+        Expr::Paren {
+            exprs: exprs
+                .into_iter()
+                .map(|expr| self.alloc_expr(Some(expr)))
+                .collect(),
         }
     }
 
@@ -51,19 +109,16 @@ impl Database {
             _ => unreachable!(),
         };
 
-        let expr = self.lower_expr(ast.expr());
-
         Expr::Unary {
             op,
-            expr: self.exprs.alloc(expr),
+            expr: self.alloc_expr(ast.expr()),
         }
     }
 
     fn lower_variable_def(&mut self, ast: ast::VariableDef) -> Expr {
-        let expr = self.lower_expr(ast.value());
         Expr::VariableDef {
             name: ast.name().unwrap().text().into(),
-            value: self.exprs.alloc(expr),
+            value: self.alloc_expr(ast.value()),
         }
     }
 }
@@ -76,19 +131,19 @@ mod tests {
         ast::Root::cast(parser::parse(input).syntax()).unwrap()
     }
 
-    fn check(input: &str, expected_hir: Expr, expected_database: Database) {
+    fn check(input: &str, expected_hir: Expr, expected_exprs: Arena<Expr>) {
         let root = parse(input);
         let first_expr = root.expr();
         let mut database = Database::default();
         let hir = database.lower_expr(first_expr);
 
         assert_eq!(hir, expected_hir);
-        assert_eq!(database, expected_database);
+        assert_eq!(database.exprs, expected_exprs);
     }
 
     #[test]
     fn lower_literal() {
-        check("100", Expr::Literal { n: 100 }, Database::default());
+        check("100", Expr::Literal { n: 100 }, Arena::new());
     }
 
     #[test]
@@ -104,7 +159,7 @@ mod tests {
                 lhs,
                 rhs,
             },
-            Database { exprs },
+            exprs,
         );
     }
 
@@ -119,7 +174,7 @@ mod tests {
                 expr: twelve,
                 op: UnaryOp::Neg,
             },
-            Database { exprs },
+            exprs,
         );
     }
 
@@ -128,7 +183,7 @@ mod tests {
         check(
             "$foo",
             Expr::VariableRef { var: "$foo".into() },
-            Database::default(),
+            Arena::new(),
         );
     }
 
@@ -143,18 +198,43 @@ mod tests {
                 name: "$foo".into(),
                 value,
             },
-            Database { exprs },
+            exprs,
+        );
+    }
+
+    #[test]
+    fn lower_block() {
+        let mut exprs = Arena::new();
+        let value = exprs.alloc(Expr::Literal { n: 5 });
+        let def = exprs.alloc(Expr::VariableDef {
+            name: "$x".into(),
+            value,
+        });
+        let var = exprs.alloc(Expr::VariableRef { var: "$x".into() });
+
+        check(
+            "($x := 5; $x)",
+            Expr::Paren {
+                exprs: vec![def, var],
+            },
+            exprs,
         );
     }
 
+    #[test]
+    fn lower_parenthesized_expression() {
+        check("(100)", Expr::Literal { n: 100 }, Arena::new());
+    }
+
     #[test]
     fn lower_path_ident() {
         check(
             "Account",
             Expr::PathIdent {
                 name: "Account".into(),
+                keep_array: false,
             },
-            Database::default(),
+            Arena::new(),
         );
     }
 
@@ -163,9 +243,11 @@ mod tests {
         let mut exprs = Arena::new();
         let lhs = exprs.alloc(Expr::PathIdent {
             name: "Account".into(),
+            keep_array: false,
         });
         let rhs = exprs.alloc(Expr::PathIdent {
             name: "History".into(),
+            keep_array: false,
         });
 
         check(
@@ -175,7 +257,7 @@ mod tests {
                 lhs,
                 rhs,
             },
-            Database { exprs },
+            exprs,
         )
     }
 
@@ -185,10 +267,12 @@ mod tests {
 
         let account_expr = exprs.alloc(Expr::PathIdent {
             name: "Account".into(),
+            keep_array: false,
         });
 
         let history_expr = exprs.alloc(Expr::PathIdent {
             name: "History".into(),
+            keep_array: false,
         });
 
         let lhs = exprs.alloc(Expr::Binary {
@@ -199,6 +283,7 @@ mod tests {
 
         let order_expr = exprs.alloc(Expr::PathIdent {
             name: "Orders".into(),
+            keep_array: false,
         });
 
         check(
@@ -208,7 +293,75 @@ mod tests {
                 lhs,
                 rhs: order_expr,
             },
-            Database { exprs },
+            exprs,
         )
     }
+
+    #[test]
+    fn record_ranges_without_trivia() {
+        let root = parse("2 * (3 + 4) ");
+        let mut database = Database::default();
+        let hir = database.lower_expr(root.expr());
+
+        let (lhs, rhs) = match hir {
+            Expr::Binary { lhs, rhs, .. } => (lhs, rhs),
+            _ => panic!("expected a binary expression"),
+        };
+        assert_eq!(
+            database.range(lhs),
+            Some(TextRange::new(0.into(), 1.into()))
+        );
+        assert_eq!(
+            database.range(rhs),
+            Some(TextRange::new(4.into(), 11.into()))
+        );
+    }
+
+    #[test]
+    fn lower_keep_array_path_ident() {
+        check(
+            "Order[]",
+            Expr::PathIdent {
+                name: "Order".into(),
+                keep_array: true,
+            },
+            Arena::new(),
+        );
+    }
+
+    #[test]
+    fn lower_function_call() {
+        let mut exprs = Arena::new();
+        let arg = exprs.alloc(Expr::PathIdent {
+            name: "Order".into(),
+            keep_array: false,
+        });
+
+        check(
+            "$count(Order)",
+            Expr::Call {
+                func: "$count".into(),
+                args: vec![arg],
+            },
+            exprs,
+        );
+    }
+
+    #[test]
+    fn lower_object_constructor() {
+        let mut exprs = Arena::new();
+        let key = exprs.alloc(Expr::PathIdent {
+            name: "Id".into(),
+            keep_array: false,
+        });
+        let value = exprs.alloc(Expr::Literal { n: 1 });
+
+        check(
+            "{Id: 1}",
+            Expr::Object {
+                pairs: vec![(key, value)],
+            },
+            exprs,
+        );
+    }
 }
@@ -3,7 +3,7 @@ pub use database::Database;
 
 use la_arena::Idx;
 
-type ExprIdx = Idx<Expr>;
+pub type ExprIdx = Idx<Expr>;
 
 #[derive(Debug, PartialEq)]
 pub enum Expr {
@@ -13,14 +13,22 @@ pub enum Expr {
         lhs: ExprIdx,
         rhs: ExprIdx,
     },
+    Call {
+        func: String,
+        args: Vec<ExprIdx>,
+    },
     Literal {
         n: u64,
     },
+    Object {
+        pairs: Vec<(ExprIdx, ExprIdx)>,
+    },
     Paren {
         exprs: Vec<ExprIdx>,
     },
     PathIdent {
         name: String,
+        keep_array: bool,
     },
     Unary {
         op: UnaryOp,
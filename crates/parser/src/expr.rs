@@ -59,6 +59,8 @@ fn lhs(p: &mut Parser) -> Option<CompletedMarker> {
         prefix_expr(p)
     } else if p.at(SyntaxKind::LParen) {
         paren_expr(p)
+    } else if p.at(SyntaxKind::LBrace) {
+        object_expr(p)
     } else {
         p.error();
         None
@@ -78,8 +80,9 @@ fn literal(p: &mut Parser) -> Option<CompletedMarker> {
     Some(m.complete(p, SyntaxKind::Literal))
 }
 
-/// Parses a variable definition or a variable reference
+/// Parses a variable definition, a function call or a variable reference
 /// VariableDef: `$foo := 5`
+/// FunctionCall: `$foo(5)`
 /// VariableRef: `$foo`
 fn variable(p: &mut Parser) -> Option<CompletedMarker> {
     assert!(p.at(SyntaxKind::VariableIdent));
@@ -94,6 +97,17 @@ fn variable(p: &mut Parser) -> Option<CompletedMarker> {
         expr(p);
 
         Some(m.complete(p, SyntaxKind::VariableDef))
+    } else if p.at(SyntaxKind::LParen) {
+        // function call
+        // todo: more than one argument needs a comma token from the lexer
+        p.bump();
+
+        if !p.at(SyntaxKind::RParen) {
+            expr_binding_power(p, 0);
+        }
+        p.expect(SyntaxKind::RParen);
+
+        Some(m.complete(p, SyntaxKind::FunctionCall))
     } else {
         // variable ref
         Some(m.complete(p, SyntaxKind::VariableRef))
@@ -110,10 +124,12 @@ fn path_expr(p: &mut Parser) -> Option<CompletedMarker> {
         // map expression
         // todo!()
     } else if p.at(SyntaxKind::LBracket) {
-        // filter
-        // } else if p.at(SyntaxKind::Caret) {
-        // order-by
-        // todo!()
+        // keep-array
+        // todo: filter predicates, e.g. `a[0]`, which are an error for now
+        keep_array(p);
+    // } else if p.at(SyntaxKind::Caret) {
+    //     order-by
+    //     todo!()
     } else if p.at(SyntaxKind::LBrace) {
         // reduce
         // todo!()
@@ -122,6 +138,41 @@ fn path_expr(p: &mut Parser) -> Option<CompletedMarker> {
     Some(m.complete(p, SyntaxKind::PathIdentExpr))
 }
 
+/// Parses the empty predicate which keeps the result of a path as an array,
+/// even when it holds a single value
+/// KeepArray: `Account.Order[]`
+fn keep_array(p: &mut Parser) -> Option<CompletedMarker> {
+    assert!(p.at(SyntaxKind::LBracket));
+
+    let m = p.start();
+    p.bump();
+    p.expect(SyntaxKind::RBracket);
+
+    Some(m.complete(p, SyntaxKind::KeepArray))
+}
+
+/// Parses an object constructor, the key of each pair is an expression
+/// which must evaluate to a string
+/// ObjectExpr: `{ Product: Price }`
+fn object_expr(p: &mut Parser) -> Option<CompletedMarker> {
+    assert!(p.at(SyntaxKind::LBrace));
+
+    let m = p.start();
+    p.bump();
+
+    // todo: more than one pair needs a comma token from the lexer
+    if !p.at(SyntaxKind::RBrace) {
+        let pair = p.start();
+        expr_binding_power(p, 0);
+        p.expect(SyntaxKind::Colon);
+        expr_binding_power(p, 0);
+        pair.complete(p, SyntaxKind::ObjectPair);
+    }
+    p.expect(SyntaxKind::RBrace);
+
+    Some(m.complete(p, SyntaxKind::ObjectExpr))
+}
+
 fn prefix_expr(p: &mut Parser) -> Option<CompletedMarker> {
     assert!(p.at(SyntaxKind::Minus));
 
@@ -138,12 +189,18 @@ fn prefix_expr(p: &mut Parser) -> Option<CompletedMarker> {
     Some(m.complete(p, SyntaxKind::PrefixExpr))
 }
 
+/// Parses an expression in parentheses, or a block of expressions separated by `;`
+/// ParenExpr: `(1 + 2)`, `($x := 5; $x * 2)`
 fn paren_expr(p: &mut Parser) -> Option<CompletedMarker> {
     assert!(p.at(SyntaxKind::LParen));
 
     let m = p.start();
     p.bump();
     expr_binding_power(p, 0);
+    while p.at(SyntaxKind::SemiColon) {
+        p.bump();
+        expr_binding_power(p, 0);
+    }
     p.expect(SyntaxKind::RParen);
 
     Some(m.complete(p, SyntaxKind::ParenExpr))
@@ -281,10 +338,33 @@ Root@0..2
     LParen@0..1 "("
     Literal@1..2
       Number@1..2 "2"
-error at 1..2: expected ‘+’, ‘-’, ‘*’, ‘/’, ‘.’, or ‘)’"#]],
+error at 1..2: expected ‘+’, ‘-’, ‘*’, ‘/’, ‘.’, ‘;’, or ‘)’"#]],
         )
     }
 
+    #[test]
+    fn parse_block() {
+        check(
+            "($x := 5; $x)",
+            expect![[r#"
+Root@0..13
+  ParenExpr@0..13
+    LParen@0..1 "("
+    VariableDef@1..8
+      VariableIdent@1..3 "$x"
+      Whitespace@3..4 " "
+      ColonEquals@4..6 ":="
+      Whitespace@6..7 " "
+      Literal@7..8
+        Number@7..8 "5"
+    SemiColon@8..9 ";"
+    Whitespace@9..10 " "
+    VariableRef@10..12
+      VariableIdent@10..12 "$x"
+    RParen@12..13 ")""#]],
+        );
+    }
+
     #[test]
     fn parse_one_level_path() {
         check(
@@ -329,4 +409,137 @@ Root@0..22
       PathIdent@16..22 "Orders""#]],
         )
     }
+
+    #[test]
+    fn parse_keep_array_path() {
+        check(
+            "Account.Order[]",
+            expect![[r#"
+Root@0..15
+  InfixExpr@0..15
+    PathIdentExpr@0..7
+      PathIdent@0..7 "Account"
+    Dot@7..8 "."
+    PathIdentExpr@8..15
+      PathIdent@8..13 "Order"
+      KeepArray@13..15
+        LBracket@13..14 "["
+        RBracket@14..15 "]""#]],
+        )
+    }
+
+    #[test]
+    fn map_has_higher_binding_power_than_arithmetic() {
+        check(
+            "2*a.b",
+            expect![[r#"
+Root@0..5
+  InfixExpr@0..5
+    Literal@0..1
+      Number@0..1 "2"
+    Star@1..2 "*"
+    InfixExpr@2..5
+      PathIdentExpr@2..3
+        PathIdent@2..3 "a"
+      Dot@3..4 "."
+      PathIdentExpr@4..5
+        PathIdent@4..5 "b""#]],
+        )
+    }
+
+    #[test]
+    fn parse_map_to_paren_expression() {
+        check(
+            "Order.(Price*Qty)",
+            expect![[r#"
+Root@0..17
+  InfixExpr@0..17
+    PathIdentExpr@0..5
+      PathIdent@0..5 "Order"
+    Dot@5..6 "."
+    ParenExpr@6..17
+      LParen@6..7 "("
+      InfixExpr@7..16
+        PathIdentExpr@7..12
+          PathIdent@7..12 "Price"
+        Star@12..13 "*"
+        PathIdentExpr@13..16
+          PathIdent@13..16 "Qty"
+      RParen@16..17 ")""#]],
+        )
+    }
+
+    #[test]
+    fn parse_map_to_function_call() {
+        check(
+            "Order.$count(Product)",
+            expect![[r#"
+Root@0..21
+  InfixExpr@0..21
+    PathIdentExpr@0..5
+      PathIdent@0..5 "Order"
+    Dot@5..6 "."
+    FunctionCall@6..21
+      VariableIdent@6..12 "$count"
+      LParen@12..13 "("
+      PathIdentExpr@13..20
+        PathIdent@13..20 "Product"
+      RParen@20..21 ")""#]],
+        )
+    }
+
+    #[test]
+    fn parse_function_call_without_arguments() {
+        check(
+            "$now()",
+            expect![[r#"
+Root@0..6
+  FunctionCall@0..6
+    VariableIdent@0..4 "$now"
+    LParen@4..5 "("
+    RParen@5..6 ")""#]],
+        )
+    }
+
+    #[test]
+    fn parse_map_to_object_constructor() {
+        check(
+            "Order.{Id: Price}",
+            expect![[r#"
+Root@0..17
+  InfixExpr@0..17
+    PathIdentExpr@0..5
+      PathIdent@0..5 "Order"
+    Dot@5..6 "."
+    ObjectExpr@6..17
+      LBrace@6..7 "{"
+      ObjectPair@7..16
+        PathIdentExpr@7..9
+          PathIdent@7..9 "Id"
+        Colon@9..10 ":"
+        Whitespace@10..11 " "
+        PathIdentExpr@11..16
+          PathIdent@11..16 "Price"
+      RBrace@16..17 "}""#]],
+        )
+    }
+
+    #[test]
+    fn parse_filter_predicate_is_an_error() {
+        check(
+            "a[0]",
+            expect![[r#"
+Root@0..4
+  PathIdentExpr@0..3
+    PathIdent@0..1 "a"
+    KeepArray@1..3
+      LBracket@1..2 "["
+      Error@2..3
+        Number@2..3 "0"
+  Error@3..4
+    RBracket@3..4 "]"
+error at 2..3: expected ‘]’, but found number
+error at 3..4: expected ‘+’, ‘-’, ‘*’, ‘/’, or ‘.’, but found ‘]’"#]],
+        )
+    }
 }
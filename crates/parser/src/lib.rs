@@ -4,7 +4,7 @@ mod parser;
 mod sink;
 mod source;
 
-use self::parser::{ParseError, Parser};
+use self::parser::Parser;
 use lexer::Lexer;
 use rowan::GreenNode;
 use source::Source;
@@ -12,6 +12,8 @@ use syntax::SyntaxNode;
 
 use self::sink::Sink;
 
+pub use self::parser::ParseError;
+
 pub fn parse(input: &str) -> Parse {
     let tokens: Vec<_> = Lexer::new(input).collect();
     let source = Source::new(&tokens);
@@ -46,6 +48,10 @@ impl Parse {
     pub fn syntax(&self) -> SyntaxNode {
         SyntaxNode::new_root(self.green_node.clone())
     }
+
+    pub fn errors(&self) -> &[ParseError] {
+        &self.errors
+    }
 }
 
 enum BinaryOp {
@@ -63,7 +69,7 @@ impl BinaryOp {
         match self {
             Self::Add | Self::Sub => (1, 2),
             Self::Mul | Self::Div => (3, 4),
-            Self::Map => (3, 4),
+            Self::Map => (7, 8),
         }
     }
 }
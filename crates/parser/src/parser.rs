@@ -5,7 +5,7 @@ use syntax::SyntaxKind;
 
 pub(crate) mod marker;
 mod parse_error;
-pub(crate) use parse_error::ParseError;
+pub use parse_error::ParseError;
 
 use crate::event::Event;
 use crate::expr::expr;
@@ -34,6 +34,19 @@ impl<'t, 'input> Parser<'t, 'input> {
     pub(crate) fn parse(mut self) -> Vec<Event> {
         let m = self.start();
         expr(&mut self);
+
+        // Tokens left over after the expression are kept in a single error
+        // node, so that the tree still covers all of the input.
+        if !self.at_end() {
+            self.report_error();
+
+            let error = self.start();
+            while !self.at_end() {
+                self.bump();
+            }
+            error.complete(&mut self, SyntaxKind::Error);
+        }
+
         m.complete(&mut self, SyntaxKind::Root);
 
         self.events
@@ -48,6 +61,17 @@ impl<'t, 'input> Parser<'t, 'input> {
     }
 
     pub(crate) fn error(&mut self) {
+        self.report_error();
+
+        if !self.at_set(&RECOVERY_SET) && !self.at_end() {
+            let m = self.start();
+            self.bump();
+            m.complete(self, SyntaxKind::Error);
+        }
+    }
+
+    /// Records an error at the current token without consuming it
+    fn report_error(&mut self) {
         let current_token = self.source.peek_token();
 
         let (found, range) = if let Some(Token { kind, range, .. }) = current_token {
@@ -62,12 +86,6 @@ impl<'t, 'input> Parser<'t, 'input> {
             found,
             range,
         }));
-
-        if !self.at_set(&RECOVERY_SET) && !self.at_end() {
-            let m = self.start();
-            self.bump();
-            m.complete(self, SyntaxKind::Error);
-        }
     }
 
     pub(crate) fn start(&mut self) -> Marker {
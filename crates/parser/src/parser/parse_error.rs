@@ -3,13 +3,20 @@ use std::fmt;
 use syntax::SyntaxKind;
 use text_size::TextRange;
 
-#[derive(Debug, PartialEq)]
-pub(crate) struct ParseError {
+#[derive(Debug, Clone, PartialEq)]
+pub struct ParseError {
     pub(super) expected: Vec<SyntaxKind>,
     pub(super) found: Option<SyntaxKind>,
     pub(super) range: TextRange,
 }
 
+impl ParseError {
+    /// Range of the token where the parser expected something else
+    pub fn range(&self) -> TextRange {
+        self.range
+    }
+}
+
 impl fmt::Display for ParseError {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         write!(
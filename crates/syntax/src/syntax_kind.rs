@@ -41,6 +41,10 @@ pub enum SyntaxKind {
     PrefixExpr,
     ParenExpr,
     PathIdentExpr,
+    KeepArray,
+    FunctionCall,
+    ObjectExpr,
+    ObjectPair,
     Root,
     Comment,
     Error,
@@ -102,6 +106,7 @@ impl fmt::Display for SyntaxKind {
         f.write_str(match self {
             SyntaxKind::Whitespace => "whitespace",
             SyntaxKind::VariableIdent => "identifier",
+            SyntaxKind::PathIdent => "name",
             SyntaxKind::Number => "number",
             SyntaxKind::FunctionKeyword => "‘function’",
             SyntaxKind::Plus => "‘+’",
@@ -129,7 +134,7 @@ impl fmt::Display for SyntaxKind {
             SyntaxKind::Question => "‘?’",
             SyntaxKind::Colon => "‘:’",
             SyntaxKind::SemiColon => "‘;’",
-            _ => unreachable!(format!("unreachable: found {:?}", &self)),
+            _ => unreachable!("unreachable: found {:?}", self),
         })
     }
 }